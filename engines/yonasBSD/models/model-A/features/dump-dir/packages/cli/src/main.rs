@@ -9,72 +9,534 @@
  *     3. Structured JSON logging via ReportExt / ApiError
  */
 
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    time::Duration,
+};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use errors_lib::{LibReport, ReportExt, rootcause::Report};
-use lib::{DumpError, config, filter, printer, walker};
+use lib::{
+    DumpError, config,
+    enums::{Order, OutputFormat, SummaryFormat, Symlinks},
+    filter, git, groups, manifest, presets, printer, walker,
+};
 use miette::Result as MietteResult;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
 
 /// Dump directory file contents to terminal, respecting .gitignore
 #[derive(Parser, Debug)]
 #[command(
     name = "dump-dir",
     version,
-    about = "Prints file contents of a directory, git-aware and filter-configurable"
+    about = "Prints file contents of a directory, git-aware and filter-configurable",
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
+    /// Optional subcommand; without one, the given paths are dumped.
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Paths to dump (files or directories). Defaults to current directory.
     #[arg(value_name = "PATH")]
     paths: Vec<PathBuf>,
 
+    /// Read the paths to dump from stdin, one per line, instead of PATH args
+    #[arg(long, conflicts_with_all = ["paths", "stdin0"])]
+    stdin: bool,
+
+    /// Like --stdin, but paths are NUL-separated (as from `fd -0` or `find -print0`)
+    #[arg(long, conflicts_with = "paths")]
+    stdin0: bool,
+
     /// Override config: skip extensions (comma-separated, e.g. "snap,lock")
     #[arg(long, value_delimiter = ',', value_name = "EXT")]
     skip_extensions: Option<Vec<String>>,
 
+    /// Add to the configured skip extensions instead of replacing them (comma-separated)
+    #[arg(long, value_delimiter = ',', value_name = "EXT")]
+    skip_extensions_add: Vec<String>,
+
     /// Override config: skip filename patterns (comma-separated regex)
     #[arg(long, value_delimiter = ',', value_name = "PATTERN")]
     skip_patterns: Option<Vec<String>>,
 
+    /// Add to the configured skip patterns instead of replacing them (comma-separated regex)
+    #[arg(long, value_delimiter = ',', value_name = "PATTERN")]
+    skip_patterns_add: Vec<String>,
+
+    /// Add GLOB to the configured skip globs (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    skip_globs_add: Vec<String>,
+
+    /// Override config: skip files whose content matches PATTERN (repeatable regex)
+    #[arg(long = "skip-content", value_name = "PATTERN")]
+    skip_content_patterns: Option<Vec<String>>,
+
+    /// Append skip globs read from FILE (one per line, `#` comments allowed)
+    #[arg(long, value_name = "FILE")]
+    exclude_from: Vec<PathBuf>,
+
+    /// Only dump files with these extensions (comma-separated, e.g. "rs,toml"); skip rules still
+    /// apply
+    #[arg(long, value_delimiter = ',', value_name = "EXT")]
+    include_extensions: Option<Vec<String>>,
+
+    /// Only dump files matching GLOB (repeatable); skip rules still apply
+    #[arg(long, value_name = "GLOB")]
+    include_globs: Option<Vec<String>>,
+
+    /// Always dump paths (relative to each root) or files matching GLOB, whatever
+    /// the other filters say (repeatable); ignore files still apply
+    #[arg(long, value_name = "PATH|GLOB")]
+    always_include: Option<Vec<String>>,
+
+    /// Add built-in filter presets (comma-separated, e.g. "rust,node"), in place
+    /// of the configured `presets`
+    #[arg(long, value_delimiter = ',', value_name = "NAME")]
+    preset: Option<Vec<String>>,
+
+    /// Print each preset --preset accepts, with what it adds, and exit
+    #[arg(long, exclusive = true)]
+    list_presets: bool,
+
+    /// Add built-in groups of skip rules (comma-separated, e.g. "lockfiles,images"),
+    /// in place of the configured `skip_groups`
+    #[arg(long, value_delimiter = ',', value_name = "NAME")]
+    skip_groups: Option<Vec<String>>,
+
+    /// Print each group --skip-groups accepts, with what it skips, and exit
+    #[arg(long, exclusive = true)]
+    list_groups: bool,
+
+    /// Only dump files of these languages (comma-separated, e.g. "rust,toml,make"); skip rules
+    /// still apply, though skip_filenames never drops a file the language names (Makefile)
+    #[arg(long = "lang", value_delimiter = ',', value_name = "LANG")]
+    include_languages: Option<Vec<String>>,
+
+    /// Apply the filter settings of each dump.toml below a root to its subtree
+    #[arg(long)]
+    nested_configs: bool,
+
+    /// Symbolic links: skip them, follow them (with cycle detection), or list each as `-> target`
+    #[arg(long, value_enum, value_name = "MODE")]
+    symlinks: Option<Symlinks>,
+
+    /// Walk into symlinked directories and print linked files; same as --symlinks follow
+    #[arg(long, conflicts_with = "symlinks")]
+    follow_links: bool,
+
+    /// Walk each root on N threads (0 = one per CPU); the file order is unchanged
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+
+    /// Descend at most N levels below each path (1 = its direct children only)
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Skip files last modified before WHEN: a duration (7d, 36h) or an RFC 3339 date
+    #[arg(long, value_name = "WHEN")]
+    changed_since: Option<String>,
+
+    /// Skip files last modified at or after WHEN; same formats as --changed-since
+    #[arg(long, value_name = "WHEN")]
+    changed_before: Option<String>,
+
+    /// Skip files larger than SIZE, e.g. 500k, 2MiB or 1GB
+    #[arg(long, value_name = "SIZE")]
+    max_size: Option<String>,
+
+    /// Match names, regexes and globs case-sensitively (`[filter] case_insensitive = false`)
+    #[arg(long)]
+    case_sensitive: bool,
+
+    /// Sniff the first N bytes of each file for binary content ([binary] sniff_bytes)
+    #[arg(long, value_name = "N")]
+    binary_sample: Option<usize>,
+
+    /// Skip files with more than N lines (0 disables the check)
+    #[arg(long, value_name = "N", visible_alias = "max-lines")]
+    max_file_lines: Option<usize>,
+
+    /// Walk files that .gitignore and the git exclude files leave out; filters
+    /// still apply
+    #[arg(long)]
+    no_gitignore: bool,
+
     /// Include files that would normally be skipped (overrides all filters)
     #[arg(long)]
     no_filter: bool,
 
+    /// File order within each path: depth-first by name, shallow files first, smallest first,
+    /// newest first, or by extension
+    #[arg(long, visible_alias = "sort", value_enum, value_name = "ORDER")]
+    order: Option<Order>,
+
+    /// Print the files within each path in the reverse of --order
+    #[arg(long)]
+    reverse: bool,
+
+    /// Output format: file contents (`text`), headings and code fences (`markdown`),
+    /// one JSON object per file per line (`ndjson`), `sha256  size  path` lines
+    /// (`manifest`), or the bare contents back to back (`concat`)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+
+    /// Print the files' contents back to back with no headers or separators;
+    /// same as --format concat
+    #[arg(long, conflicts_with = "format")]
+    concat: bool,
+
+    /// Start with a table of contents: numbered in text; linked in markdown
+    #[arg(long)]
+    toc: bool,
+
+    /// Re-hash the tree and compare against MANIFEST; exits non-zero on differences
+    #[arg(long, value_name = "MANIFEST", conflicts_with_all = ["format", "list"])]
+    verify: Option<PathBuf>,
+
+    /// Copy the rendered dump (without colors) to the clipboard instead of printing it
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with_all = ["list", "verify", "watch"])]
+    clipboard: bool,
+
+    /// List the files that would be dumped, one per line, without reading them
+    /// (except for binary sniffing when skip_binary is on). With --summary, the
+    /// file count goes to stderr.
+    #[arg(long, visible_alias = "dry-run")]
+    list: bool,
+
+    /// Instead of dumping, print for each PATH whether it would be included or
+    /// which rule (and matching value) leaves it out
+    #[arg(long, visible_alias = "why", conflicts_with_all = ["list", "verify", "watch"])]
+    explain: bool,
+
+    /// With --list, terminate each path with NUL instead of a newline (for `xargs -0`)
+    #[arg(short = '0', long, requires = "list")]
+    print0: bool,
+
+    /// After the first dump, keep watching the paths and re-dump on every change
+    #[arg(long)]
+    watch: bool,
+
+    /// Show a spinner on stderr counting files found, then printed (stderr must be a terminal)
+    #[cfg(feature = "progress")]
+    #[arg(long)]
+    progress: bool,
+
     /// Show a summary line count at the end
     #[arg(long)]
     summary: bool,
 
-    /// Path to a local config file (default: ./dump.toml)
+    /// After the summary, list every file and directory the filters and ignore files excluded, by
+    /// reason
+    #[arg(long)]
+    show_skipped: bool,
+
+    /// After the dump, list on stderr the directories every file of which was filtered out
+    #[arg(long)]
+    report_empty_dirs: bool,
+
+    /// Estimate LLM tokens in the output and report them in the summary
+    #[arg(long)]
+    tokens: bool,
+
+    /// Summary format; `json` prints a single-line JSON object (implies --summary)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    summary_format: Option<SummaryFormat>,
+
+    /// Print the summary to stderr instead of stdout (implies --summary)
+    #[arg(long)]
+    summary_stderr: bool,
+
+    /// Read up to N files concurrently (plain output only; 0 = one per CPU)
+    #[arg(long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Truncate lines longer than N characters (plain output only)
+    #[arg(long, value_name = "N", conflicts_with = "wrap")]
+    max_line_length: Option<usize>,
+
+    /// Hard-wrap lines at N characters (plain output only)
+    #[arg(long, value_name = "N")]
+    wrap: Option<usize>,
+
+    /// Expand leading tabs to spaces (tab width N, default 4)
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "4"
+    )]
+    expand_tabs: Option<usize>,
+
+    /// Like --expand-tabs, but also expand tabs in the middle of lines
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "4",
+        conflicts_with = "expand_tabs"
+    )]
+    expand_all_tabs: Option<usize>,
+
+    /// Expand every tab to the next multiple of N columns; same as --expand-all-tabs=N
+    #[arg(long, value_name = "N", conflicts_with_all = ["expand_tabs", "expand_all_tabs"])]
+    tab_width: Option<usize>,
+
+    /// Print files with identical content only once; later copies get a header and a note
+    #[arg(long, visible_alias = "dedupe")]
+    dedupe_content: bool,
+
+    /// Stop after printing N files, in --order order, across all paths; a file
+    /// that fails to read doesn't count
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Stop printing further files once N lines have been printed in total
+    #[arg(long, value_name = "N")]
+    max_total_lines: Option<usize>,
+
+    /// Stop printing further files once N bytes of content have been printed in total
+    #[arg(long, value_name = "N")]
+    max_total_bytes: Option<u64>,
+
+    /// Abort once N bytes have been printed in total; the file crossing the limit is cut off
+    #[arg(long, value_name = "N")]
+    abort_after_bytes: Option<u64>,
+
+    /// Reuse file contents cached in DIR by earlier runs, keyed by path, mtime and
+    /// size, re-reading only changed files ([printer] cache_dir)
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Pipe each file's content through the shell command CMD and print its output
+    /// instead, e.g. to redact secrets; on failure or after [printer]
+    /// filter_cmd_timeout the file is printed unchanged. A project's dump.toml
+    /// cannot set it; the global config can ([printer] filter_cmd)
+    #[arg(long, value_name = "CMD")]
+    filter_cmd: Option<String>,
+
+    /// Exit with an error if the output budget caused any file to be omitted
+    #[arg(long)]
+    fail_on_budget: bool,
+
+    /// Show paths under each root as NAME/<relative path>, hiding the real directory
+    #[arg(long, value_name = "NAME")]
+    root_label: Option<String>,
+
+    /// Emit FILE verbatim before the first file header
     #[arg(long, value_name = "FILE")]
-    config: Option<PathBuf>,
+    prepend: Option<PathBuf>,
+
+    /// Emit FILE verbatim after the last file, before the summary
+    #[arg(long, value_name = "FILE")]
+    append: Option<PathBuf>,
+
+    /// Start each file's content with a comment naming its path (e.g. `// src/main.rs`);
+    /// not applied to bat or --concat output, or languages without a known comment syntax
+    #[arg(long)]
+    path_comment: bool,
+
+    /// Never use bat, even if installed: plain, copy-pasteable output
+    #[arg(long)]
+    no_bat: bool,
+
+    /// Kill bat after SECS on one file and print that file plainly (0 = no limit)
+    #[arg(long, value_name = "SECS")]
+    bat_timeout: Option<u64>,
+
+    /// Syntax highlighting theme for bat output (see --list-themes)
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// Print the theme names --theme accepts and exit
+    #[arg(long, exclusive = true)]
+    list_themes: bool,
+
+    /// Show the last commit (hash, date, subject) touching each file in its header
+    #[arg(long)]
+    git_meta: bool,
+
+    /// Show each file's size, modification time and (on Unix) mode bits in its header
+    #[arg(long)]
+    metadata: bool,
+
+    /// Dump only files that differ from a git ref (default HEAD) in the working tree
+    #[arg(
+        long,
+        value_name = "REF",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "HEAD",
+        conflicts_with = "verify"
+    )]
+    changed: Option<String>,
+
+    /// Dump only files that differ between two git refs, e.g. two release tags
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["FROM", "TO"],
+        conflicts_with_all = ["changed", "verify"]
+    )]
+    changed_between: Option<Vec<String>>,
+
+    /// Dump only files tracked by git (`git ls-files`); skip rules still apply
+    #[arg(long, visible_alias = "tracked-only", conflicts_with = "git_untracked")]
+    git_tracked: bool,
+
+    /// Dump only files git neither tracks nor ignores (new work not yet added)
+    #[arg(long)]
+    git_untracked: bool,
+
+    /// Local config file (default: ./dump.toml); repeat to layer, later files win
+    #[arg(long, value_name = "FILE")]
+    config: Vec<PathBuf>,
+
+    /// Suppress warnings about unreadable files on stderr (errors are still reported)
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+    /// Print a JSON Schema for dump.toml, for editor completion and validation
+    Schema,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Load the config and compile its filters without dumping anything
+    Check {
+        /// Local config file (default: ./dump.toml); repeat to layer, later files win
+        #[arg(long, value_name = "FILE")]
+        config: Vec<PathBuf>,
+    },
 }
 
+/// Quiet period after the last file event before `--watch` re-dumps.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
 fn run(cli: Cli) -> Result<(), DumpError> {
-    // Load layered config: global → local → CLI overrides
-    let mut cfg = config::load(cli.config.as_deref())?;
+    match cli.command {
+        Some(Command::Config {
+            action: ConfigCommand::Check {
+                config,
+            },
+        }) => check_config(&config),
+        Some(Command::Schema) => {
+            println!("{}", config::json_schema());
+            Ok(())
+        },
+        None if cli.list_themes => list_themes(),
+        None if cli.list_presets => list_presets(),
+        None if cli.list_groups => list_groups(),
+        None => dump(cli),
+    }
+}
 
-    // Apply CLI overrides on top of config
-    if cli.no_filter {
-        cfg.skip_extensions.clear();
-        cfg.skip_patterns.clear();
-        cfg.skip_filenames.clear();
-        cfg.skip_path_components.clear();
-        cfg.skip_globs.clear();
-        cfg.skip_binary = false;
-        cfg.skip_hidden = false;
+/// Print bat's theme names, one per line.
+fn list_themes() -> Result<(), DumpError> {
+    let Some(themes) = printer::bat_themes() else {
+        lib::warning!("bat is not installed; themes only apply to bat output");
+        return Ok(());
+    };
+    let mut out = io::stdout().lock();
+    for theme in themes {
+        writeln!(out, "{theme}").map_err(|source| DumpError::Write {
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Print each preset: its name and description, then the config lists it adds
+/// to, as TOML.
+fn list_presets() -> Result<(), DumpError> {
+    let mut out = io::stdout().lock();
+    for (i, preset) in presets::PRESETS.iter().enumerate() {
+        let mut text = format!(
+            "{}{}: {}\n",
+            if i > 0 { "\n" } else { "" },
+            preset.name,
+            preset.description
+        );
+        for (key, values) in preset.entries() {
+            text.push_str(&format!("  {key} = {values:?}\n"));
+        }
+        write!(out, "{text}").map_err(|source| DumpError::Write {
+            source,
+        })?;
+    }
+    Ok(())
+}
+
+/// Print each skip group: its name and description, then the skip lists it adds
+/// to, as TOML.
+fn list_groups() -> Result<(), DumpError> {
+    let mut out = io::stdout().lock();
+    for (i, group) in groups::GROUPS.iter().enumerate() {
+        let mut text = format!(
+            "{}{}: {}\n",
+            if i > 0 { "\n" } else { "" },
+            group.name,
+            group.description
+        );
+        for (key, values) in group.entries() {
+            text.push_str(&format!("  {key} = {values:?}\n"));
+        }
+        write!(out, "{text}").map_err(|source| DumpError::Write {
+            source,
+        })?;
     }
-    if let Some(exts) = cli.skip_extensions {
-        cfg.skip_extensions = exts;
+    Ok(())
+}
+
+/// Load the layered config and build the `Filter`, surfacing invalid regexes and
+/// globs without walking anything.
+fn check_config(paths: &[PathBuf]) -> Result<(), DumpError> {
+    let cfg = config::load(paths)?;
+    filter::Filter::new(&cfg)?;
+    if let Some(theme) = &cfg.printer.theme {
+        printer::check_theme(theme)?;
     }
-    if let Some(patterns) = cli.skip_patterns {
-        cfg.skip_patterns = patterns;
+    println!("OK");
+    Ok(())
+}
+
+fn dump(cli: Cli) -> Result<(), DumpError> {
+    lib::utils::set_quiet(cli.quiet);
+
+    // A bad theme is bat's to report; only `config check` looks it up.
+    let cfg = build_config(&cli)?;
+    if let Some(dir) = &cfg.printer.cache_dir {
+        std::fs::create_dir_all(dir).map_err(|source| DumpError::Io {
+            path: dir.display().to_string(),
+            source,
+        })?;
+        if let Err(e) = lib::cache::ContentCache::new(dir).prune(lib::cache::MAX_CACHE_BYTES) {
+            lib::warning!("cannot prune cache '{}': {e}", dir.display());
+        }
     }
 
     // Resolve paths to walk
-    let paths: Vec<PathBuf> = if cli.paths.is_empty() {
+    let paths: Vec<PathBuf> = if cli.stdin || cli.stdin0 {
+        read_stdin_paths(if cli.stdin0 { b'\0' } else { b'\n' })?
+    } else if cli.paths.is_empty() {
         vec![PathBuf::from(".")]
     } else {
-        cli.paths
+        cli.paths.clone()
     };
 
     // Validate all paths exist upfront — typed PathNotFound error
@@ -87,22 +549,778 @@ fn run(cli: Cli) -> Result<(), DumpError> {
     }
 
     let filter = Arc::new(filter::Filter::new(&cfg)?);
-    let mut printer = printer::Printer::new(cli.summary);
 
-    for path in &paths {
-        let files = walker::collect_files(path, Arc::clone(&filter))?;
-        for file in files {
-            printer.print_file(&file)?;
+    if cli.explain {
+        return explain(&paths, &filter);
+    }
+
+    if cli.watch {
+        watch(&paths, &filter, || dump_once(&cli, &cfg, &paths, &filter))
+    } else {
+        dump_once(&cli, &cfg, &paths, &filter)
+    }
+}
+
+/// The paths listed on stdin (`--stdin`, `--stdin0`), split on `separator`.
+/// Empty entries are dropped, as is the `\r` of a CRLF line ending.
+fn read_stdin_paths(separator: u8) -> Result<Vec<PathBuf>, DumpError> {
+    let mut input = Vec::new();
+    io::Read::read_to_end(&mut io::stdin().lock(), &mut input).map_err(|source| DumpError::Io {
+        path: "<stdin>".into(),
+        source,
+    })?;
+    Ok(input
+        .split(|&b| b == separator)
+        .map(|entry| match separator {
+            b'\n' => entry.strip_suffix(b"\r").unwrap_or(entry),
+            _ => entry,
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(path_from_bytes)
+        .collect())
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Load layered config (global → local → `--config` files) and apply CLI overrides.
+fn build_config(cli: &Cli) -> Result<config::AppConfig, DumpError> {
+    let mut cfg = config::load_with(&cli.config, cli.preset.clone(), None)?;
+
+    // Apply CLI overrides on top of config
+    if cli.no_filter {
+        cfg.skip_extensions.clear();
+        cfg.skip_patterns.clear();
+        cfg.skip_content_patterns.clear();
+        cfg.skip_filenames.clear();
+        cfg.skip_path_components.clear();
+        cfg.skip_globs.clear();
+        cfg.skip_binary = false;
+        cfg.skip_hidden = false;
+        cfg.skip_generated = false;
+        cfg.skip_minified = false;
+        cfg.respect_dumpignore = false;
+        cfg.custom_ignore_files.clear();
+        cfg.skip_larger_than = None;
+        cfg.max_lines_per_file = 0;
+        cfg.changed_since = None;
+        cfg.changed_before = None;
+        cfg.nested_configs = false;
+        cfg.include_extensions.clear();
+        cfg.include_filenames.clear();
+        cfg.include_globs.clear();
+        cfg.include_languages.clear();
+        cfg.skip_languages.clear();
+        cfg.skip_groups.clear();
+    }
+    if cli.no_gitignore {
+        cfg.respect_gitignore = false;
+    }
+    if cli.nested_configs {
+        cfg.nested_configs = true;
+    }
+    if cli.case_sensitive {
+        cfg.filter.case_insensitive = false;
+    }
+    if let Some(symlinks) = cli.symlinks {
+        cfg.symlinks = symlinks;
+    }
+    if cli.follow_links {
+        cfg.symlinks = Symlinks::Follow;
+    }
+    if let Some(threads) = cli.threads {
+        cfg.threads = threads;
+    }
+    if let Some(depth) = cli.max_depth {
+        cfg.max_depth = Some(depth);
+    }
+    if let Some(when) = &cli.changed_since {
+        cfg.changed_since = Some(when.clone());
+    }
+    if let Some(when) = &cli.changed_before {
+        cfg.changed_before = Some(when.clone());
+    }
+    if let Some(size) = &cli.max_size {
+        cfg.skip_larger_than = Some(size.clone());
+    }
+    if let Some(bytes) = cli.binary_sample {
+        cfg.binary.sniff_bytes = bytes;
+    }
+    if let Some(max) = cli.max_file_lines {
+        cfg.max_lines_per_file = max;
+    }
+    // The last list layer: replacements, then additions (see `config::load`).
+    cfg.apply_list_overrides(config::ListOverrides {
+        skip_extensions: cli.skip_extensions.clone(),
+        skip_extensions_add: cli.skip_extensions_add.clone(),
+        skip_patterns: cli.skip_patterns.clone(),
+        skip_patterns_add: cli.skip_patterns_add.clone(),
+        skip_globs_add: cli.skip_globs_add.clone(),
+        ..config::ListOverrides::default()
+    });
+    if let Some(patterns) = &cli.skip_content_patterns {
+        cfg.skip_content_patterns = patterns.clone();
+    }
+    if let Some(exts) = &cli.include_extensions {
+        cfg.include_extensions = exts.clone();
+    }
+    if let Some(globs) = &cli.include_globs {
+        cfg.include_globs = globs.clone();
+    }
+    if let Some(langs) = &cli.include_languages {
+        cfg.include_languages = langs.clone();
+    }
+    if let Some(names) = &cli.skip_groups {
+        cfg.skip_groups = names.clone();
+    }
+    if let Some(patterns) = &cli.always_include {
+        cfg.always_include = patterns.clone();
+    }
+    for file in &cli.exclude_from {
+        cfg.skip_globs.extend(config::read_exclude_file(file)?);
+    }
+    if let Some(order) = cli.order {
+        cfg.order = order;
+    }
+    if cli.reverse {
+        cfg.reverse_order = true;
+    }
+    if let Some(format) = cli.format {
+        cfg.printer.format = format;
+    }
+    if cli.concat {
+        cfg.printer.format = OutputFormat::Concat;
+    }
+    if cli.toc {
+        cfg.printer.toc = true;
+    }
+    if let Some(jobs) = cli.jobs {
+        cfg.printer.jobs = jobs;
+    }
+    if let Some(max) = cli.max_line_length {
+        cfg.printer.max_line_length = Some(max);
+        cfg.printer.wrap = None;
+    }
+    if let Some(width) = cli.wrap {
+        cfg.printer.wrap = Some(width);
+        cfg.printer.max_line_length = None;
+    }
+    if let Some(width) = cli.expand_tabs {
+        cfg.printer.expand_tabs = Some(width);
+    }
+    if let Some(width) = cli.expand_all_tabs.or(cli.tab_width) {
+        cfg.printer.expand_tabs = Some(width);
+        cfg.printer.expand_all_tabs = true;
+    }
+    if cli.dedupe_content {
+        cfg.printer.dedupe_content = true;
+    }
+    if cli.path_comment {
+        cfg.printer.path_comment = true;
+    }
+    if let Some(label) = &cli.root_label {
+        cfg.printer.root_label = Some(label.clone());
+    }
+    if cli.no_bat {
+        cfg.printer.use_bat = false;
+    }
+    if let Some(secs) = cli.bat_timeout {
+        cfg.printer.bat_timeout = secs;
+    }
+    if let Some(theme) = &cli.theme {
+        cfg.printer.theme = Some(theme.clone());
+    }
+    if cli.git_meta {
+        cfg.printer.git_meta = true;
+    }
+    if cli.metadata {
+        cfg.printer.metadata = true;
+    }
+    if let Some(file) = &cli.prepend {
+        cfg.prompt_prefix_file = Some(file.clone());
+    }
+    if let Some(file) = &cli.append {
+        cfg.prompt_suffix_file = Some(file.clone());
+    }
+    if let Some(max) = cli.max_total_lines {
+        cfg.printer.max_total_lines = Some(max);
+    }
+    if let Some(max) = cli.max_total_bytes {
+        cfg.printer.max_total_bytes = Some(max);
+    }
+    if let Some(max) = cli.abort_after_bytes {
+        cfg.printer.abort_after_bytes = Some(max);
+    }
+    if let Some(dir) = &cli.cache_dir {
+        cfg.printer.cache_dir = Some(dir.clone());
+    }
+    if let Some(command) = &cli.filter_cmd {
+        cfg.printer.filter_cmd = Some(command.clone());
+    }
+    if cli.tokens {
+        cfg.printer.tokens = true;
+    }
+
+    Ok(cfg)
+}
+
+/// One full pass over `paths`: list or print every file, then the summary.
+fn dump_once(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+) -> Result<(), DumpError> {
+    if let Some(expected) = &cli.verify {
+        return verify_manifest(cli, expected, cfg, paths, filter);
+    }
+    if cfg.printer.format == OutputFormat::Manifest {
+        return write_manifest(cli, cfg, paths, filter);
+    }
+
+    if cli.list {
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        for path in paths {
+            let mut keys = walker::SortKeys::new();
+            let mut found = match archive_entry_paths(cli, cfg, path, filter, &mut seen, &mut keys)?
+            {
+                Some(entries) => entries,
+                None => {
+                    let (mut found, found_keys) =
+                        walker::collect_files_with_keys(path, Arc::clone(filter))?;
+                    retain_git(cli, path, &mut found)?;
+                    keys = found_keys;
+                    found
+                },
+            };
+            walker::sort_files(&mut found, cfg.order, cfg.reverse_order, &keys);
+            walker::dedupe_files(&mut found, &mut seen, cfg.symlinks);
+            files.extend(found);
+        }
+        if let Some(limit) = cli.limit {
+            files.truncate(limit);
+        }
+        list_files(&mut io::stdout().lock(), &files, cli.print0).map_err(|source| {
+            DumpError::Write {
+                source,
+            }
+        })?;
+        // Keep stdout a clean path list; the count is for humans and scripts alike.
+        if cli.summary {
+            eprintln!("{}", files.len());
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "clipboard")]
+    if cli.clipboard {
+        return dump_to_clipboard(cli, cfg, paths, filter);
+    }
+
+    let mut printer = printer::Printer::new(&cfg.printer);
+    print_dump(cli, cfg, paths, filter, &mut printer)
+}
+
+/// With the `archives` feature, the entry paths of an archive given as `path`,
+/// by name and size alone (see [`lib::archive::entry_names`]); none if
+/// [`keeps_archive`] drops the archive itself. `None` for anything else, which
+/// is walked instead.
+fn archive_entry_paths(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    path: &Path,
+    filter: &filter::Filter,
+    seen: &mut HashSet<PathBuf>,
+    keys: &mut walker::SortKeys,
+) -> Result<Option<Vec<PathBuf>>, DumpError> {
+    #[cfg(feature = "archives")]
+    if lib::archive::is_archive(path) && path.is_file() {
+        if !keeps_archive(cli, cfg, path, seen)? {
+            return Ok(Some(Vec::new()));
         }
+        let entries = lib::archive::entry_names(path, filter)?;
+        return Ok(Some(
+            entries
+                .into_iter()
+                .map(|entry| {
+                    keys.insert(entry.path.clone(), archive_sort_key(entry.size));
+                    entry.path
+                })
+                .collect(),
+        ));
     }
+    let _ = (cli, cfg, path, filter, seen, keys);
+    Ok(None)
+}
+
+/// Whether to dump the archive given as `path`. Its entries are not in git, so
+/// they go with the archive: the git filters (`--changed`, `--git-tracked`,
+/// ...) keep or drop it whole. So does [`walker::dedupe_files`], with `seen`,
+/// if an earlier path already reached the same archive.
+#[cfg(feature = "archives")]
+fn keeps_archive(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    path: &Path,
+    seen: &mut HashSet<PathBuf>,
+) -> Result<bool, DumpError> {
+    let mut archive = vec![path.to_path_buf()];
+    retain_git(cli, path, &mut archive)?;
+    walker::dedupe_files(&mut archive, seen, cfg.symlinks);
+    Ok(!archive.is_empty())
+}
 
-    if cli.summary {
-        printer.print_summary();
+/// An archive entry's sort key: its size, with no modification time.
+#[cfg(feature = "archives")]
+fn archive_sort_key(size: u64) -> walker::SortKey {
+    walker::SortKey {
+        size,
+        modified: None,
     }
+}
 
+/// Print `<path>: included` or `<path>: skipped by <rule>` for each path.
+fn explain(paths: &[PathBuf], filter: &filter::Filter) -> Result<(), DumpError> {
+    let mut out = io::stdout().lock();
+    for path in paths {
+        let decision = walker::explain(path, filter);
+        writeln!(out, "{}: {decision}", path.display()).map_err(|source| DumpError::Write {
+            source,
+        })?;
+    }
     Ok(())
 }
 
+/// Render the dump to the clipboard instead of stdout, without colors.
+#[cfg(feature = "clipboard")]
+fn dump_to_clipboard(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+) -> Result<(), DumpError> {
+    lib::utils::set_color(false);
+    let mut printer = printer::Printer::with_writer(&cfg.printer, Vec::new());
+    let result = print_dump(cli, cfg, paths, filter, &mut printer);
+    // A blown budget still produced a (shortened) dump worth copying.
+    if matches!(
+        result,
+        Ok(()) | Err(DumpError::BudgetExceeded { .. } | DumpError::TotalSizeExceeded { .. })
+    ) {
+        let text = String::from_utf8_lossy(&printer.into_inner()).into_owned();
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text.as_str()))
+            .map_err(|e| DumpError::Clipboard {
+                message: e.to_string(),
+            })?;
+        eprintln!("Copied {} bytes to the clipboard", text.len());
+    }
+    result
+}
+
+/// Print every file under `paths` through `printer`, framed by the prompt
+/// prefix/suffix and followed by the summary.
+fn print_dump<W: Write>(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+    printer: &mut printer::Printer<W>,
+) -> Result<(), DumpError> {
+    // Read both prompt files upfront so a missing suffix fails before any output.
+    let prefix = cfg
+        .prompt_prefix_file
+        .as_deref()
+        .map(config::read_prompt_file)
+        .transpose()?;
+    let suffix = cfg
+        .prompt_suffix_file
+        .as_deref()
+        .map(config::read_prompt_file)
+        .transpose()?;
+
+    if let Some(text) = &prefix {
+        printer.print_verbatim(text)?;
+    }
+
+    let progress = Spinner::start(cli);
+
+    // Walk every root before printing anything: the TOC lists them all.
+    let mut files = Vec::new();
+    let mut skipped = filter::FilterStats::default();
+    let mut empty_dirs = Vec::new();
+    let mut seen = HashSet::new();
+    let mut duplicate_paths = 0;
+    for path in paths {
+        let mut keys = walker::SortKeys::new();
+        // An archive's files are read into memory up front.
+        #[cfg(feature = "archives")]
+        if lib::archive::is_archive(path) && path.is_file() {
+            if !keeps_archive(cli, cfg, path, &mut seen)? {
+                continue;
+            }
+            let mut found = Vec::new();
+            for entry in lib::archive::read_entries(path, filter)? {
+                found.push(entry.path.clone());
+                keys.insert(
+                    entry.path.clone(),
+                    archive_sort_key(entry.content.len() as u64),
+                );
+                printer.add_in_memory(entry.path, entry.content);
+            }
+            walker::sort_files(&mut found, cfg.order, cfg.reverse_order, &keys);
+            files.extend(found);
+            continue;
+        }
+        let on_found = || {
+            if let Some(progress) = &progress {
+                progress.found(1);
+            }
+        };
+        // Only the serial walk can report files one at a time, or the
+        // directories it visits.
+        let mut visited = Vec::new();
+        let mut found = if cli.show_skipped || cli.report_empty_dirs {
+            let mut walk = walker::walk_report(path, Arc::clone(filter), |_| on_found())?;
+            if cli.show_skipped {
+                walker::record_ignored(path, filter, &mut walk);
+                skipped.merge(&walk.stats);
+            }
+            visited = walk.dirs;
+            keys = walk.keys;
+            walk.files
+        } else if filter.threads() == 1 && !cfg.order.needs_metadata() {
+            walker::walk_files(path, Arc::clone(filter))
+                .inspect(|_| on_found())
+                .collect::<Result<_, _>>()?
+        } else {
+            let (found, found_keys) = walker::collect_files_with_keys(path, Arc::clone(filter))?;
+            if let Some(progress) = &progress {
+                progress.found(found.len());
+            }
+            keys = found_keys;
+            found
+        };
+        retain_git(cli, path, &mut found)?;
+        if cli.report_empty_dirs {
+            empty_dirs.extend(walker::empty_dirs(path, &visited, &found));
+        }
+        walker::sort_files(&mut found, cfg.order, cfg.reverse_order, &keys);
+        duplicate_paths += walker::dedupe_files(&mut found, &mut seen, cfg.symlinks);
+        files.extend(found);
+    }
+    // Files read whole to count their lines are printed from that read.
+    for (path, content) in filter.take_counted() {
+        printer.add_in_memory(path, content);
+    }
+    // Counted as files are printed, so one that fails to read is not one of them.
+    if let Some(limit) = cli.limit {
+        printer.set_max_files(limit);
+    }
+    printer.set_roots(paths);
+    printer.set_symlinks(cfg.symlinks);
+    printer.set_duplicate_paths(duplicate_paths);
+    let (since, before) = filter.modified_window();
+    printer.set_modified_window(since, before);
+    let listed = cli
+        .limit
+        .map_or(files.len(), |limit| files.len().min(limit));
+    if cfg.printer.toc {
+        printer.print_toc(&files[..listed])?;
+    }
+    if let Some(progress) = &progress {
+        let progress = progress.clone();
+        progress.start_printing(listed);
+        printer.set_on_file(move |_| progress.printed());
+    }
+    let printed = printer.print_files(&files);
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    printed?;
+    printer.print_budget_notice()?;
+    if let Some(text) = &suffix {
+        printer.print_verbatim(text)?;
+    }
+    printer.flush()?;
+
+    let summary = printer.summary();
+    if cli.summary || cli.summary_format.is_some() || cli.summary_stderr {
+        let format = cli.summary_format.unwrap_or_default();
+        if cli.summary_stderr {
+            eprintln!("{}", summary.render(format));
+        } else {
+            printer.print_summary(format)?;
+        }
+    }
+    if cli.show_skipped {
+        if cli.summary_stderr {
+            eprintln!("{skipped}");
+        } else {
+            printer.print_skipped(&skipped)?;
+        }
+    }
+
+    if cli.report_empty_dirs {
+        eprintln!("── Empty directories: {}", empty_dirs.len());
+        for dir in &empty_dirs {
+            eprintln!("  {}/", dir.display());
+        }
+    }
+
+    if cli.fail_on_budget && summary.files_omitted > 0 {
+        return Err(DumpError::BudgetExceeded {
+            omitted: summary.files_omitted,
+        });
+    }
+
+    Ok(())
+}
+
+/// Run `rerun` once, then again whenever something under `paths` changes, until
+/// interrupted. Watching starts before the first run so no change is missed. Bursts of events (an
+/// editor's save, a `git checkout`) are coalesced: a run starts once no relevant event has arrived
+/// for `WATCH_DEBOUNCE`.
+fn watch(
+    paths: &[PathBuf],
+    filter: &filter::Filter,
+    mut rerun: impl FnMut() -> Result<(), DumpError>,
+) -> Result<(), DumpError> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| DumpError::Watch {
+        path: paths[0].display().to_string(),
+        source: io::Error::other(e),
+    })?;
+    for path in paths {
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| DumpError::Watch {
+                path: path.display().to_string(),
+                source: io::Error::other(e),
+            })?;
+    }
+
+    rerun()?;
+
+    // Changes to filtered files (e.g. `.git/index`) don't warrant a re-dump.
+    let relevant = |event: &notify::Result<Event>| match event {
+        Ok(event) => {
+            !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|p| !filter.should_skip(p))
+        },
+        Err(_) => false,
+    };
+
+    // The loop ends when the watcher's sender is dropped, which only happens
+    // if the watcher itself shuts down.
+    while let Ok(event) = rx.recv() {
+        if !relevant(&event) {
+            continue;
+        }
+        while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+
+        println!("\n── change detected, re-dumping ──\n");
+        match rerun() {
+            Err(err) if err.is_broken_pipe() => return Err(err),
+            // Files can vanish mid-run while they're being edited; keep watching.
+            Err(err) => eprintln!("Error: {err}"),
+            Ok(()) => {},
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply the git-based selections (`--changed`, `--git-tracked`,
+/// `--git-untracked`) to the `files` walked from `root`.
+fn retain_git(cli: &Cli, root: &Path, files: &mut Vec<PathBuf>) -> Result<(), DumpError> {
+    if let Some(rev) = &cli.changed {
+        git::retain_changed(root, rev, files)?;
+    }
+    if let Some([from, to]) = cli.changed_between.as_deref() {
+        git::retain_changed_between(root, from, to, files)?;
+    }
+    if cli.git_tracked {
+        git::retain_tracked(root, files)?;
+    }
+    if cli.git_untracked {
+        git::retain_untracked(root, files)?;
+    }
+    Ok(())
+}
+
+/// The manifest entries for the files a dump of `paths` would print, roots in
+/// order. Writing and verifying a manifest both select files here, so a
+/// manifest always verifies against the same selection it was written from.
+/// With several roots, each path is led by its root, so files with the same
+/// relative path under two roots do not collide.
+fn manifest_entries(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+) -> Result<Vec<manifest::ManifestEntry>, DumpError> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for path in paths {
+        let (mut files, keys) = walker::collect_files_with_keys(path, Arc::clone(filter))?;
+        retain_git(cli, path, &mut files)?;
+        walker::sort_files(&mut files, cfg.order, cfg.reverse_order, &keys);
+        walker::dedupe_files(&mut files, &mut seen, cfg.symlinks);
+        entries.extend(manifest::build(
+            path,
+            &files,
+            cfg.printer.jobs,
+            paths.len() > 1,
+        )?);
+    }
+    Ok(entries)
+}
+
+/// Print a manifest line per file, roots in order. Content is never printed.
+fn write_manifest(
+    cli: &Cli,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+) -> Result<(), DumpError> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for entry in manifest_entries(cli, cfg, paths, filter)? {
+        writeln!(out, "{entry}").map_err(|source| DumpError::Write {
+            source,
+        })?;
+    }
+    out.flush().map_err(|source| DumpError::Write {
+        source,
+    })
+}
+
+/// Re-walk and re-hash `paths`, then report differences from the manifest at
+/// `expected`. Any difference is a `ManifestMismatch` error.
+fn verify_manifest(
+    cli: &Cli,
+    expected: &Path,
+    cfg: &config::AppConfig,
+    paths: &[PathBuf],
+    filter: &Arc<filter::Filter>,
+) -> Result<(), DumpError> {
+    let expected = manifest::read(expected)?;
+    let actual = manifest_entries(cli, cfg, paths, filter)?;
+
+    let diff = manifest::diff(&expected, &actual);
+    if diff.is_empty() {
+        println!("OK: {} files match", actual.len());
+        return Ok(());
+    }
+    for (label, paths) in [
+        ("added", &diff.added),
+        ("removed", &diff.removed),
+        ("changed", &diff.changed),
+    ] {
+        for path in paths {
+            println!("{label}: {path}");
+        }
+    }
+    Err(DumpError::ManifestMismatch {
+        added: diff.added.len(),
+        removed: diff.removed.len(),
+        changed: diff.changed.len(),
+    })
+}
+
+/// The `--progress` display on stderr: a spinner counting files found while
+/// walking, then a bar of files printed out of the total, cleared by
+/// [`Spinner::finish`]. indicatif draws nothing unless stderr is a terminal.
+/// While printing to a terminal stdout it is hidden, so it never lands inside
+/// the dump.
+#[cfg(feature = "progress")]
+#[derive(Clone)]
+struct Spinner(indicatif::ProgressBar);
+
+#[cfg(feature = "progress")]
+impl Spinner {
+    /// A spinner if `--progress` is given.
+    fn start(cli: &Cli) -> Option<Self> {
+        cli.progress.then(|| {
+            let bar = indicatif::ProgressBar::with_draw_target(
+                None,
+                indicatif::ProgressDrawTarget::stderr(),
+            );
+            bar.set_style(Self::style("{spinner} walking: {pos} files found"));
+            Self(bar)
+        })
+    }
+
+    fn style(template: &str) -> indicatif::ProgressStyle {
+        indicatif::ProgressStyle::with_template(template).expect("progress template is valid")
+    }
+
+    fn found(&self, n: usize) {
+        self.0.inc(n as u64);
+    }
+
+    fn start_printing(&self, total: usize) {
+        if std::io::IsTerminal::is_terminal(&io::stdout()) {
+            self.0
+                .set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        self.0.set_style(Self::style(
+            "{spinner} printing: [{bar:30}] {pos}/{len} files",
+        ));
+        self.0.set_length(total as u64);
+        self.0.set_position(0);
+    }
+
+    fn printed(&self) {
+        self.0.inc(1);
+    }
+
+    fn finish(&self) {
+        self.0.finish_and_clear();
+    }
+}
+
+/// Without the `progress` feature there is no `--progress`, so no spinner is
+/// ever started.
+#[cfg(not(feature = "progress"))]
+#[derive(Clone)]
+struct Spinner;
+
+#[cfg(not(feature = "progress"))]
+impl Spinner {
+    fn start(_cli: &Cli) -> Option<Self> {
+        None
+    }
+
+    fn found(&self, _n: usize) {}
+
+    fn start_printing(&self, _total: usize) {}
+
+    fn printed(&self) {}
+
+    fn finish(&self) {}
+}
+
+/// Write `files` to `out`, each terminated by a newline, or by NUL with `print0`.
+fn list_files(out: &mut impl Write, files: &[PathBuf], print0: bool) -> io::Result<()> {
+    let terminator: &[u8] = if print0 { b"\0" } else { b"\n" };
+    for file in files {
+        out.write_all(file.as_os_str().as_encoded_bytes())?;
+        out.write_all(terminator)?;
+    }
+    out.flush()
+}
+
 fn main() -> MietteResult<()> {
     // Fancy panic reports for unhandled crashes
     color_eyre::install().expect("Failed to install color-eyre");
@@ -124,6 +1342,8 @@ fn main() -> MietteResult<()> {
     // Run the application, wrapping DumpError into LibReport at the boundary.
     match run(cli) {
         Ok(()) => Ok(()),
+        // The reader closed the pipe (`dump-dir | head`): stop quietly, like cat.
+        Err(err) if err.is_broken_pipe() => Ok(()),
         Err(err) => {
             // Typed introspection — react to specific variants before rendering
             match &err {
@@ -135,7 +1355,7 @@ fn main() -> MietteResult<()> {
                 DumpError::ConfigNotFound {
                     path,
                 } => {
-                    eprintln!("Hint: check --config argument, '{}' not found.", path);
+                    eprintln!("Hint: check --config arguments, '{}' not found.", path);
                 },
                 DumpError::InvalidRegex {
                     pattern, ..
@@ -150,10 +1370,17 @@ fn main() -> MietteResult<()> {
                 _ => {},
             }
 
+            // The wrapper renders the message, not the variant's diagnostic code,
+            // so the code is printed alongside the ID for scripts to match on.
+            let code = miette::Diagnostic::code(&err).map(|code| code.to_string());
+
             // Wrap into LibReport for miette rendering + structured logging
             let report = LibReport(Report::new(err));
             let api_err = report.to_api_error();
-            eprintln!("\n[Diagnostic ID: {}]", api_err.correlation_id);
+            match code {
+                Some(code) => eprintln!("\n[Diagnostic ID: {}] {code}", api_err.correlation_id),
+                None => eprintln!("\n[Diagnostic ID: {}]", api_err.correlation_id),
+            }
 
             // Return as miette::Report for beautiful terminal output
             Err(miette::Report::new(report))
@@ -1,6 +1,6 @@
 /// End-to-end tests: spawn the real `dump-dir` binary and assert on stdout/stderr.
 /// These test the full user-facing behaviour.
-use std::fs;
+use std::{fs, path::Path};
 
 use assert_cmd::Command;
 use predicates::prelude::*;
@@ -33,6 +33,36 @@ skip_hidden = false
 "#
 }
 
+/// No path filters, but binary detection on.
+fn binary_only_toml() -> &'static str {
+    r#"
+skip_extensions = []
+skip_patterns = []
+skip_filenames = []
+skip_path_components = []
+skip_globs = []
+skip_binary = true
+skip_hidden = false
+"#
+}
+
+fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    let mut bytes = if big_endian {
+        vec![0xFE, 0xFF]
+    } else {
+        vec![0xFF, 0xFE]
+    };
+    for unit in text.encode_utf16() {
+        let pair = if big_endian {
+            unit.to_be_bytes()
+        } else {
+            unit.to_le_bytes()
+        };
+        bytes.extend_from_slice(&pair);
+    }
+    bytes
+}
+
 // ── Basic output ───────────────────────────────────────────────────────────
 
 #[test]
@@ -95,6 +125,160 @@ fn no_filter_flag_shows_hidden_files() {
         .stdout(predicate::str::contains(".env"));
 }
 
+#[test]
+fn no_filter_flag_bypasses_dumpignore() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[(".dumpignore", "*.log\n"), ("app.log", "log line")]);
+
+    cmd()
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("log line").not());
+    cmd()
+        .arg(dir.path())
+        .arg("--no-filter")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("log line"));
+}
+
+// ── --nested-configs ───────────────────────────────────────────────────────
+
+#[test]
+fn nested_configs_warn_about_invalid_file_and_keep_going() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("ok/dump.toml", "skip_extensions = [\"log\"]\n"),
+        ("ok/a.log", "quiet log"),
+        ("bad/dump.toml", "skip_patterns = [\"(\"]\n"),
+        ("bad/b.txt", "still dumped"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--nested-configs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("still dumped"))
+        .stdout(predicate::str::contains("quiet log").not())
+        .stderr(predicate::str::contains("Warning: ignoring"))
+        .stderr(predicate::str::contains("bad/dump.toml"));
+}
+
+// ── --changed-since / --changed-before ──────────────────────────────────────
+
+/// Backdate `path` to midnight UTC on 2024-03-05.
+fn backdate(path: &Path) {
+    let when = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_709_596_800);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(when)
+        .unwrap();
+}
+
+#[test]
+fn changed_since_skips_older_files_and_notes_the_cutoff() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("old.txt", "old content"),
+        ("new.txt", "new content"),
+    ]);
+    backdate(&dir.path().join("old.txt"));
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--changed-since", "2024-06-01", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("new content"))
+        .stdout(predicate::str::contains("old content").not())
+        .stdout(predicate::str::contains(
+            "── Modified: since 2024-06-01T00:00:00Z",
+        ));
+}
+
+#[test]
+fn changed_before_selects_a_range() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("old.txt", "old content"),
+        ("new.txt", "new content"),
+    ]);
+    backdate(&dir.path().join("old.txt"));
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--changed-since", "2024-03-01", "--changed-before", "7d"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("old content"))
+        .stdout(predicate::str::contains("new content").not());
+}
+
+#[test]
+fn changed_since_rejects_bad_value_with_formats() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "a")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--changed-since", "last week"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid time 'last week'"));
+}
+
+// ── --max-size ─────────────────────────────────────────────────────────────
+
+#[test]
+fn max_size_skips_larger_files() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("small.txt", "tiny"),
+        ("big.txt", &"x".repeat(2000)),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--max-size", "1k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("small.txt"))
+        .stdout(predicate::str::contains("big.txt").not());
+}
+
+#[test]
+fn max_size_rejects_bad_value() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "a")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--max-size", "lots"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid size 'lots'"));
+}
+
 // ── --skip-extensions ─────────────────────────────────────────────────────
 
 #[test]
@@ -115,6 +299,33 @@ fn skip_extensions_flag_excludes_files() {
         .stdout(predicate::str::contains("notes.txt").not());
 }
 
+#[test]
+fn skip_extensions_add_extends_the_config_list() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("main.rs", "fn main() {}"),
+        ("notes.txt", "hello"),
+        ("doc.pdf", "pdf"),
+    ]);
+    let config = no_filter_toml().replace(
+        "skip_extensions = []",
+        "skip_extensions = []\nskip_extensions_add = [\"pdf\"]",
+    );
+    fs::write(config_dir.path().join("dump.toml"), config).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--skip-extensions-add", "txt", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("notes.txt").not())
+        .stdout(predicate::str::contains("doc.pdf").not());
+}
+
 // ── --summary ─────────────────────────────────────────────────────────────
 
 #[test]
@@ -134,6 +345,110 @@ fn summary_flag_prints_summary_line() {
         .stdout(predicate::str::contains("file"));
 }
 
+#[test]
+fn summary_format_json_prints_parseable_summary() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.txt", "line1\nline2\n"),
+        ("b.rs", "fn main() {}\n"),
+        ("Makefile", "all:\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--summary-format")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Summary:"));
+    let last = stdout.lines().last().unwrap();
+    let json: serde_json::Value = serde_json::from_str(last).unwrap();
+    assert_eq!(json["file_count"], 3);
+    assert_eq!(json["line_count"], 4);
+    assert_eq!(json["byte_count"], 30);
+    assert_eq!(json["skipped_unreadable"], 0);
+    assert!(json["elapsed_ms"].is_u64());
+    assert_eq!(json["extensions"]["txt"]["line_count"], 2);
+    assert_eq!(json["extensions"]["rs"]["file_count"], 1);
+    assert_eq!(json["extensions"]["(none)"]["byte_count"], 5);
+}
+
+#[test]
+fn summary_stderr_moves_summary_off_stdout() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "line1")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--summary-stderr")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Summary:").not())
+        .stderr(predicate::str::contains("Summary:"));
+}
+
+fn token_estimate(dir: &TempDir, config_dir: &TempDir) -> u64 {
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--tokens", "--summary-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+    json["tokens"].as_u64().unwrap()
+}
+
+#[test]
+fn tokens_flag_adds_token_line_to_summary() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "some words here\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--tokens", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"── Tokens: ~\d+ \(").unwrap());
+}
+
+#[test]
+fn token_estimate_is_proportional_to_input_size() {
+    let small = TempDir::new().unwrap();
+    let large = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let text = "pub fn add(a: u32, b: u32) -> u32 { a + b }\n";
+    make(&small, &[("a.rs", text)]);
+    make(&large, &[
+        ("a.rs", &text.repeat(10)),
+        ("b.rs", &text.repeat(10)),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let small = token_estimate(&small, &config_dir);
+    let large = token_estimate(&large, &config_dir);
+    assert!(small > 0);
+    assert!(
+        (18 * small..=22 * small).contains(&large),
+        "small={small} large={large}"
+    );
+}
+
 // ── --config ───────────────────────────────────────────────────────────────
 
 #[test]
@@ -246,3 +561,3022 @@ skip_hidden = false
         .stdout(predicate::str::contains("main.rs"))
         .stdout(predicate::str::contains("binary").not());
 }
+
+// ── --skip-content ─────────────────────────────────────────────────────────
+
+#[test]
+fn skip_content_excludes_files_by_content() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("open.rs", "fn open() {}\n"),
+        (
+            "secret.rs",
+            "// PROPRIETARY, do not share\nfn secret() {}\n",
+        ),
+        ("nda.txt", "Do Not Distribute\n"),
+    ]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--skip-content", "proprietary"])
+        .args(["--skip-content", "do not distribute"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("fn open() {}"));
+    assert!(!stdout.contains("secret"));
+    assert!(!stdout.contains("nda.txt"));
+}
+
+#[test]
+fn skip_content_reports_invalid_regex() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.rs", "x\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--skip-content", "(unclosed"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("(unclosed"));
+}
+
+// ── --dedupe-content ───────────────────────────────────────────────────────
+
+#[test]
+fn dedupe_content_prints_duplicate_as_note() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a/__init__.py", "shared = True\n"),
+        ("b/__init__.py", "shared = True\n"),
+        ("c.py", "unique = 1\n"),
+    ]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--dedupe-content")
+        .arg("--summary")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches("shared = True").count(), 1);
+    assert!(stdout.contains("b/__init__.py"));
+    assert!(stdout.contains("(identical to "));
+    assert!(stdout.contains("1 duplicate elided (1 line saved)"));
+}
+
+#[test]
+fn dedupe_is_an_alias_for_dedupe_content() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "same\n"), ("b.txt", "same\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--dedupe"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches("same").count(), 1);
+    let b = stdout.find("b.txt").unwrap();
+    assert!(stdout[b..].contains("(identical to "));
+}
+
+#[test]
+fn without_dedupe_content_duplicates_are_printed() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "same"), ("b.txt", "same")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert_eq!(stdout.matches("same").count(), 2);
+    assert!(!stdout.contains("identical to"));
+}
+
+// ── --jobs ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn parallel_reads_match_serial_output_order() {
+    let dir = TempDir::new().unwrap();
+    let files: Vec<(String, String)> = (0..40)
+        .map(|i| {
+            (
+                format!("d{}/file_{i:02}.txt", i % 3),
+                format!("body of file {i}\n").repeat(i + 1),
+            )
+        })
+        .collect();
+    let refs: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(p, c)| (p.as_str(), c.as_str()))
+        .collect();
+    make(&dir, &refs);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let run = |jobs: &str| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(dir.path().join("dump.toml"))
+            .arg("--summary")
+            .arg("--jobs")
+            .arg(jobs)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+
+    let serial = run("1");
+    let parallel = run("4");
+    assert!(!serial.is_empty());
+    assert_eq!(
+        String::from_utf8(serial).unwrap(),
+        String::from_utf8(parallel).unwrap()
+    );
+}
+
+// ── --max-line-length / --wrap ─────────────────────────────────────────────
+
+#[test]
+fn max_line_length_truncates_and_reports_in_summary() {
+    let dir = TempDir::new().unwrap();
+    let long = "x".repeat(1_050);
+    make(&dir, &[("min.js", &format!("{long}\nshort\n"))]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--max-line-length")
+        .arg("40")
+        .arg("--summary")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{}… (+1,010 chars)",
+            "x".repeat(40)
+        )))
+        .stdout(predicate::str::contains("short"))
+        .stdout(predicate::str::contains("1 line truncated"));
+}
+
+#[test]
+fn wrap_hard_wraps_long_lines() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("data.json", "abcdefghij")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--wrap")
+        .arg("4")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("abcd\nefgh\nij"));
+}
+
+#[test]
+fn max_line_length_and_wrap_conflict() {
+    cmd()
+        .arg("--max-line-length")
+        .arg("10")
+        .arg("--wrap")
+        .arg("10")
+        .assert()
+        .failure();
+}
+
+// ── --exclude-from ─────────────────────────────────────────────────────────
+
+#[test]
+fn exclude_from_file_appends_skip_globs() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap(); // keep config + excludes out of the walk
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}"),
+        ("target/debug/binary", "ELF"),
+    ]);
+    let config_path = config_dir.path().join("dump.toml");
+    fs::write(&config_path, no_filter_toml()).unwrap();
+    let excludes = config_dir.path().join("excludes.txt");
+    fs::write(&excludes, "# shared across repos\n\n**/target/**\n").unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--exclude-from")
+        .arg(&excludes)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("binary").not());
+}
+
+#[test]
+fn missing_exclude_from_file_exits_with_error() {
+    let dir = TempDir::new().unwrap();
+    cmd()
+        .arg(dir.path())
+        .arg("--exclude-from")
+        .arg("/no/such/excludes.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("does not exist"));
+}
+
+// ── --stdin / --stdin0 ─────────────────────────────────────────────────────
+
+#[test]
+fn stdin0_reads_nul_separated_paths_and_filters_them() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.txt", "alpha body\n"),
+        ("b.txt", "beta body\n"),
+        ("c.lock", "lock body\n"),
+        ("unlisted.txt", "unlisted body\n"),
+    ]);
+    let config = no_filter_toml().replace("skip_extensions = []", "skip_extensions = [\"lock\"]");
+    fs::write(config_dir.path().join("dump.toml"), config).unwrap();
+
+    cmd()
+        .current_dir(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--stdin0", "--no-bat"])
+        .write_stdin("a.txt\0b.txt\0c.lock\0")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alpha body"))
+        .stdout(predicate::str::contains("beta body"))
+        .stdout(predicate::str::contains("lock body").not())
+        .stdout(predicate::str::contains("unlisted body").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn stdin0_handles_paths_with_newlines() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("odd\nname.txt", "odd body\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .current_dir(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--stdin0", "--no-bat"])
+        .write_stdin("odd\nname.txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("odd body"));
+}
+
+#[test]
+fn stdin_reads_one_path_per_line() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "alpha body\n"), ("b.txt", "beta body\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .current_dir(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--stdin", "--list"])
+        .write_stdin("a.txt\r\n\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("a.txt\n"));
+}
+
+// ── Byte order marks ───────────────────────────────────────────────────────
+
+#[test]
+fn bom_files_are_decoded_and_bom_stripped() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("le.txt"), utf16("little endian ✓", false)).unwrap();
+    fs::write(dir.path().join("be.txt"), utf16("big endian ✓", true)).unwrap();
+    fs::write(dir.path().join("bom8.txt"), b"\xEF\xBB\xBFutf8 with bom").unwrap();
+    let config_path = config_dir.path().join("dump.toml");
+    fs::write(&config_path, binary_only_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("little endian ✓"));
+    assert!(stdout.contains("big endian ✓"));
+    assert!(stdout.contains("utf8 with bom"));
+    assert!(!stdout.contains('\u{FEFF}'));
+    assert!(!stdout.contains('\0'));
+}
+
+// ── Non-UTF-8 text ─────────────────────────────────────────────────────────
+
+#[test]
+fn bomless_utf16le_and_latin1_are_transcoded() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let bomless: Vec<u8> = "windows tool output"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    fs::write(dir.path().join("utf16.txt"), bomless).unwrap();
+    fs::write(
+        dir.path().join("latin1.txt"),
+        b"caf\xE9 cr\xE8me br\xFBl\xE9e",
+    )
+    .unwrap();
+    let config_path = config_dir.path().join("dump.toml");
+    fs::write(&config_path, binary_only_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("windows tool output"))
+        .stdout(predicate::str::contains("café crème brûlée"));
+}
+
+// ── --binary-sample ────────────────────────────────────────────────────────
+
+#[test]
+fn binary_sample_widens_the_sniffed_head() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let mut content = vec![b'x'; 10_000];
+    content.push(0);
+    fs::write(dir.path().join("late.bin"), content).unwrap();
+    let config_path = config_dir.path().join("dump.toml");
+    fs::write(&config_path, binary_only_toml()).unwrap();
+
+    let list = |sample: &str| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(&config_path)
+            .args(["--list", "--binary-sample", sample])
+            .assert()
+            .success()
+    };
+    list("8192").stdout(predicate::str::contains("late.bin"));
+    list("16384").stdout(predicate::str::contains("late.bin").not());
+}
+
+// ── MIME rules ─────────────────────────────────────────────────────────────
+
+#[test]
+fn skip_mime_types_skips_a_png_only_when_its_type_is_listed() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("logo.dat"),
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+    )
+    .unwrap();
+    fs::write(dir.path().join("notes.txt"), "text\n").unwrap();
+    let config_path = config_dir.path().join("dump.toml");
+
+    let list = |types: &str| {
+        fs::write(
+            &config_path,
+            format!("{}skip_mime_types = {types}\n", binary_only_toml()),
+        )
+        .unwrap();
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--list")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("notes.txt"))
+    };
+    list(r#"["image/png"]"#).stdout(predicate::str::contains("logo.dat").not());
+    list(r#"["image/"]"#).stdout(predicate::str::contains("logo.dat").not());
+    // Another binary type listed: the PNG is a recognized type no entry names.
+    list(r#"["application/zip"]"#).stdout(predicate::str::contains("logo.dat"));
+    // The list applies with binary detection off too.
+    fs::write(
+        &config_path,
+        format!(
+            "{}skip_mime_types = [\"image/\"]\n",
+            binary_only_toml().replace("skip_binary = true", "skip_binary = false")
+        ),
+    )
+    .unwrap();
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("logo.dat").not());
+}
+
+// ── --expand-tabs ──────────────────────────────────────────────────────────
+
+#[test]
+fn expand_tabs_converts_leading_tabs_only() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("main.go", "func main() {\n\tx := 1\t// one\n}\n")]);
+    let config_path = config_dir.path().join("dump.toml");
+    fs::write(&config_path, no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--expand-tabs")
+        .arg("--summary")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n    x := 1\t// one\n"))
+        .stdout(predicate::str::contains("1 file, 3 lines"));
+}
+
+#[test]
+fn expand_all_tabs_with_custom_width() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("table.tsv", "a\tb\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--expand-all-tabs=2")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a b\n"));
+}
+
+#[test]
+fn tab_width_expands_every_tab_column_aware() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("main.go", "\tx := 1\t// one\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--tab-width", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n    x := 1  // one\n"));
+}
+
+#[test]
+fn tab_width_conflicts_with_expand_tabs() {
+    cmd()
+        .args(["--tab-width", "4", "--expand-tabs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+// ── layered --config ───────────────────────────────────────────────────────
+
+#[test]
+fn repeated_config_layers_later_files_over_earlier() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "text"), ("blob.dat", "\0\0\0binary")]);
+    fs::write(config_dir.path().join("base.toml"), binary_only_toml()).unwrap();
+    fs::write(
+        config_dir.path().join("override.toml"),
+        "skip_binary = false\n",
+    )
+    .unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("base.toml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("blob.dat").not());
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("base.toml"))
+        .arg("--config")
+        .arg(config_dir.path().join("override.toml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("blob.dat"));
+}
+
+#[test]
+fn missing_file_among_repeated_configs_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("base.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("base.toml"))
+        .arg("--config")
+        .arg(dir.path().join("missing.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing.toml"));
+}
+
+// ── config check ───────────────────────────────────────────────────────────
+
+#[test]
+fn config_check_accepts_valid_config() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .args(["config", "check", "--config"])
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("OK\n"));
+}
+
+#[test]
+fn config_check_reports_invalid_regex() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("dump.toml"),
+        "skip_patterns = [\"(unclosed\"]\n",
+    )
+    .unwrap();
+
+    cmd()
+        .args(["config", "check", "--config"])
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("OK").not())
+        .stderr(predicate::str::contains(
+            "invalid regex in config: '(unclosed'",
+        ));
+}
+
+#[test]
+fn dump_reports_invalid_regex_in_config_with_hint() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "hello\n").unwrap();
+    fs::write(
+        dir.path().join("dump.toml"),
+        "skip_patterns = [\"(unclosed\"]\n",
+    )
+    .unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("hello").not())
+        .stderr(predicate::str::contains(
+            "invalid regex in config: '(unclosed'",
+        ))
+        .stderr(predicate::str::contains(
+            "Invalid regex pattern '(unclosed'",
+        ))
+        .stderr(predicate::str::contains("dump_dir::filter::invalid_regex"));
+}
+
+#[test]
+fn config_check_reports_invalid_glob() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("dump.toml"), "skip_globs = [\"a[b\"]\n").unwrap();
+
+    cmd()
+        .args(["config", "check", "--config"])
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid glob in config: 'a[b'"));
+}
+
+#[test]
+fn config_check_reports_missing_config() {
+    let dir = TempDir::new().unwrap();
+
+    cmd()
+        .args(["config", "check", "--config"])
+        .arg(dir.path().join("missing.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not found"));
+}
+
+#[test]
+fn schema_prints_the_config_json_schema() {
+    let output = cmd().arg("schema").output().unwrap();
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let skip_extensions = &schema["properties"]["skip_extensions"];
+    assert_eq!(skip_extensions["type"], "array");
+    assert_eq!(skip_extensions["items"]["type"], "string");
+}
+
+// ── --git-meta ─────────────────────────────────────────────────────────────
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .env("GIT_AUTHOR_DATE", "2024-03-05T12:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2024-03-05T12:00:00Z")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn git_meta_annotates_headers() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("src/tracked.txt", "tracked")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "src/tracked.txt"]);
+    git(dir.path(), &["commit", "-q", "-m", "Add tracked file"]);
+    make(&dir, &[("src/new.txt", "untracked")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path().join("src"))
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--git-meta")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"GIT: +[0-9a-f]{7,} 2024-03-05 Add tracked file").unwrap(),
+        )
+        .stdout(predicate::str::contains("GIT:  (untracked)"));
+}
+
+#[test]
+fn git_meta_is_silent_outside_a_repository() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--git-meta")
+        .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello"))
+        .stdout(predicate::str::contains("GIT:").not())
+        .stderr(predicate::str::is_empty());
+}
+
+// ── --metadata ─────────────────────────────────────────────────────────────
+
+#[test]
+fn metadata_shows_size_and_timestamp_in_headers() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path().join("a.txt"))
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--metadata", "--no-bat"])
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r" META: 6 bytes, modified \d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z",
+            )
+            .unwrap(),
+        );
+
+    cmd()
+        .arg(dir.path().join("a.txt"))
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--metadata", "--format", "ndjson"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""metadata":{"#))
+        .stdout(predicate::str::contains(r#""size":6"#));
+}
+
+// ── --changed ──────────────────────────────────────────────────────────────
+
+#[test]
+fn changed_dumps_only_files_modified_since_head() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/same.txt", "unchanged"),
+        ("src/edited.txt", "before"),
+    ]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+    make(&dir, &[("src/edited.txt", "after")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--changed")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("edited.txt"))
+        .stdout(predicate::str::contains("after"))
+        .stdout(predicate::str::contains("same.txt").not());
+}
+
+#[test]
+fn changed_outside_a_repository_fails_clearly() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello")]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--changed=main")
+        .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not inside a git repository"));
+}
+
+#[test]
+fn changed_between_dumps_only_files_changed_between_two_commits() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "first a"), ("b.txt", "first b")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+    git(dir.path(), &["tag", "v1"]);
+    make(&dir, &[("b.txt", "second b")]);
+    git(dir.path(), &["commit", "-q", "-am", "Edit b"]);
+    git(dir.path(), &["tag", "v2"]);
+    make(&dir, &[("a.txt", "uncommitted a")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let run = |to: &str| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(config_dir.path().join("dump.toml"))
+            .args(["--changed-between", "v1", to])
+            .assert()
+    };
+    run("v2")
+        .success()
+        .stdout(predicate::str::contains("second b"))
+        .stdout(predicate::str::contains("a.txt").not());
+    run("v3")
+        .failure()
+        .stderr(predicate::str::contains("Failed to diff against 'v3'"));
+}
+
+// ── --git-tracked / --git-untracked ────────────────────────────────────────
+
+#[test]
+fn git_tracked_and_untracked_split_the_tree() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("src/lib.rs", "committed code")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+    make(&dir, &[("notes.txt", "scratch notes")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let run = |flag: &str| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(config_dir.path().join("dump.toml"))
+            .arg(flag)
+            .assert()
+            .success()
+    };
+    run("--git-tracked")
+        .stdout(predicate::str::contains("committed code"))
+        .stdout(predicate::str::contains("scratch notes").not());
+    run("--git-untracked")
+        .stdout(predicate::str::contains("scratch notes"))
+        .stdout(predicate::str::contains("committed code").not());
+    run("--tracked-only")
+        .stdout(predicate::str::contains("committed code"))
+        .stdout(predicate::str::contains("scratch notes").not());
+}
+
+#[test]
+fn git_tracked_outside_a_repository_fails_clearly() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello")]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--git-tracked")
+        .env("GIT_CEILING_DIRECTORIES", dir.path().parent().unwrap())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not inside a git repository"));
+}
+
+// ── --list / --print0 ──────────────────────────────────────────────────────
+
+#[test]
+fn list_prints_paths_without_contents() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "alpha body"), ("sub/b.txt", "beta body")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let expected = format!(
+        "{}\n{}\n",
+        dir.path().join("a.txt").display(),
+        dir.path().join("sub/b.txt").display()
+    );
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(expected));
+}
+
+#[test]
+fn print0_separates_paths_with_nul() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "a"), ("b.txt", "b")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--list", "--print0"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains('\n'));
+    let paths: Vec<&str> = stdout.split_terminator('\0').collect();
+    assert_eq!(paths, [
+        dir.path().join("a.txt").to_str().unwrap(),
+        dir.path().join("b.txt").to_str().unwrap(),
+    ]);
+}
+
+#[test]
+fn list_short_print0_with_summary_counts_on_stderr() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a b.txt", "a"),
+        ("sub/c.txt", "c"),
+        ("sub/d.rs", "d"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--list", "-0", "--summary"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.split_terminator('\0').count(), 3);
+    assert!(stdout.contains("a b.txt\0"));
+    assert!(!stdout.contains("FILE:"));
+    assert!(!stdout.contains('\x1b'));
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "3\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn list_does_not_open_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("locked.txt", "secret")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let locked = dir.path().join("locked.txt");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+    // Listing never reads content, so an unreadable file is listed without a warning.
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("locked.txt"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn print0_requires_list() {
+    cmd()
+        .arg("--print0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--list"));
+}
+
+// ── output budget ──────────────────────────────────────────────────────────
+
+fn budget_dir() -> (TempDir, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.txt", "a1\na2\na3\n"),
+        ("b.txt", "b1\nb2\nb3\n"),
+        ("c.txt", "c1\nc2\nc3\n"),
+        ("d.txt", "d1\nd2\nd3\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    (dir, config_dir)
+}
+
+#[test]
+fn limit_prints_only_the_first_files_in_order() {
+    let (dir, config_dir) = budget_dir();
+    make(&dir, &[("e.txt", "e1\n")]);
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--limit", "2", "--summary-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("a3") && stdout.contains("b3"));
+    for omitted in ["c.txt", "d.txt", "e.txt"] {
+        assert!(!stdout.contains(omitted), "{omitted} printed:\n{stdout}");
+    }
+    let summary: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+    assert_eq!(summary["file_count"], 2);
+}
+
+#[test]
+fn max_total_lines_omits_remaining_files() {
+    let (dir, config_dir) = budget_dir();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--max-total-lines", "5", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("b3"))
+        .stdout(predicate::str::contains("c.txt").not())
+        .stdout(predicate::str::contains(
+            "── budget reached, 2 files omitted ──",
+        ))
+        .stdout(predicate::str::contains(
+            "2 files, 6 lines, 2 files omitted (budget)",
+        ));
+}
+
+#[test]
+fn max_total_bytes_omits_remaining_files() {
+    let (dir, config_dir) = budget_dir();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--max-total-bytes", "9", "--jobs", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a3"))
+        .stdout(predicate::str::contains("b.txt").not())
+        .stdout(predicate::str::contains("3 files omitted"));
+}
+
+#[test]
+fn abort_after_bytes_aborts_once_exhausted() {
+    let (dir, config_dir) = budget_dir();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--abort-after-bytes", "9", "--jobs", "2", "--summary"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("a3"))
+        .stdout(predicate::str::contains("b.txt").not())
+        .stdout(predicate::str::contains("Summary").not())
+        .stderr(predicate::str::contains(
+            "Output size limit of 9 bytes reached; dump aborted",
+        ));
+}
+
+#[test]
+fn abort_after_bytes_cuts_off_the_file_that_crosses_it() {
+    let (dir, config_dir) = budget_dir();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--abort-after-bytes", "14"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("a3\n"))
+        .stdout(predicate::str::contains(
+            "b1\nb2\n── output size limit reached",
+        ))
+        .stdout(predicate::str::contains("b3").not())
+        .stdout(predicate::str::contains("c.txt").not());
+}
+
+#[test]
+fn fail_on_budget_exits_non_zero_only_when_files_are_omitted() {
+    let (dir, config_dir) = budget_dir();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--max-total-lines", "3", "--fail-on-budget"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Output budget reached: 3 file(s) omitted",
+        ));
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--max-total-lines", "100", "--fail-on-budget"])
+        .assert()
+        .success();
+}
+
+// ── --root-label ───────────────────────────────────────────────────────────
+
+#[test]
+fn root_label_replaces_root_in_headers() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("src/main.rs", "fn main() {}\n")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--root-label", "project"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" FILE: project/src/main.rs\n"))
+        .stdout(predicate::str::contains(dir.path().to_str().unwrap()).not());
+}
+
+// ── --prepend / --append ───────────────────────────────────────────────────
+
+#[test]
+fn prepend_and_append_wrap_the_dump() {
+    let dir = TempDir::new().unwrap();
+    let prompt_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "body\n")]);
+    make(&prompt_dir, &[
+        ("dump.toml", no_filter_toml()),
+        ("pre.md", "You are reviewing the following codebase."),
+        ("post.md", "What would you change?\n"),
+    ]);
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(prompt_dir.path().join("dump.toml"))
+        .arg("--prepend")
+        .arg(prompt_dir.path().join("pre.md"))
+        .arg("--append")
+        .arg(prompt_dir.path().join("post.md"))
+        .arg("--summary")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("You are reviewing the following codebase.\n"));
+    let header = stdout.find("FILE:").unwrap();
+    let epilogue = stdout.find("What would you change?").unwrap();
+    let summary = stdout.find("Summary:").unwrap();
+    assert!(header < epilogue && epilogue < summary);
+    // Prompt text is not counted as dumped content.
+    assert!(stdout.contains("1 file, 1 line"));
+}
+
+#[test]
+fn prepended_text_counts_toward_byte_budget() {
+    let dir = TempDir::new().unwrap();
+    let prompt_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "body\n")]);
+    make(&prompt_dir, &[
+        ("dump.toml", no_filter_toml()),
+        ("pre.md", "a long preamble\n"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(prompt_dir.path().join("dump.toml"))
+        .arg("--prepend")
+        .arg(prompt_dir.path().join("pre.md"))
+        .args(["--max-total-bytes", "10"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt").not())
+        .stdout(predicate::str::contains("1 file omitted"));
+}
+
+#[test]
+fn prepended_text_counts_toward_abort_after_bytes() {
+    let dir = TempDir::new().unwrap();
+    let prompt_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "body\n")]);
+    make(&prompt_dir, &[
+        ("dump.toml", no_filter_toml()),
+        ("pre.md", "a long preamble\n"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(prompt_dir.path().join("dump.toml"))
+        .arg("--prepend")
+        .arg(prompt_dir.path().join("pre.md"))
+        .args(["--abort-after-bytes", "10"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("a long preamble"))
+        .stdout(predicate::str::contains("a.txt").not())
+        .stderr(predicate::str::contains(
+            "Output size limit of 10 bytes reached",
+        ));
+}
+
+#[test]
+fn missing_append_file_is_path_not_found() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "body")]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--append")
+        .arg(dir.path().join("missing.md"))
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("Path does not exist"));
+}
+
+// ── --quiet ────────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn quiet_suppresses_unreadable_file_warning() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("locked.txt", "secret"), ("open.txt", "visible")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let locked = dir.path().join("locked.txt");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&locked).is_ok() {
+        // Running as root: permissions are not enforced, nothing to test.
+        return;
+    }
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Warning: cannot read"));
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--quiet", "--summary"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("visible"))
+        .stdout(predicate::str::contains("1 unreadable skipped"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn quiet_still_reports_hard_errors() {
+    let dir = TempDir::new().unwrap();
+
+    cmd()
+        .arg(dir.path().join("missing"))
+        .arg("--quiet")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Path does not exist"));
+}
+
+// ── broken pipe ────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn broken_pipe_exits_cleanly() {
+    use std::process::Stdio;
+
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let body = "line\n".repeat(2_000);
+    for i in 0..50 {
+        make(&dir, &[(&format!("f{i:02}.txt"), &body)]);
+    }
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let mut dump = std::process::Command::new(env!("CARGO_BIN_EXE_dump-dir"))
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--summary")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // `head -c 1` reads one byte and exits, closing the pipe early.
+    let head = std::process::Command::new("head")
+        .args(["-c", "1"])
+        .stdin(dump.stdout.take().unwrap())
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(head.success());
+
+    let output = dump.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "stderr: {stderr}");
+    assert!(stderr.is_empty(), "unexpected stderr: {stderr}");
+}
+
+// ── buffered output ────────────────────────────────────────────────────────
+
+/// ~5k small files with stdout and stderr sharing one file descriptor: every
+/// section must come out whole and in order, with warnings only between sections.
+#[cfg(unix)]
+#[test]
+fn buffered_output_does_not_interleave_with_warnings() {
+    use std::{os::unix::fs::PermissionsExt, process::Stdio};
+
+    const SEP: &str = "====================================================";
+
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let mut expected = Vec::new();
+    let mut unreadable = 0;
+    for d in 0..10 {
+        for f in 0..500 {
+            let path = dir.path().join(format!("d{d}/f{f:03}.txt"));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, format!("content {d}/{f}\n")).unwrap();
+            if f % 100 == 0 {
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+            }
+            if fs::read(&path).is_err() {
+                unreadable += 1;
+                continue;
+            }
+            expected.extend([
+                SEP.to_string(),
+                format!(" FILE: {}", path.display()),
+                SEP.to_string(),
+                format!("content {d}/{f}"),
+                String::new(),
+            ]);
+        }
+    }
+
+    let combined = config_dir.path().join("combined.log");
+    let log = fs::File::create(&combined).unwrap();
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_dump-dir"))
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .stdout(Stdio::from(log.try_clone().unwrap()))
+        .stderr(Stdio::from(log))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = fs::read_to_string(&combined).unwrap();
+    let (warnings, lines): (Vec<&str>, Vec<&str>) =
+        output.lines().partition(|l| l.starts_with("Warning: "));
+    assert_eq!(warnings.len(), unreadable);
+    for warning in warnings {
+        assert!(warning.ends_with(".txt' (permission denied)"), "{warning}");
+    }
+    assert_eq!(lines, expected);
+}
+
+// ── --watch ────────────────────────────────────────────────────────────────
+
+#[test]
+fn watch_redumps_after_a_file_changes() {
+    use std::{
+        io::{BufRead, BufReader},
+        process::Stdio,
+        sync::mpsc,
+        time::{Duration, Instant},
+    };
+
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "first version\n")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_dump-dir"))
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--watch")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    let wait_for = |needle: &str| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(left) {
+                Ok(line) if line.contains(needle) => return true,
+                Ok(_) => {},
+                Err(_) => break,
+            }
+        }
+        false
+    };
+
+    let first = wait_for("first version");
+    if first {
+        fs::write(dir.path().join("a.txt"), "second version\n").unwrap();
+    }
+    let redumped = first && wait_for("change detected") && wait_for("second version");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(first, "initial dump not seen");
+    assert!(redumped, "no re-dump after the file changed");
+}
+
+// ── --format manifest / --verify ───────────────────────────────────────────
+
+#[test]
+fn manifest_lists_hash_size_and_relative_path() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("abc.txt", "abc"), ("sub/empty.txt", "")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--format", "manifest"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  3  abc.txt\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  0  sub/empty.txt\n",
+        ));
+}
+
+#[test]
+fn verify_passes_for_unchanged_tree_and_reports_differences() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("keep.txt", "same"),
+        ("edit.txt", "before"),
+        ("drop.txt", "x"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let config = config_dir.path().join("dump.toml");
+    let saved = config_dir.path().join("tree.manifest");
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .args(["--format", "manifest", "--jobs", "4"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    fs::write(&saved, output.stdout).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .arg("--verify")
+        .arg(&saved)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK: 3 files match"));
+
+    fs::write(dir.path().join("edit.txt"), "after").unwrap();
+    fs::remove_file(dir.path().join("drop.txt")).unwrap();
+    make(&dir, &[("new.txt", "new")]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .arg("--verify")
+        .arg(&saved)
+        .assert()
+        .failure()
+        .stdout(predicate::str::diff(
+            "added: new.txt\nremoved: drop.txt\nchanged: edit.txt\n",
+        ))
+        .stderr(predicate::str::contains("1 added, 1 removed, 1 changed"));
+}
+
+#[test]
+fn verify_applies_the_same_git_selection_as_the_manifest() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("tracked.txt", "committed")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+    make(&dir, &[("scratch.txt", "not added")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let config = config_dir.path().join("dump.toml");
+    let saved = config_dir.path().join("tree.manifest");
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .args(["--format", "manifest", "--git-tracked"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    fs::write(&saved, output.stdout).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .arg("--git-tracked")
+        .arg("--verify")
+        .arg(&saved)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("OK: 1 files match"));
+}
+
+#[test]
+fn manifest_of_several_roots_keeps_same_named_files_apart() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("a/x.txt", "one"), ("b/x.txt", "two")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let config = config_dir.path().join("dump.toml");
+    let saved = config_dir.path().join("tree.manifest");
+    let roots = [dir.path().join("a"), dir.path().join("b")];
+
+    let output = cmd()
+        .args(&roots)
+        .arg("--config")
+        .arg(&config)
+        .args(["--format", "manifest"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2, "{text}");
+    assert!(lines[0].ends_with("/a/x.txt"), "{text}");
+    assert!(lines[1].ends_with("/b/x.txt"), "{text}");
+    fs::write(&saved, &text).unwrap();
+
+    fs::write(dir.path().join("b/x.txt"), "changed").unwrap();
+    cmd()
+        .args(&roots)
+        .arg("--config")
+        .arg(&config)
+        .arg("--verify")
+        .arg(&saved)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("/b/x.txt"))
+        .stdout(predicate::str::contains("/a/x.txt").not())
+        .stderr(predicate::str::contains("0 added, 0 removed, 1 changed"));
+}
+
+#[test]
+fn verify_rejects_malformed_manifest() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("bad.manifest", "not a manifest\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .arg("--verify")
+        .arg(dir.path().join("bad.manifest"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("line 1"));
+}
+
+// ── --format markdown / --toc ──────────────────────────────────────────────
+
+#[test]
+fn markdown_toc_lists_every_root_before_any_content() {
+    let a = TempDir::new().unwrap();
+    let b = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&a, &[("main.rs", "fn main() {}\n")]);
+    make(&b, &[("main_test.rs", "#[test]\nfn t() {}\n")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(a.path())
+        .arg(b.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--format", "markdown", "--toc"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let toc_end = stdout.find("<a id=").unwrap();
+    let toc = &stdout[..toc_end];
+    assert!(toc.starts_with("## Contents\n\n"));
+    let links: Vec<&str> = toc
+        .lines()
+        .filter_map(|line| line.split("](#").nth(1))
+        .map(|rest| rest.trim_end_matches(')'))
+        .collect();
+    assert_eq!(links.len(), 2);
+    for anchor in links {
+        assert!(stdout.contains(&format!("<a id=\"{anchor}\"></a>\n## ")));
+    }
+    assert!(stdout.contains("```rust\nfn main() {}\n```\n"));
+}
+
+#[test]
+fn text_toc_lists_files_in_order_before_the_first_header() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.rs", "fn a() {}\n"),
+        ("b/c.rs", "fn c() {}\nfn d() {}\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--no-bat", "--toc"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let first_header = stdout.find("FILE:").unwrap();
+    let toc = &stdout[..first_header];
+    assert!(toc.starts_with("Contents:\n"));
+    let a = toc.find("1. ").unwrap();
+    let c = toc.find("2. ").unwrap();
+    assert!(a < c);
+    assert!(toc[a..c].contains("a.rs"));
+    assert!(toc[c..].contains("c.rs"));
+}
+
+// ── --concat ───────────────────────────────────────────────────────────────
+
+#[test]
+fn concat_prints_contents_back_to_back_without_headers() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "alpha\n"), ("b.txt", "beta")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--concat", "--no-bat"])
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("alpha\nbeta\n"));
+}
+
+#[test]
+fn ndjson_start_offsets_index_the_concat_output() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.txt", "alpha\n"),
+        ("b.txt", "beta"),
+        ("c/d.rs", "fn d() {}\n// é\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    fs::write(config_dir.path().join("prompt.md"), "review").unwrap();
+    let run = |format: &str| {
+        let output = cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(config_dir.path().join("dump.toml"))
+            .arg("--prepend")
+            .arg(config_dir.path().join("prompt.md"))
+            .args(["--no-bat", "--format", format])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    let concat = run("concat");
+    let ndjson = String::from_utf8(run("ndjson")).unwrap();
+    let mut files = 0;
+    for line in ndjson.lines() {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        let Some(content) = record["content"].as_str() else {
+            continue;
+        };
+        let start = usize::try_from(record["start_offset"].as_u64().unwrap()).unwrap();
+        assert_eq!(
+            &concat[start..start + content.len()],
+            content.as_bytes(),
+            "{line}"
+        );
+        files += 1;
+    }
+    assert_eq!(files, 3);
+}
+
+// ── --format ndjson ────────────────────────────────────────────────────────
+
+#[test]
+fn ndjson_lines_each_parse_on_their_own() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.txt", "first line\nsecond \"quoted\" line\n"),
+        ("sub/b.rs", "fn main() {\n\tprintln!(\"{}\", 1);\n}\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    fs::write(config_dir.path().join("prompt.md"), "review\n").unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--prepend")
+        .arg(config_dir.path().join("prompt.md"))
+        .args(["--format", "ndjson", "--summary"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect(line))
+        .collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0]["text"], "review\n");
+    assert!(records[1]["path"].as_str().unwrap().ends_with("a.txt"));
+    assert_eq!(
+        records[1]["content"],
+        "first line\nsecond \"quoted\" line\n"
+    );
+    assert!(records[2]["path"].as_str().unwrap().ends_with("b.rs"));
+    assert_eq!(records[3]["summary"]["file_count"], 2);
+}
+
+// ── --theme / --list-themes ────────────────────────────────────────────────
+
+/// A directory holding a stand-in `bat` that knows three themes and otherwise
+/// echoes its arguments, to be put first on PATH.
+#[cfg(unix)]
+fn fake_bat() -> (TempDir, String) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = TempDir::new().unwrap();
+    let bat = bin.path().join("bat");
+    fs::write(
+        &bat,
+        "#!/bin/sh\ncase \"$*\" in\n  *--list-themes*) printf 'Dracula\\nNord\\nSolarized (dark)\\n' ;;\n  *) echo \"bat $*\" ;;\nesac\n",
+    )
+    .unwrap();
+    fs::set_permissions(&bat, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    (bin, path)
+}
+
+#[cfg(unix)]
+#[test]
+fn list_themes_prints_bat_theme_names() {
+    let (_bin, path) = fake_bat();
+
+    cmd()
+        .env("PATH", path)
+        .arg("--list-themes")
+        .assert()
+        .success()
+        .stdout(predicate::str::diff("Dracula\nNord\nSolarized (dark)\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn theme_is_forwarded_to_bat() {
+    let (_bin, path) = fake_bat();
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("main.rs", "fn main() {}\n")]);
+
+    cmd()
+        .env("PATH", path)
+        .arg(dir.path())
+        .args(["--theme", "Solarized (dark)"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--theme=Solarized (dark)"));
+}
+
+#[cfg(unix)]
+#[test]
+fn unknown_theme_is_left_to_bat() {
+    let (_bin, path) = fake_bat();
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("main.rs", "fn main() {}\n")]);
+
+    cmd()
+        .env("PATH", path)
+        .arg(dir.path())
+        .args(["--theme", "Nope"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--theme=Nope"));
+}
+
+#[cfg(unix)]
+#[test]
+fn config_check_rejects_unknown_theme() {
+    let (_bin, path) = fake_bat();
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("dump.toml"),
+        "[printer]\ntheme = \"Nope\"\n",
+    )
+    .unwrap();
+
+    cmd()
+        .env("PATH", path)
+        .args(["config", "check", "--config"])
+        .arg(dir.path().join("dump.toml"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown theme 'Nope'. Valid themes: Dracula, Nord, Solarized (dark)",
+        ));
+}
+
+// ── --bat-timeout ──────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn hung_bat_is_killed_and_file_printed_plainly() {
+    use std::{os::unix::fs::PermissionsExt, time::Instant};
+
+    let bin = TempDir::new().unwrap();
+    let bat = bin.path().join("bat");
+    // `exec` so the kill reaches the sleeping process itself.
+    fs::write(&bat, "#!/bin/sh\nexec sleep 30\n").unwrap();
+    fs::set_permissions(&bat, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("main.rs", "fn main() {}\n")]);
+
+    let started = Instant::now();
+    cmd()
+        .env("PATH", path)
+        .arg(dir.path())
+        .args(["--bat-timeout", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn main() {}"))
+        .stderr(predicate::str::contains("bat timed out"));
+    assert!(started.elapsed().as_secs() < 10);
+}
+
+// ── --show-skipped ─────────────────────────────────────────────────────────
+
+#[test]
+fn show_skipped_lists_excluded_paths_by_reason() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("Cargo.lock", "lock\n"),
+        ("node_modules/pkg/index.js", "x\n"),
+        ("debug.log", "noise\n"),
+        (".gitignore", "*.log\n"),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        r#"
+skip_extensions = ["lock"]
+skip_patterns = []
+skip_filenames = []
+skip_path_components = [".git", "node_modules"]
+skip_globs = []
+skip_binary = false
+skip_hidden = true
+"#,
+    )
+    .unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--summary", "--show-skipped"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let summary = stdout.find("── Summary:").unwrap();
+    let report = &stdout[stdout.find("── Skipped:").unwrap()..];
+    assert!(stdout.find("── Skipped:").unwrap() > summary);
+    let root = dir.path().display();
+    assert_eq!(
+        report,
+        format!(
+            "── Skipped: 3 files, 2 directories\n\
+             component:\n  {root}/node_modules/\n\
+             hidden:\n  {root}/.git/\n  {root}/.gitignore\n\
+             extension:\n  {root}/Cargo.lock\n\
+             gitignore:\n  {root}/debug.log\n"
+        )
+    );
+}
+
+#[test]
+fn show_skipped_lists_generated_files() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("main.py", "print('hi')\n"),
+        ("api_pb2.py", "x = 1\n"),
+        (
+            "schema.rs",
+            "// @generated by a tool, DO NOT EDIT\nstruct S;\n",
+        ),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        format!("{}skip_generated = true\n", no_filter_toml()),
+    )
+    .unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--no-bat", "--show-skipped"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("print('hi')"));
+    assert!(!stdout.contains("struct S;"));
+    let root = dir.path().display();
+    assert!(stdout.contains(&format!(
+        "── Skipped: 2 files, 0 directories\ngenerated:\n  {root}/api_pb2.py\n  {root}/schema.rs\n"
+    )));
+}
+
+#[test]
+fn max_file_lines_skips_long_files_and_reports_them() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("short.rs", "fn a() {}\n"),
+        ("long.rs", &"x();\n".repeat(50)),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--no-bat", "--show-skipped", "--max-file-lines", "10"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("fn a() {}"));
+    assert!(!stdout.contains("x();"));
+    let root = dir.path().display();
+    assert!(stdout.contains(&format!(
+        "── Skipped: 1 file, 0 directories\nlines:\n  {root}/long.rs\n"
+    )));
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--no-bat", "--max-file-lines", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("x();"));
+}
+
+#[test]
+fn max_lines_and_skip_max_lines_name_the_same_limit() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("short.txt", &"kept\n".repeat(10)),
+        ("tall.txt", &"a\n".repeat(100)),
+    ]);
+    let config = config_dir.path().join("dump.toml");
+
+    fs::write(&config, no_filter_toml()).unwrap();
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .args(["--no-bat", "--max-lines", "50"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept"))
+        .stdout(predicate::str::contains("a\na\n").not());
+
+    fs::write(
+        &config,
+        format!("{}\nskip_max_lines = 50\n", no_filter_toml()),
+    )
+    .unwrap();
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(&config)
+        .arg("--no-bat")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("kept"))
+        .stdout(predicate::str::contains("a\na\n").not());
+}
+
+// ── --report-empty-dirs ────────────────────────────────────────────────────
+
+#[test]
+fn report_empty_dirs_names_a_directory_holding_only_a_lockfile() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("locks/Cargo.lock", "lock\n"),
+    ]);
+
+    let root = dir.path().display();
+    cmd()
+        .arg(dir.path())
+        .arg("--report-empty-dirs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn main() {}"))
+        .stderr(predicate::str::contains(format!(
+            "── Empty directories: 1\n  {root}/locks/\n"
+        )))
+        .stderr(predicate::str::contains("src/").not());
+}
+
+#[test]
+fn report_empty_dirs_follows_the_git_selection() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("src/main.rs", "v1"), ("docs/guide.txt", "guide")]);
+    git(dir.path(), &["init", "-q"]);
+    git(dir.path(), &["add", "."]);
+    git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+    make(&dir, &[("src/main.rs", "v2")]);
+
+    // Under the default config, which prunes `.git`.
+    let root = dir.path().display();
+    cmd()
+        .arg(dir.path())
+        .args(["--changed", "--report-empty-dirs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("v2"))
+        .stderr(predicate::str::contains(format!(
+            "── Empty directories: 1\n  {root}/docs/\n"
+        )));
+}
+
+// ── --symlinks ─────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+fn symlinked_dir() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("real.rs", "fn real() {}\n")]);
+    std::os::unix::fs::symlink("real.rs", dir.path().join("alias.rs")).unwrap();
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    dir
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_are_left_out_by_default() {
+    let dir = symlinked_dir();
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alias.rs").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_list_prints_a_target_placeholder() {
+    let dir = symlinked_dir();
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--symlinks", "list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("fn real() {}").count(), 1);
+    let alias = stdout.find("alias.rs").unwrap();
+    assert!(stdout[alias..].contains("-> real.rs\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_follow_prints_the_target_content_once() {
+    let dir = symlinked_dir();
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--symlinks", "follow"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("fn real() {}").count(), 1);
+    assert!(stdout.contains("alias.rs"));
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_links_walks_a_linked_directory_and_survives_a_cycle() {
+    let dir = TempDir::new().unwrap();
+    let shared = TempDir::new().unwrap();
+    make(&dir, &[("app/main.rs", "fn main() {}\n")]);
+    make(&shared, &[("util.rs", "fn util() {}\n")]);
+    std::os::unix::fs::symlink(shared.path(), dir.path().join("app/shared")).unwrap();
+    // Points back up at the root: following it would never end.
+    std::os::unix::fs::symlink("..", dir.path().join("app/loop")).unwrap();
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--follow-links"])
+        .timeout(std::time::Duration::from_secs(30))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("fn util() {}").count(), 1);
+    assert_eq!(stdout.matches("fn main() {}").count(), 1);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("loop"), "{stderr}");
+}
+
+// ── --progress ─────────────────────────────────────────────────────────────
+
+#[cfg(feature = "progress")]
+#[test]
+fn progress_leaves_stdout_unchanged() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.rs", "fn a() {}\n"), ("b/c.rs", "fn c() {}\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+    let run = |extra: &[&str]| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(dir.path().join("dump.toml"))
+            .args(["--no-bat", "--summary"])
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+
+    let plain = run(&[]);
+    let with_progress = run(&["--progress"]);
+    assert!(with_progress.status.success());
+    let strip_elapsed = |out: &[u8]| {
+        let text = String::from_utf8(out.to_vec()).unwrap();
+        text[..text.find("── Summary:").unwrap()].to_string()
+    };
+    assert_eq!(
+        strip_elapsed(&with_progress.stdout),
+        strip_elapsed(&plain.stdout)
+    );
+    // Not a terminal, so nothing is drawn.
+    assert!(with_progress.stderr.is_empty());
+}
+
+// ── --explain ──────────────────────────────────────────────────────────────
+
+#[test]
+fn explain_prints_a_verdict_per_path() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("Cargo.lock", "lock\n"),
+        ("target/debug/app", "bin\n"),
+        ("vendor/a.rs", "fn a() {}\n"),
+        (".gitignore", "# build output\n*.log\ntarget/\n"),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        format!(
+            "{}skip_extensions = [\"lock\"]\nskip_globs = [\"**/vendor/**\"]\n",
+            no_filter_toml()
+                .replace("skip_extensions = []\n", "")
+                .replace("skip_globs = []\n", "")
+        ),
+    )
+    .unwrap();
+
+    cmd()
+        .current_dir(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--explain", "src/main.rs", "Cargo.lock", "target/debug/app"])
+        .arg("vendor/a.rs")
+        .assert()
+        .success()
+        .stdout(
+            "src/main.rs: included\n\
+             Cargo.lock: skipped by skip_extensions: lock\n\
+             target/debug/app: skipped by gitignore: target/ in .gitignore line 3\n\
+             vendor/a.rs: skipped by skip_globs: **/vendor/**\n",
+        );
+}
+
+#[test]
+fn why_is_an_alias_for_explain() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("notes.md", "x\n")]);
+
+    cmd()
+        .current_dir(dir.path())
+        .args(["--include-extensions", "rs", "--why", "notes.md"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "notes.md: skipped by include rules: ",
+        ));
+}
+
+// ── --no-bat ───────────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn no_bat_prints_plainly_even_when_bat_is_installed() {
+    let (_bin, path) = fake_bat();
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("main.rs", "fn main() {}\n")]);
+
+    cmd()
+        .env("PATH", &path)
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bat --style=numbers"));
+
+    cmd()
+        .env("PATH", &path)
+        .arg(dir.path())
+        .arg("--no-bat")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn main() {}\n"))
+        .stdout(predicate::str::contains("bat --style").not());
+}
+
+// ── --include-extensions / --include-globs ─────────────────────────────────
+
+#[test]
+fn include_flags_limit_the_dump_to_matching_files() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("Cargo.toml", "[package]\n"),
+        ("notes.md", "# notes\n"),
+        ("web/app.js", "let x;\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--include-extensions", "rs,toml", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("Cargo.toml"))
+        .stdout(predicate::str::contains("notes.md").not())
+        .stdout(predicate::str::contains("app.js").not());
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--include-globs", "**/web/**", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("app.js"))
+        .stdout(predicate::str::contains("main.rs").not());
+}
+
+#[test]
+fn globs_are_relative_to_the_root_not_the_cwd() {
+    let dir = TempDir::new().unwrap();
+    let elsewhere = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/gen/out.rs", "// generated\n"),
+        ("notes.md", "# notes\n"),
+    ]);
+    // Picked up as ./dump.toml from the cwd.
+    fs::write(elsewhere.path().join("dump.toml"), no_filter_toml()).unwrap();
+    fs::write(elsewhere.path().join("excludes.txt"), "src/gen/**\n").unwrap();
+
+    cmd()
+        .current_dir(elsewhere.path())
+        .arg(dir.path())
+        .args([
+            "--include-globs",
+            "src/**",
+            "--exclude-from",
+            "excludes.txt",
+        ])
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("out.rs").not())
+        .stdout(predicate::str::contains("notes.md").not());
+}
+
+// ── --case-sensitive ───────────────────────────────────────────────────────
+
+#[test]
+fn case_sensitive_flag_matches_names_exactly() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[("README.md", "upper\n"), ("readme.md", "lower\n")]);
+    let config = format!("{}skip_filenames = [\"README\"]\n", no_filter_toml())
+        .replace("skip_filenames = []\n", "");
+    fs::write(config_dir.path().join("dump.toml"), config).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--case-sensitive", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("readme.md"))
+        .stdout(predicate::str::contains("README.md").not());
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("readme.md").not());
+}
+
+// ── --lang ─────────────────────────────────────────────────────────────────
+
+#[test]
+fn lang_selects_files_by_language_and_fences_them() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("Makefile", "all:\n"),
+        ("rules.mk", "X = 1\n"),
+        ("notes.md", "# notes\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--lang", "rust,make", "--format", "markdown"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("```rust"))
+        .stdout(predicate::str::contains("```makefile"))
+        .stdout(predicate::str::contains("rules.mk"))
+        .stdout(predicate::str::contains("notes.md").not());
+}
+
+#[test]
+fn lang_selects_makefiles_and_dockerfiles_under_the_default_config() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("Makefile", "all:\n"),
+        ("docker/Dockerfile", "FROM scratch\n"),
+        ("src/main.rs", "fn main() {}\n"),
+    ]);
+    fs::write(config_dir.path().join("dump.toml"), "").unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--lang", "make,docker", "--list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Makefile"))
+        .stdout(predicate::str::contains("Dockerfile"))
+        .stdout(predicate::str::contains("main.rs").not());
+}
+
+#[test]
+fn extensionless_scripts_are_text_fenced_by_their_shebang() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("bin/deploy", "#!/usr/bin/env bash\necho deploying\n"),
+        ("bin/manage", "#!/usr/bin/python3\nprint('managing')\n"),
+        ("bin/bogus", "#!\0\u{1}\u{2}"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--format", "markdown"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("```bash\n#!/usr/bin/env bash"))
+        .stdout(predicate::str::contains("```python\n#!/usr/bin/python3"))
+        .stdout(predicate::str::contains("bin/bogus").not());
+}
+
+#[test]
+fn unknown_lang_fails_at_startup() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.rs", "fn a() {}\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--lang", "klingon"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown language 'klingon'"));
+}
+
+// ── --no-gitignore ─────────────────────────────────────────────────────────
+
+#[test]
+fn no_gitignore_dumps_gitignored_files_but_keeps_filters() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        (".gitignore", "build/\n"),
+        ("src/main.rs", "fn main() {}"),
+        ("build/out.txt", "generated output"),
+        ("build/cache.bak", "backup"),
+    ]);
+    git(dir.path(), &["init", "-q"]);
+    fs::write(
+        dir.path().join("dump.toml"),
+        "skip_extensions = [\"bak\", \"toml\"]\n",
+    )
+    .unwrap();
+
+    let run = |extra: &[&str]| {
+        cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(dir.path().join("dump.toml"))
+            .args(extra)
+            .assert()
+            .success()
+    };
+
+    run(&[])
+        .stdout(predicate::str::contains("fn main()"))
+        .stdout(predicate::str::contains("generated output").not());
+    run(&["--no-gitignore"])
+        .stdout(predicate::str::contains("fn main()"))
+        .stdout(predicate::str::contains("generated output"))
+        .stdout(predicate::str::contains("backup").not());
+}
+
+// ── --cache-dir ────────────────────────────────────────────────────────────
+
+#[test]
+fn cache_dir_reuses_unchanged_files_across_runs() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let cache = config_dir.path().join("cache");
+    make(&dir, &[("a.txt", "alpha\n"), ("b.txt", "beta\n")]);
+    fs::write(config_dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let run = || {
+        let output = cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(config_dir.path().join("dump.toml"))
+            .arg("--cache-dir")
+            .arg(&cache)
+            .args(["--summary-format", "json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let json: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+        (stdout, json["cache_hits"].as_u64().unwrap())
+    };
+
+    let (first, hits) = run();
+    assert_eq!(hits, 0);
+    assert!(cache.is_dir());
+    fs::write(dir.path().join("b.txt"), "beta, edited\n").unwrap();
+    let (second, hits) = run();
+    assert_eq!(hits, 1);
+    assert!(first.contains("alpha") && second.contains("alpha"));
+    assert!(second.contains("beta, edited"));
+}
+
+// ── --always-include ───────────────────────────────────────────────────────
+
+#[test]
+fn always_include_overrides_filters_and_directory_pruning() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("README.md", "# readme"),
+        ("crates/cli/Cargo.lock", "cli lockfile"),
+        ("crates/lib/Cargo.lock", "lib lockfile"),
+        ("node_modules/pkg/index.js", "kept module"),
+        ("node_modules/other/index.js", "dropped module"),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        "skip_extensions = [\"lock\", \"md\"]\nskip_path_components = [\"node_modules\"]\n",
+    )
+    .unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--always-include", "README.md"])
+        .args(["--always-include", "crates/cli/Cargo.lock"])
+        .args(["--always-include", "node_modules/pkg/*.js"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# readme"))
+        .stdout(predicate::str::contains("cli lockfile"))
+        .stdout(predicate::str::contains("kept module"))
+        .stdout(predicate::str::contains("lib lockfile").not())
+        .stdout(predicate::str::contains("dropped module").not());
+}
+
+// ── skip_minified ──────────────────────────────────────────────────────────
+
+#[test]
+fn skip_minified_reports_bundles_in_show_skipped() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    let bundle = format!("!function(){{{}}}();", "var a=1;".repeat(800));
+    make(&dir, &[
+        ("app.js", "export const x = 1;\n"),
+        ("app.bundle.js", &bundle),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        "skip_minified = true\n",
+    )
+    .unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--show-skipped")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("export const x"));
+    assert!(!stdout.contains("var a=1"));
+    assert!(stdout.contains(&format!(
+        "minified:\n  {}\n",
+        dir.path().join("app.bundle.js").display()
+    )));
+}
+
+// ── DUMP_DIR_* environment ─────────────────────────────────────────────────
+
+#[test]
+fn environment_variables_override_config_and_yield_to_flags() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("notes.txt", "plain notes\n"),
+        ("main.rs", "fn main() {}\n"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .env("DUMP_DIR_SKIP_EXTENSIONS", "rs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("plain notes"))
+        .stdout(predicate::str::contains("fn main").not());
+
+    cmd()
+        .arg(dir.path())
+        .env("DUMP_DIR_SKIP_EXTENSIONS", "rs")
+        .args(["--skip-extensions", "txt"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fn main"))
+        .stdout(predicate::str::contains("plain notes").not());
+}
+
+// ── --preset / --list-presets ──────────────────────────────────────────────
+
+#[test]
+fn preset_adds_its_filters_to_the_config() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("index.js", "export const app = 1;\n"),
+        ("vendor.min.js", "var minified=1;\n"),
+        ("node_modules/dep/index.js", "module.exports = 'dep';\n"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--no-bat", "--preset", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("export const app"))
+        .stdout(predicate::str::contains("var minified").not())
+        .stdout(predicate::str::contains("'dep'").not());
+
+    cmd()
+        .arg(dir.path())
+        .args(["--preset", "cobol"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown preset 'cobol'"));
+}
+
+#[test]
+fn list_presets_prints_each_preset_and_its_entries() {
+    let output = cmd().arg("--list-presets").output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("code-only: "));
+    assert!(stdout.contains(
+        "\nrust: Build output, lockfiles and rustfmt backups of Rust projects\n  \
+         skip_extensions = [\"lock\"]\n  skip_path_components = [\"target\"]\n  \
+         skip_globs = [\"**/*.rs.bk\"]\n"
+    ));
+}
+
+// ── --skip-groups / --list-groups ──────────────────────────────────────────
+
+#[test]
+fn skip_groups_compose_with_the_config_skip_lists() {
+    let dir = TempDir::new().unwrap();
+    let config_dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}\n"),
+        ("debug.log", "noise\n"),
+        ("web/yarn.lock", "lock\n"),
+        ("assets/logo.svg", "<svg/>\n"),
+    ]);
+    fs::write(
+        config_dir.path().join("dump.toml"),
+        "skip_extensions = [\"log\"]\nskip_groups = [\"lockfiles\"]\n",
+    )
+    .unwrap();
+
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .arg("--list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main.rs"))
+        .stdout(predicate::str::contains("logo.svg"))
+        .stdout(predicate::str::contains("debug.log").not())
+        .stdout(predicate::str::contains("yarn.lock").not());
+
+    // The flag replaces the configured groups; the config's own list stays.
+    cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(config_dir.path().join("dump.toml"))
+        .args(["--list", "--skip-groups", "images"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("yarn.lock"))
+        .stdout(predicate::str::contains("logo.svg").not())
+        .stdout(predicate::str::contains("debug.log").not());
+}
+
+#[test]
+fn unknown_skip_group_names_the_available_groups() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.rs", "fn a() {}\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--skip-groups", "videos"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown skip group 'videos'"));
+}
+
+#[test]
+fn list_groups_prints_each_group_and_its_entries() {
+    cmd()
+        .arg("--list-groups")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "ci-config: CI pipeline definitions\n",
+        ))
+        .stdout(predicate::str::contains(
+            "\nfonts: Font files\n  \
+             skip_extensions = [\"ttf\", \"otf\", \"woff\", \"woff2\", \"eot\"]\n",
+        ));
+}
+
+// ── --filter-cmd ───────────────────────────────────────────────────────────
+
+#[cfg(unix)]
+#[test]
+fn filter_cmd_rewrites_each_file_before_printing() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--no-bat", "--filter-cmd", "tr a-z A-Z"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("HELLO\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_filter_cmd_warns_and_prints_the_file_unchanged() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.txt", "hello\n")]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--no-bat", "--filter-cmd", "exit 3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello\n"))
+        .stderr(predicate::str::contains("filter command failed on"));
+}
+
+// ── Overlapping roots ──────────────────────────────────────────────────────
+
+#[test]
+fn overlapping_roots_print_each_file_once() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/a.rs", "fn a() {}\n"),
+        ("src/walker/b.rs", "fn b() {}\n"),
+    ]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path().join("src"))
+        .arg(dir.path().join("src/walker"))
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--summary"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("fn b() {}").count(), 1);
+    assert!(stdout.contains("2 files"), "{stdout}");
+    assert!(stdout.contains("1 duplicate path skipped"), "{stdout}");
+}
+
+#[cfg(unix)]
+#[test]
+fn a_followed_symlinked_subdirectory_is_printed_once() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("lib/inner.rs", "fn inner() {}\n")]);
+    std::os::unix::fs::symlink("lib", dir.path().join("lib_link")).unwrap();
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .args(["--no-bat", "--symlinks", "follow", "--list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("inner.rs").count(), 1, "{stdout}");
+    assert!(stdout.contains("lib/inner.rs"));
+}
+
+#[test]
+fn a_directory_and_a_file_inside_it_print_the_file_once_in_first_seen_order() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let output = cmd()
+        .arg(dir.path().join("b.rs"))
+        .arg(dir.path())
+        .arg(dir.path())
+        .arg("--config")
+        .arg(dir.path().join("dump.toml"))
+        .arg("--no-bat")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.matches("fn a() {}").count(), 1);
+    assert_eq!(stdout.matches("fn b() {}").count(), 1);
+    assert!(stdout.find("fn b() {}").unwrap() < stdout.find("fn a() {}").unwrap());
+}
+
+// ── --threads ──────────────────────────────────────────────────────────────
+
+#[test]
+fn threads_walk_in_parallel_without_changing_the_output() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.rs", "fn a() {}\n"),
+        ("a/b.rs", "fn b() {}\n"),
+        ("c/d/e.rs", "fn e() {}\n"),
+        ("c/f.rs", "fn f() {}\n"),
+    ]);
+    fs::write(dir.path().join("dump.toml"), no_filter_toml()).unwrap();
+
+    let run = |threads: &str| {
+        let output = cmd()
+            .arg(dir.path())
+            .arg("--config")
+            .arg(dir.path().join("dump.toml"))
+            .args(["--no-bat", "--threads", threads])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+
+    assert_eq!(run("4"), run("1"));
+    assert_eq!(run("0"), run("1"));
+}
+
+// ── --max-depth ────────────────────────────────────────────────────────────
+
+#[test]
+fn max_depth_one_keeps_root_files_and_drops_nested_ones() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("Cargo.toml", "[package]\n"),
+        ("src/deep/file.rs", "fn deep() {}\n"),
+    ]);
+
+    cmd()
+        .arg(dir.path())
+        .args(["--no-filter", "--list", "--max-depth", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cargo.toml"))
+        .stdout(predicate::str::contains("file.rs").not());
+}
+
+#[test]
+fn max_depth_always_includes_a_file_argument() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[("src/deep/file.rs", "fn deep() {}\n")]);
+
+    cmd()
+        .arg(dir.path().join("src/deep/file.rs"))
+        .args(["--no-filter", "--list", "--max-depth", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("file.rs"));
+}
+
+// ── --sort / --reverse ─────────────────────────────────────────────────────
+
+#[test]
+fn sort_size_lists_smallest_first_and_reverse_flips_it() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("big.txt", "0123456789\n"),
+        ("mid.txt", "01234\n"),
+        ("small.txt", "0\n"),
+    ]);
+
+    let list = |extra: &[&str]| {
+        let output = cmd()
+            .arg(dir.path())
+            .args(["--no-filter", "--list", "--sort", "size"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                Path::new(line)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(list(&[]), ["small.txt", "mid.txt", "big.txt"]);
+    assert_eq!(list(&["--reverse"]), ["big.txt", "mid.txt", "small.txt"]);
+}
+
+#[test]
+fn sort_size_orders_files_within_each_root() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a/big.txt", "0123456789\n"),
+        ("a/small.txt", "0\n"),
+        ("b/mid.txt", "01234\n"),
+    ]);
+
+    let output = cmd()
+        .arg(dir.path().join("a"))
+        .arg(dir.path().join("b"))
+        .args(["--no-filter", "--list", "--sort", "size", "--limit", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let names = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            Path::new(line)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["small.txt", "big.txt"]);
+
+    cmd()
+        .arg(dir.path().join("a"))
+        .arg(dir.path().join("b"))
+        .args(["--no-filter", "--sort", "size"])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| {
+            let small = out.find("small.txt").unwrap();
+            let mid = out.find("mid.txt").unwrap();
+            let big = out.find("big.txt").unwrap();
+            small < big && big < mid
+        }));
+}
@@ -0,0 +1,413 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+use crate::{
+    errors::{ArchiveSnafu, ArchiveTooLargeSnafu, DumpResult},
+    filter::Filter,
+};
+
+/// Joins an archive's path and an entry's name in the paths [`read_entries`]
+/// returns: `archive.zip!/src/main.rs`.
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// Entries larger than this are left out even without `skip_larger_than`, so a
+/// small archive that inflates to gigabytes cannot exhaust memory.
+pub const MAX_ENTRY_BYTES: u64 = 256 * 1024 * 1024;
+
+/// The most [`read_entries`] keeps from one archive, all entries together;
+/// past it the archive is refused rather than held in memory.
+pub const MAX_ARCHIVE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// A file inside an archive, named but not read; see [`entry_names`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveName {
+    /// `archive!/name`, as the dump shows it.
+    pub path: PathBuf,
+    /// The size the entry's header gives.
+    pub size: u64,
+}
+
+/// A file inside an archive, read into memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// `archive!/name`, as the dump shows it.
+    pub path: PathBuf,
+    pub content: Vec<u8>,
+}
+
+enum Kind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn kind(path: &Path) -> Option<Kind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else {
+        None
+    }
+}
+
+/// Whether `path` names an archive [`read_entries`] can open, going by its
+/// extension: `.zip`, `.tar`, `.tar.gz` or `.tgz`.
+pub fn is_archive(path: &Path) -> bool {
+    kind(path).is_some()
+}
+
+/// The files in the archive at `path` that pass `filter`, sorted the way the
+/// walk orders files. Entry names are filtered like paths below a walked root,
+/// and only entries they keep are read. The size rules go by the entry header,
+/// then by the bytes actually read, never more than [`MAX_ENTRY_BYTES`]; the
+/// content rules look at those bytes. Directories, links, and entries whose
+/// name would lead outside the archive are left out. Fails with
+/// `ArchiveTooLarge` once the kept entries pass [`MAX_ARCHIVE_BYTES`].
+pub fn read_entries(path: &Path, filter: &Filter) -> DumpResult<Vec<ArchiveEntry>> {
+    read_entries_within(path, filter, MAX_ARCHIVE_BYTES)
+}
+
+/// [`read_entries`], keeping at most `cap` bytes in all.
+fn read_entries_within(path: &Path, filter: &Filter, cap: u64) -> DumpResult<Vec<ArchiveEntry>> {
+    let mut left = cap;
+    let mut too_large = false;
+    let files = visit_entries(path, |name, size, entry| {
+        let Some(content) = read_entry(name, size, entry, filter)? else {
+            return Ok(None);
+        };
+        match left.checked_sub(content.len() as u64) {
+            Some(rest) => left = rest,
+            None => {
+                too_large = true;
+                return Err(io::Error::other("too large"));
+            },
+        }
+        Ok(Some(content))
+    });
+    if too_large {
+        return ArchiveTooLargeSnafu {
+            path: path.display().to_string(),
+            limit: cap,
+        }
+        .fail();
+    }
+    Ok(files?
+        .into_iter()
+        .map(|(path, content)| ArchiveEntry {
+            path,
+            content,
+        })
+        .collect())
+}
+
+/// The files in the archive at `path` that pass `filter`'s name and size rules,
+/// sorted like [`read_entries`], without reading their contents, as for
+/// `--list`. The content rules (binary, content patterns, ...) are not applied,
+/// so this may name entries a dump would leave out.
+pub fn entry_names(path: &Path, filter: &Filter) -> DumpResult<Vec<ArchiveName>> {
+    let files = visit_entries(path, |name, size, _| {
+        let kept = filter.name_skip_reason(name).is_none() && size <= size_limit(name, filter);
+        Ok(kept.then_some(size))
+    })?;
+    Ok(files
+        .into_iter()
+        .map(|(path, size)| ArchiveName {
+            path,
+            size,
+        })
+        .collect())
+}
+
+/// Call `keep` with each file entry of the archive at `path`: its name, the
+/// size its header gives, and its reader. Returns what it kept, under the
+/// shown `archive!/name` paths, sorted by name.
+fn visit_entries<T>(
+    path: &Path,
+    keep: impl FnMut(&Path, u64, &mut dyn Read) -> io::Result<Option<T>>,
+) -> DumpResult<Vec<(PathBuf, T)>> {
+    let failed = |message: String| {
+        ArchiveSnafu {
+            path: path.display().to_string(),
+            message,
+        }
+        .build()
+    };
+    let file = File::open(path).map_err(|e| failed(e.to_string()))?;
+    let mut files = match kind(path) {
+        Some(Kind::Zip) => zip_entries(file, keep),
+        Some(Kind::Tar) => tar_entries(file, keep),
+        Some(Kind::TarGz) => tar_entries(GzDecoder::new(file), keep),
+        None => Ok(Vec::new()),
+    }
+    .map_err(|e| failed(e.to_string()))?;
+
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files
+        .into_iter()
+        .map(|(name, kept)| {
+            let shown = format!("{}{ENTRY_SEPARATOR}{}", path.display(), name.display());
+            (PathBuf::from(shown), kept)
+        })
+        .collect())
+}
+
+fn zip_entries<T>(
+    file: File,
+    mut keep: impl FnMut(&Path, u64, &mut dyn Read) -> io::Result<Option<T>>,
+) -> io::Result<Vec<(PathBuf, T)>> {
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let size = entry.size();
+        if let Some(kept) = keep(&name, size, &mut entry)? {
+            files.push((name, kept));
+        }
+    }
+    Ok(files)
+}
+
+fn tar_entries<T>(
+    reader: impl Read,
+    mut keep: impl FnMut(&Path, u64, &mut dyn Read) -> io::Result<Option<T>>,
+) -> io::Result<Vec<(PathBuf, T)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.into_owned();
+        let enclosed = name
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !enclosed {
+            continue;
+        }
+        let size = entry.header().size()?;
+        if let Some(kept) = keep(&name, size, &mut entry)? {
+            files.push((name, kept));
+        }
+    }
+    Ok(files)
+}
+
+/// The entry's bytes, unless `filter` leaves it out. `size` is what the
+/// entry's header claims; the read stops one byte past the limit whatever
+/// the header says.
+fn read_entry(
+    name: &Path,
+    size: u64,
+    entry: &mut dyn Read,
+    filter: &Filter,
+) -> io::Result<Option<Vec<u8>>> {
+    if filter.name_skip_reason(name).is_some() {
+        return Ok(None);
+    }
+    let limit = size_limit(name, filter);
+    if size > limit {
+        return Ok(None);
+    }
+    let mut content = Vec::new();
+    entry.take(limit + 1).read_to_end(&mut content)?;
+    if content.len() as u64 > limit {
+        return Ok(None);
+    }
+    Ok(filter
+        .content_skip_reason(name, &content)
+        .is_none()
+        .then_some(content))
+}
+
+/// The most bytes the entry `name` may hold: `skip_larger_than`, unless
+/// `always_include` names it, and never more than [`MAX_ENTRY_BYTES`].
+fn size_limit(name: &Path, filter: &Filter) -> u64 {
+    match filter.skip_larger_than() {
+        Some(limit) if !filter.always_includes(name) => limit.min(MAX_ENTRY_BYTES),
+        _ => MAX_ENTRY_BYTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::GzEncoder};
+    use tempfile::TempDir;
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use super::*;
+    use crate::config::AppConfig;
+
+    const FILES: &[(&str, &[u8])] = &[
+        ("src/main.rs", b"fn main() {}\n"),
+        ("notes.txt", b"hello\n"),
+        ("node_modules/pkg/index.js", b"module.exports = 1;\n"),
+        ("logo.bin", b"\x00\x01\x02\x03binary"),
+    ];
+
+    fn filter() -> Filter {
+        Filter::new(&AppConfig::default()).unwrap()
+    }
+
+    fn names(entries: &[ArchiveEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn zip_entries_are_filtered_and_sorted() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fixture.zip");
+        let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+        zip.add_directory("src/", SimpleFileOptions::default())
+            .unwrap();
+        for (name, content) in FILES {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        std::fs::write(&path, zip.finish().unwrap().into_inner()).unwrap();
+
+        let entries = read_entries(&path, &filter()).unwrap();
+        let shown = path.display();
+        assert_eq!(names(&entries), [
+            format!("{shown}!/notes.txt"),
+            format!("{shown}!/src/main.rs"),
+        ]);
+        assert_eq!(entries[1].content, b"fn main() {}\n");
+    }
+
+    #[test]
+    fn archives_inflating_past_the_cap_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fixture.tar");
+        std::fs::write(&path, tar_of(FILES)).unwrap();
+
+        let kept: u64 = [FILES[0].1, FILES[1].1]
+            .iter()
+            .map(|c| c.len() as u64)
+            .sum();
+        assert_eq!(
+            read_entries_within(&path, &filter(), kept).unwrap().len(),
+            2
+        );
+        assert!(matches!(
+            read_entries_within(&path, &filter(), kept - 1),
+            Err(crate::DumpError::ArchiveTooLarge { limit, .. }) if limit == kept - 1
+        ));
+    }
+
+    #[test]
+    fn entry_names_list_without_the_content_rules() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fixture.tar");
+        std::fs::write(&path, tar_of(FILES)).unwrap();
+
+        let listed = entry_names(&path, &filter()).unwrap();
+        let shown = path.display();
+        assert_eq!(listed, [
+            ArchiveName {
+                path: format!("{shown}!/logo.bin").into(),
+                size: 10,
+            },
+            ArchiveName {
+                path: format!("{shown}!/notes.txt").into(),
+                size: 6,
+            },
+            ArchiveName {
+                path: format!("{shown}!/src/main.rs").into(),
+                size: 13,
+            },
+        ]);
+    }
+
+    fn tar_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        tar.into_inner().unwrap()
+    }
+
+    #[test]
+    fn gzipped_tar_entries_are_read_too() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fixture.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, content) in FILES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        std::fs::write(&path, tar.into_inner().unwrap().finish().unwrap()).unwrap();
+
+        let entries = read_entries(&path, &filter()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[1].path.ends_with("main.rs"));
+    }
+
+    /// An entry that must not be read.
+    struct Unread;
+
+    impl Read for Unread {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            panic!("entry was read")
+        }
+    }
+
+    #[test]
+    fn entries_are_left_out_by_name_and_size_before_they_are_read() {
+        let by_name = read_entry(
+            Path::new("node_modules/pkg/index.js"),
+            1,
+            &mut Unread,
+            &filter(),
+        );
+        assert_eq!(by_name.unwrap(), None);
+
+        let small = Filter::builder().skip_larger_than("10").build().unwrap();
+        let by_header = read_entry(Path::new("big.txt"), 1 << 40, &mut Unread, &small);
+        assert_eq!(by_header.unwrap(), None);
+        // A header that understates the size gets no more than the limit read.
+        let endless = read_entry(Path::new("big.txt"), 1, &mut io::repeat(b'a'), &small);
+        assert_eq!(endless.unwrap(), None);
+        let tiny = read_entry(Path::new("tiny.txt"), 4, &mut &b"tiny"[..], &small);
+        assert_eq!(tiny.unwrap(), Some(b"tiny".to_vec()));
+    }
+
+    #[test]
+    fn a_corrupt_archive_is_a_typed_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.zip");
+        std::fs::write(&path, b"not a zip").unwrap();
+        assert!(matches!(
+            read_entries(&path, &filter()),
+            Err(crate::DumpError::Archive { .. })
+        ));
+        assert!(is_archive(Path::new("a.TGZ")) && !is_archive(Path::new("a.gz")));
+    }
+}
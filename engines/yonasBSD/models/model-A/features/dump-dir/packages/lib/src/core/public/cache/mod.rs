@@ -0,0 +1,244 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use sha2::{Digest, Sha256};
+
+/// Mixed into every key, so a change to the entry layout misses old entries
+/// instead of misreading them.
+const FORMAT: &[u8] = b"dump-dir content cache v2";
+
+/// Bytes before the MIME type in an entry: the digest, the size (LE), then the
+/// MIME type's length; the content follows the MIME type.
+const HEADER_LEN: usize = 32 + 8 + 1;
+
+/// How large the entries in a cache directory may grow in total before
+/// [`ContentCache::prune`] removes the least recently used ones.
+pub const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Decoded file contents kept across runs in `[printer] cache_dir`, keyed by
+/// path, modification time and size, so an unchanged file is neither read nor
+/// decoded again. A hit refreshes its entry's modification time, and
+/// [`ContentCache::prune`] keeps the directory under [`MAX_CACHE_BYTES`].
+#[derive(Debug, Clone)]
+pub struct ContentCache {
+    dir: PathBuf,
+}
+
+/// Names the entry for one version of a file; see [`ContentCache::key`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey(String);
+
+/// A cache entry: the decoded content, plus what the printer would otherwise
+/// compute from the raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedFile {
+    /// SHA-256 of the raw bytes, for `dedupe_content`.
+    pub digest: [u8; 32],
+    /// Size of the file on disk.
+    pub size: u64,
+    /// The MIME type sniffed from the raw bytes, if recognized.
+    pub mime: Option<String>,
+    pub content: String,
+}
+
+impl ContentCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+        }
+    }
+
+    /// The key for `path` as it is now, from one `fs::metadata` call, or `None`
+    /// if it has no usable modification time.
+    pub fn key(&self, path: &Path) -> Option<CacheKey> {
+        let meta = fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        let path = std::path::absolute(path).ok()?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(FORMAT);
+        hasher.update(path.as_os_str().as_encoded_bytes());
+        hasher.update([0]);
+        hasher.update(mtime.as_nanos().to_le_bytes());
+        hasher.update(meta.len().to_le_bytes());
+        let hex = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        Some(CacheKey(hex))
+    }
+
+    /// The entry stored under `key`, if there is a well-formed one.
+    pub fn get(&self, key: &CacheKey) -> Option<CachedFile> {
+        let path = self.dir.join(&key.0);
+        let mut header = fs::read(&path).ok()?;
+        if header.len() < HEADER_LEN {
+            return None;
+        }
+        let mime_len = usize::from(header[HEADER_LEN - 1]);
+        let mut mime = header.split_off(HEADER_LEN);
+        if mime.len() < mime_len {
+            return None;
+        }
+        let content = String::from_utf8(mime.split_off(mime_len)).ok()?;
+        let mime = String::from_utf8(mime).ok()?;
+        let (digest, size) = header.split_at(32);
+        let file = CachedFile {
+            digest: digest.try_into().ok()?,
+            size: u64::from_le_bytes(size[..8].try_into().ok()?),
+            mime: (!mime.is_empty()).then_some(mime),
+            content,
+        };
+        // Marks the entry as recently used, for `prune`.
+        let _ = fs::File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|entry| entry.set_modified(SystemTime::now()));
+        Some(file)
+    }
+
+    /// Store `content` (with the raw file's `digest`, `size` and sniffed
+    /// `mime` type) under `key`. Written to a temporary file and renamed into
+    /// place, so a concurrent or interrupted run never sees half an entry.
+    pub fn put(
+        &self,
+        key: &CacheKey,
+        digest: &[u8; 32],
+        size: u64,
+        mime: Option<&str>,
+        content: &str,
+    ) -> io::Result<()> {
+        let mime = mime
+            .filter(|mime| mime.len() <= usize::from(u8::MAX))
+            .unwrap_or_default();
+        let mut entry = Vec::with_capacity(HEADER_LEN + mime.len() + content.len());
+        entry.extend_from_slice(digest);
+        entry.extend_from_slice(&size.to_le_bytes());
+        entry.push(mime.len() as u8);
+        entry.extend_from_slice(mime.as_bytes());
+        entry.extend_from_slice(content.as_bytes());
+
+        let tmp = self
+            .dir
+            .join(format!("{}.{}.tmp", key.0, std::process::id()));
+        fs::write(&tmp, entry)?;
+        fs::rename(&tmp, self.dir.join(&key.0)).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+
+    /// Remove the least recently used entries until those left take at most
+    /// `max_bytes`. Only files named like an entry are looked at, so nothing
+    /// else kept in the directory is touched.
+    pub fn prune(&self, max_bytes: u64) -> io::Result<()> {
+        let mut entries: Vec<(SystemTime, u64, PathBuf)> = fs::read_dir(&self.dir)?
+            .flatten()
+            .filter(|entry| is_entry_name(&entry.file_name().to_string_lossy()))
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+        let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+/// True for the name of an entry: a key, 64 lowercase hex digits.
+fn is_entry_name(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn round_trips_an_entry() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello\n").unwrap();
+        let cache = ContentCache::new(dir.path());
+
+        let key = cache.key(&file).unwrap();
+        assert_eq!(cache.get(&key), None);
+        cache
+            .put(&key, &[7; 32], 6, Some("text/x-test"), "hello\n")
+            .unwrap();
+        assert_eq!(
+            cache.get(&key),
+            Some(CachedFile {
+                digest: [7; 32],
+                size: 6,
+                mime: Some("text/x-test".into()),
+                content: "hello\n".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn prune_drops_the_least_recently_used_entries() {
+        let dir = TempDir::new().unwrap();
+        let cache = ContentCache::new(dir.path());
+        let keep = dir.path().join("notes.txt");
+        fs::write(&keep, "not an entry").unwrap();
+        let key = |n: u8| CacheKey(format!("{n:064x}"));
+        for n in 0..3 {
+            cache.put(&key(n), &[n; 32], 1, None, "x").unwrap();
+            let entry = fs::File::options()
+                .append(true)
+                .open(dir.path().join(&key(n).0))
+                .unwrap();
+            entry
+                .set_modified(UNIX_EPOCH + std::time::Duration::from_secs(u64::from(n) + 1))
+                .unwrap();
+        }
+        // A hit counts as a use, so entry 0 outlives entry 1.
+        assert!(cache.get(&key(0)).is_some());
+
+        let entry_len = (HEADER_LEN + 1) as u64;
+        cache.prune(2 * entry_len).unwrap();
+        assert!(cache.get(&key(0)).is_some());
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(2)).is_some());
+        assert!(keep.exists());
+    }
+
+    #[test]
+    fn key_changes_with_size_and_path() {
+        let dir = TempDir::new().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        fs::write(&a, "one").unwrap();
+        fs::copy(&a, &b).unwrap();
+        let cache = ContentCache::new(dir.path());
+
+        let before = cache.key(&a).unwrap();
+        assert_ne!(cache.key(&b), Some(before.clone()));
+        fs::write(&a, "one more").unwrap();
+        assert_ne!(cache.key(&a), Some(before));
+    }
+
+    #[test]
+    fn malformed_entries_miss() {
+        let dir = TempDir::new().unwrap();
+        let cache = ContentCache::new(dir.path());
+        let key = CacheKey("short".into());
+        fs::write(dir.path().join("short"), b"too short").unwrap();
+        assert_eq!(cache.get(&key), None);
+    }
+}
@@ -1,37 +1,374 @@
 use std::path::{Path, PathBuf};
 
-use config::{Config as ConfigRs, File, FileFormat};
+use config::{Config as ConfigRs, Environment, File, FileFormat, Map};
 use dirs::home_dir;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 
-use crate::errors::{ConfigLoadSnafu, ConfigNotFoundSnafu, DumpResult};
+use crate::{
+    enums::{Order, OutputFormat, Symlinks},
+    errors::{
+        ConfigLoadSnafu, ConfigNotFoundSnafu, DumpResult, IoSnafu, LocalFilterCmdSnafu,
+        PathNotFoundSnafu,
+    },
+    presets,
+    utils::encoding::decode,
+};
 
 /// The resolved, merged configuration.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 #[serde(default)]
 pub struct AppConfig {
-    /// File extensions to skip (without leading dot), e.g. ["snap", "lock"]
+    /// File extensions to skip (without leading dot), e.g. ["snap", "lock"]. A
+    /// dotfile with no other dot counts its name as the extension: "gitignore"
+    /// matches `.gitignore`.
     pub skip_extensions: Vec<String>,
 
-    /// Filename patterns to skip (regex), e.g. [".*\.test\.rs$"]
+    /// Path patterns to skip (regex, matched relative to the walked root), e.g. [".*\.test\.rs$"]
     pub skip_patterns: Vec<String>,
 
-    /// Exact filenames to skip (case-insensitive), e.g. ["license", "makefile"]
+    /// Filenames to skip (case-insensitive), matched against both the file's stem
+    /// and its full name, e.g. ["license"] also skips `LICENSE.md`
     pub skip_filenames: Vec<String>,
 
+    /// Complete filenames to skip (case-insensitive), dot and extension included,
+    /// e.g. ["package-lock.json", ".env.local"]; `package-lock.rs` is kept
+    pub skip_filenames_exact: Vec<String>,
+
     /// Path component names that cause a file to be skipped if any component matches.
     /// e.g. [".github", ".git", "node_modules"]
     pub skip_path_components: Vec<String>,
 
-    /// Glob patterns matched against the full file path, e.g. ["**/target/**", "**/*.min.js"]
+    /// Glob patterns matched against the path relative to the walked root, or else the
+    /// full path, e.g. ["src/generated/**", "**/target/**", "**/*.min.js"].
+    /// A `!`-prefixed entry is an exception: a path it matches is never skipped by
+    /// a glob, whatever the order of entries. Other skip rules still apply.
     pub skip_globs: Vec<String>,
 
     /// If true, skip files detected as binary by MIME sniffing
     pub skip_binary: bool,
 
-    /// If true, skip hidden files and directories (any component starting with '.')
+    /// Sniffed MIME types to skip, by prefix, e.g. ["image/", "application/zip"],
+    /// whether or not `skip_binary` is on. Also accepted as `skip_mime_types`.
+    #[serde(alias = "skip_mime_types")]
+    pub skip_mime_prefixes: Vec<String>,
+
+    /// Sniffed MIME types to keep, by prefix, e.g. ["application/pdf"]. When
+    /// set, a recognized type matching no entry is skipped, except `text/`
+    /// types, which are always kept. With either list set, a recognized type
+    /// they let through is not skipped as binary; a file whose type isn't
+    /// recognized still gets the null byte check.
+    pub include_mime_prefixes: Vec<String>,
+
+    /// If true, skip hidden files and directories (any component starting with '.',
+    /// and on Windows anything with the hidden attribute)
     pub skip_hidden: bool,
+
+    /// If true, skip generated code: well-known generated file names
+    /// (`*_pb2.py`, `*.pb.go`, ...) and files whose first 2KB contain one of
+    /// `generated_markers`.
+    pub skip_generated: bool,
+
+    /// Markers that flag a file as generated, matched case-insensitively.
+    pub generated_markers: Vec<String>,
+
+    /// If true, skip minified and bundled assets among `minified_extensions`:
+    /// judged on the first 8KB, a file whose lines average more than
+    /// `minified_line_length` bytes, or that packs over 5KB into fewer than 3 lines.
+    pub skip_minified: bool,
+
+    /// Average line length, in bytes, above which a file counts as minified.
+    pub minified_line_length: usize,
+
+    /// Extensions (without leading dot) `skip_minified` applies to, e.g. add
+    /// "json" for single-line data files.
+    pub minified_extensions: Vec<String>,
+
+    /// Regexes matched against file content, e.g. "PROPRIETARY". Checked last,
+    /// and only for files no larger than `content_scan_limit`.
+    pub skip_content_patterns: Vec<String>,
+
+    /// Largest file `skip_content_patterns` are scanned in, e.g. "1MiB"; bigger
+    /// files are kept unscanned.
+    pub content_scan_limit: String,
+
+    /// Skip files larger than this human-readable size, e.g. "500k" or "2MiB".
+    pub skip_larger_than: Option<String>,
+
+    /// Skip files with more lines than this, however few bytes they take; 0
+    /// disables the check. Also read as `skip_max_lines`. A file it lets through
+    /// is printed from the read that counted its lines.
+    #[serde(alias = "skip_max_lines")]
+    pub max_lines_per_file: usize,
+
+    /// Skip files last modified before this: a duration back from now ("7d",
+    /// "36h") or an RFC 3339 date ("2024-03-05", "2024-03-05T12:00:00Z").
+    pub changed_since: Option<String>,
+
+    /// Skip files last modified at or after this; same formats as `changed_since`.
+    pub changed_before: Option<String>,
+
+    /// If true, `.dumpignore` files (gitignore syntax) exclude paths during the walk,
+    /// layered over `.gitignore` and nested the same way.
+    pub respect_dumpignore: bool,
+
+    /// If true, `.gitignore`, `.git/info/exclude` and the global git excludes
+    /// exclude paths during the walk; the filter settings apply either way.
+    pub respect_gitignore: bool,
+
+    /// Further ignore-file names (gitignore syntax) to honour like `.dumpignore`,
+    /// e.g. [".aiignore"]. `.ignore` files are always honoured.
+    pub custom_ignore_files: Vec<String>,
+
+    /// If true, a `dump.toml` found below a walked root applies its filter settings
+    /// to that subtree, on top of the settings in effect above it.
+    pub nested_configs: bool,
+
+    /// Symbolic links: "skip" (leave them out), "follow" (walk and print their
+    /// targets, with cycle detection) or "list" (a `-> target` line each). A
+    /// layer may set it as `follow_links` instead: `true` means "follow" and
+    /// `false` "skip"; see [`SymlinkKeys`].
+    pub symlinks: Symlinks,
+
+    /// Number of threads walking each root. 1 walks serially, 0 uses one thread
+    /// per available CPU. The file order is the same either way.
+    pub threads: usize,
+
+    /// How many levels below each root the walk descends: 1 is the root's own
+    /// entries only. A file given as a root is always included. Unset for no limit.
+    pub max_depth: Option<usize>,
+
+    /// Allowlist of extensions (without leading dot). When any `include_*` list is
+    /// non-empty, a file must match at least one include rule; skip rules still apply.
+    pub include_extensions: Vec<String>,
+
+    /// Allowlist of exact filenames (case-insensitive), e.g. ["cargo.toml"].
+    pub include_filenames: Vec<String>,
+
+    /// Allowlist of globs, matched like `skip_globs`. Never prunes directories.
+    pub include_globs: Vec<String>,
+
+    /// Allowlist of languages by name, e.g. ["rust", "toml", "make"]: each stands
+    /// for its extensions and well-known file names. Works like the `include_*` lists.
+    pub include_languages: Vec<String>,
+
+    /// Languages to skip, named as in `include_languages`, e.g. ["docker"].
+    pub skip_languages: Vec<String>,
+
+    /// Built-in groups of skip rules to add to the lists above, e.g.
+    /// ["lockfiles", "images"]; `dump-dir --list-groups` shows each one's entries.
+    pub skip_groups: Vec<String>,
+
+    /// Paths relative to the walked root, or globs matched like `skip_globs`, that
+    /// are dumped whatever the skip and include rules say, e.g. ["README.md",
+    /// "crates/cli/Cargo.lock"]. The directories along their literal leading
+    /// components are never pruned, so a pattern starting with a wildcard, like
+    /// "**/Cargo.lock", only matches files in directories the walk still visits.
+    /// Ignore files still apply; see `respect_gitignore`.
+    pub always_include: Vec<String>,
+
+    /// Built-in filter sets to add, e.g. ["rust", "node"]; `dump-dir
+    /// --list-presets` shows each one's entries. They are added to the skip lists
+    /// after every config file and the environment, so a plain list set there
+    /// keeps them too.
+    pub presets: Vec<String>,
+
+    /// Order in which files under each root are printed: "lexical" (or "name"),
+    /// "breadth", "size" (smallest first), "mtime" (newest first) or "ext"
+    pub order: Order,
+
+    /// If true, files under each root are printed in the reverse of `order`.
+    pub reverse_order: bool,
+
+    /// Text file emitted verbatim before the first file header, e.g. review instructions.
+    pub prompt_prefix_file: Option<PathBuf>,
+
+    /// Text file emitted verbatim after the last file, before the summary.
+    pub prompt_suffix_file: Option<PathBuf>,
+
+    /// Matching settings, read from the `[filter]` table.
+    pub filter: FilterConfig,
+
+    /// How `skip_binary` decides, read from the `[binary]` table.
+    pub binary: BinaryConfig,
+
+    /// Output settings, read from the `[printer]` table.
+    pub printer: PrinterConfig,
+}
+
+/// Settings that control how filter rules are matched.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(default)]
+pub struct FilterConfig {
+    /// Match extensions, file names, path components, regexes and globs
+    /// ignoring case. Off, `Makefile` and `makefile` are different names.
+    pub case_insensitive: bool,
+}
+
+/// Settings that control binary detection (`skip_binary`). The extension lists
+/// are consulted first; only a file neither names is opened and sniffed.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(default)]
+pub struct BinaryConfig {
+    /// Bytes read from the start of a file to sniff it.
+    pub sniff_bytes: usize,
+
+    /// Extensions that are always text, e.g. ["svg"]: never sniffed, never
+    /// skipped as binary.
+    pub always_text_extensions: Vec<String>,
+
+    /// Extensions that are always binary, e.g. ["png"]: skipped without
+    /// opening the file.
+    pub always_binary_extensions: Vec<String>,
+}
+
+/// Settings that control how file contents are rendered.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(default)]
+pub struct PrinterConfig {
+    /// What is written per file: "text", "markdown", or "manifest" (hashes only).
+    pub format: OutputFormat,
+
+    /// Start with a table of contents: a linked list in markdown, a numbered list
+    /// in text.
+    pub toc: bool,
+
+    /// Number of files read concurrently in the plain (non-bat) path.
+    /// 1 reads serially, 0 uses one thread per available CPU.
+    pub jobs: usize,
+
+    /// If true, files whose content is identical to an already printed file are
+    /// reduced to their header plus a note naming the first occurrence.
+    pub dedupe_content: bool,
+
+    /// Truncate lines longer than this many characters (plain output only).
+    pub max_line_length: Option<usize>,
+
+    /// Hard-wrap lines at this many characters (plain output only).
+    /// Ignored when `max_line_length` is set.
+    pub wrap: Option<usize>,
+
+    /// Expand leading tabs to spaces using this tab width. bat receives the width
+    /// as `--tabs` instead.
+    pub expand_tabs: Option<usize>,
+
+    /// With `expand_tabs`, also expand tabs after the indentation.
+    pub expand_all_tabs: bool,
+
+    /// Replaces `FILE: {path}` in each file header (the `##` heading text in
+    /// markdown). Placeholders: `{path}`, `{bytes}`, `{lines}`, `{ext}`.
+    pub header_template: Option<String>,
+
+    /// Start each file's content with a comment naming its path, e.g.
+    /// `// src/main.rs`, in the file's own comment syntax. Files without a known
+    /// safe syntax, and bat output, are left alone. Not counted as a line.
+    pub path_comment: bool,
+
+    /// Show paths under a walked root as `<label>/<relative path>`, hiding the real
+    /// root directory in headers, contents links and path comments.
+    pub root_label: Option<String>,
+
+    /// Highlight through bat when it is installed. When false, bat is never
+    /// probed for and output is always plain.
+    pub use_bat: bool,
+
+    /// Seconds to wait for bat on a single file before killing it and printing
+    /// the file plainly instead. 0 waits forever.
+    pub bat_timeout: u64,
+
+    /// Syntax highlighting theme, passed to bat as `--theme`. Checked at startup
+    /// against bat's theme list; ignored when bat is not installed.
+    #[serde(alias = "bat_theme")]
+    pub theme: Option<String>,
+
+    /// Show the last commit touching each file in its header. No-op outside a
+    /// git repository.
+    pub git_meta: bool,
+
+    /// Show each file's size, modification time (RFC 3339) and, on Unix, its
+    /// permission bits in its header.
+    pub metadata: bool,
+
+    /// Stop printing further files once this many lines have been printed in
+    /// total. Checked before each file, so the file that crosses it is shown whole.
+    pub max_total_lines: Option<usize>,
+
+    /// Like `max_total_lines`, but for bytes of file content.
+    pub max_total_bytes: Option<u64>,
+
+    /// Hard cap on bytes of output. The file that would cross it is cut off at
+    /// the limit and the dump aborts with `TotalSizeExceeded`.
+    pub abort_after_bytes: Option<u64>,
+
+    /// Estimate the LLM token count of the output and report it in the summary.
+    pub tokens: bool,
+
+    /// Keep decoded file contents in this directory, keyed by path, modification
+    /// time and size, and reuse them on later runs while a file is unchanged.
+    /// The least recently used entries are removed once the directory holds
+    /// more than 256 MiB. Ignored with `abort_after_bytes`.
+    pub cache_dir: Option<PathBuf>,
+
+    /// Shell command each file's content is piped through before printing, e.g.
+    /// `"sed 's/password=.*/password=***/'"`; its stdout is printed instead. If it
+    /// cannot start, exits non-zero or outlives `filter_cmd_timeout`, a warning
+    /// is printed and so is the file unchanged. Disables bat, which would read
+    /// the file itself. Only the global config, the environment and the command
+    /// line may set it: a project's `dump.toml` could run anything.
+    pub filter_cmd: Option<String>,
+
+    /// Seconds to wait for `filter_cmd` on a single file before killing it and
+    /// printing the file unchanged. 0 waits forever.
+    pub filter_cmd_timeout: u64,
+}
+
+impl Default for FilterConfig {
+    fn default() -> Self {
+        Self {
+            case_insensitive: true,
+        }
+    }
+}
+
+impl Default for BinaryConfig {
+    fn default() -> Self {
+        Self {
+            sniff_bytes: 8192,
+            always_text_extensions: vec![],
+            always_binary_extensions: vec![],
+        }
+    }
+}
+
+impl Default for PrinterConfig {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::default(),
+            toc: false,
+            jobs: 1,
+            dedupe_content: false,
+            max_line_length: None,
+            wrap: None,
+            expand_tabs: None,
+            expand_all_tabs: false,
+            header_template: None,
+            path_comment: false,
+            root_label: None,
+            use_bat: true,
+            bat_timeout: 5,
+            theme: None,
+            git_meta: false,
+            metadata: false,
+            max_total_lines: None,
+            max_total_bytes: None,
+            abort_after_bytes: None,
+            tokens: false,
+            cache_dir: None,
+            filter_cmd: None,
+            filter_cmd_timeout: 5,
+        }
+    }
 }
 
 impl Default for AppConfig {
@@ -54,6 +391,7 @@ impl Default for AppConfig {
                 "makefile".into(),
                 "dockerfile".into(),
             ],
+            skip_filenames_exact: vec![],
             skip_path_components: vec![
                 ".github".into(),
                 ".git".into(),
@@ -62,58 +400,445 @@ impl Default for AppConfig {
             ],
             skip_globs: vec![],
             skip_binary: true,
+            skip_mime_prefixes: vec![],
+            include_mime_prefixes: vec![],
             skip_hidden: true,
+            skip_generated: false,
+            generated_markers: vec![
+                "@generated".into(),
+                "do not edit".into(),
+                "code generated by".into(),
+                "autogenerated".into(),
+                "auto-generated".into(),
+            ],
+            skip_minified: false,
+            minified_line_length: 500,
+            minified_extensions: vec![
+                "js".into(),
+                "mjs".into(),
+                "cjs".into(),
+                "css".into(),
+                "map".into(),
+            ],
+            skip_content_patterns: vec![],
+            content_scan_limit: "1MiB".into(),
+            skip_larger_than: None,
+            max_lines_per_file: 0,
+            changed_since: None,
+            changed_before: None,
+            respect_dumpignore: true,
+            respect_gitignore: true,
+            custom_ignore_files: vec![],
+            nested_configs: false,
+            symlinks: Symlinks::default(),
+            threads: 1,
+            max_depth: None,
+            include_extensions: vec![],
+            include_filenames: vec![],
+            include_globs: vec![],
+            include_languages: vec![],
+            skip_languages: vec![],
+            skip_groups: vec![],
+            always_include: vec![],
+            presets: vec![],
+            order: Order::default(),
+            reverse_order: false,
+            prompt_prefix_file: None,
+            prompt_suffix_file: None,
+            filter: FilterConfig::default(),
+            binary: BinaryConfig::default(),
+            printer: PrinterConfig::default(),
+        }
+    }
+}
+
+/// The filter settings of a `dump.toml` found during the walk (`nested_configs`).
+/// Every field is optional: unset ones, and non-filter keys, leave the settings
+/// above untouched.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FilterOverrides {
+    pub skip_extensions: Option<Vec<String>>,
+    pub skip_patterns: Option<Vec<String>>,
+    pub skip_filenames: Option<Vec<String>>,
+    pub skip_filenames_exact: Option<Vec<String>>,
+    pub skip_path_components: Option<Vec<String>>,
+    pub skip_globs: Option<Vec<String>>,
+    pub skip_binary: Option<bool>,
+    pub skip_hidden: Option<bool>,
+    pub skip_generated: Option<bool>,
+    pub generated_markers: Option<Vec<String>>,
+    pub skip_minified: Option<bool>,
+    pub skip_content_patterns: Option<Vec<String>>,
+    pub skip_larger_than: Option<String>,
+    pub max_lines_per_file: Option<usize>,
+    pub include_extensions: Option<Vec<String>>,
+    pub include_filenames: Option<Vec<String>>,
+    pub include_globs: Option<Vec<String>>,
+    pub include_languages: Option<Vec<String>>,
+    pub skip_languages: Option<Vec<String>>,
+    pub skip_groups: Option<Vec<String>>,
+    pub always_include: Option<Vec<String>>,
+}
+
+impl AppConfig {
+    /// Layer a nested `dump.toml` on top: lists are extended, scalars replaced.
+    pub fn apply_filter_overrides(&mut self, overrides: FilterOverrides) {
+        for (list, extra) in [
+            (&mut self.skip_extensions, overrides.skip_extensions),
+            (&mut self.skip_patterns, overrides.skip_patterns),
+            (&mut self.skip_filenames, overrides.skip_filenames),
+            (
+                &mut self.skip_filenames_exact,
+                overrides.skip_filenames_exact,
+            ),
+            (
+                &mut self.skip_path_components,
+                overrides.skip_path_components,
+            ),
+            (&mut self.skip_globs, overrides.skip_globs),
+            (&mut self.generated_markers, overrides.generated_markers),
+            (
+                &mut self.skip_content_patterns,
+                overrides.skip_content_patterns,
+            ),
+            (&mut self.include_extensions, overrides.include_extensions),
+            (&mut self.include_filenames, overrides.include_filenames),
+            (&mut self.include_globs, overrides.include_globs),
+            (&mut self.include_languages, overrides.include_languages),
+            (&mut self.skip_languages, overrides.skip_languages),
+            (&mut self.skip_groups, overrides.skip_groups),
+            (&mut self.always_include, overrides.always_include),
+        ] {
+            list.extend(extra.unwrap_or_default());
+        }
+        if let Some(skip_binary) = overrides.skip_binary {
+            self.skip_binary = skip_binary;
+        }
+        if let Some(skip_hidden) = overrides.skip_hidden {
+            self.skip_hidden = skip_hidden;
+        }
+        if let Some(skip_generated) = overrides.skip_generated {
+            self.skip_generated = skip_generated;
+        }
+        if let Some(skip_minified) = overrides.skip_minified {
+            self.skip_minified = skip_minified;
+        }
+        if overrides.skip_larger_than.is_some() {
+            self.skip_larger_than = overrides.skip_larger_than;
+        }
+        if let Some(max_lines) = overrides.max_lines_per_file {
+            self.max_lines_per_file = max_lines;
+        }
+    }
+}
+
+/// The symlink mode as one config layer sets it: `symlinks`, or the
+/// `follow_links` flag, which `symlinks` wins over in the same layer.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SymlinkKeys {
+    pub symlinks: Option<Symlinks>,
+    pub follow_links: Option<bool>,
+}
+
+impl AppConfig {
+    /// Apply one layer's [`SymlinkKeys`] on top of this config.
+    pub fn apply_symlink_keys(&mut self, keys: SymlinkKeys) {
+        let follow = keys.follow_links.map(|follow| {
+            if follow {
+                Symlinks::Follow
+            } else {
+                Symlinks::Skip
+            }
+        });
+        if let Some(mode) = keys.symlinks.or(follow) {
+            self.symlinks = mode;
+        }
+    }
+}
+
+/// The skip lists as one layer (a config file, or the command line) sets them:
+/// a plain key replaces the list from the layers below, and its `*_add`
+/// companion extends it. A layer may use both; the replacement comes first.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ListOverrides {
+    pub skip_extensions: Option<Vec<String>>,
+    pub skip_extensions_add: Vec<String>,
+    pub skip_patterns: Option<Vec<String>>,
+    pub skip_patterns_add: Vec<String>,
+    pub skip_filenames: Option<Vec<String>>,
+    pub skip_filenames_add: Vec<String>,
+    pub skip_filenames_exact: Option<Vec<String>>,
+    pub skip_filenames_exact_add: Vec<String>,
+    pub skip_path_components: Option<Vec<String>>,
+    pub skip_path_components_add: Vec<String>,
+    pub skip_globs: Option<Vec<String>>,
+    pub skip_globs_add: Vec<String>,
+}
+
+impl AppConfig {
+    /// Layer `overrides` on top of the skip lists resolved so far.
+    pub fn apply_list_overrides(&mut self, overrides: ListOverrides) {
+        for (list, replace, add) in [
+            (
+                &mut self.skip_extensions,
+                overrides.skip_extensions,
+                overrides.skip_extensions_add,
+            ),
+            (
+                &mut self.skip_patterns,
+                overrides.skip_patterns,
+                overrides.skip_patterns_add,
+            ),
+            (
+                &mut self.skip_filenames,
+                overrides.skip_filenames,
+                overrides.skip_filenames_add,
+            ),
+            (
+                &mut self.skip_filenames_exact,
+                overrides.skip_filenames_exact,
+                overrides.skip_filenames_exact_add,
+            ),
+            (
+                &mut self.skip_path_components,
+                overrides.skip_path_components,
+                overrides.skip_path_components_add,
+            ),
+            (
+                &mut self.skip_globs,
+                overrides.skip_globs,
+                overrides.skip_globs_add,
+            ),
+        ] {
+            if let Some(replace) = replace {
+                *list = replace;
+            }
+            list.extend(add);
         }
     }
 }
 
+/// Read the filter settings of a nested `dump.toml`.
+pub fn read_filter_overrides(path: &Path) -> DumpResult<FilterOverrides> {
+    ConfigRs::builder()
+        .add_source(File::from(path).format(FileFormat::Toml).required(true))
+        .build()
+        .and_then(ConfigRs::try_deserialize)
+        .context(ConfigLoadSnafu)
+}
+
 /// Load config by layering:
 ///   1. Built-in defaults (via `AppConfig::default()`)
 ///   2. Global config:  ~/.config/dump-dir/config.toml  (if it exists)
-///   3. Local config:   ./dump.toml  (or --config path)  (if it exists)
+///   3. Local config:   ./dump.toml  (if it exists), or each `--config` path in order (each must
+///      exist)
+///   4. Environment:    `DUMP_DIR_*` variables (see [`ENV_PREFIX`])
+///   5. Presets:        the lists of each preset named by `presets` (see [`presets`])
 ///
-/// Later layers override earlier ones. Arrays are replaced, not merged.
-pub fn load(local_override: Option<&Path>) -> DumpResult<AppConfig> {
-    let mut builder = ConfigRs::builder();
+/// Later layers override earlier ones. Arrays are replaced, not merged, except
+/// that the skip lists also take `*_add` keys (see [`ListOverrides`]): those are
+/// resolved afterwards, layer by layer from the defaults up, so an addition
+/// survives every layer above it that doesn't replace the list outright. Presets
+/// only ever add, so a list any layer replaces keeps their entries. The command
+/// line is the last layer; see `build_config` in the CLI.
+pub fn load(local_overrides: &[PathBuf]) -> DumpResult<AppConfig> {
+    load_with(local_overrides, None, None)
+}
+
+/// [`load`], with `presets` in place of the configured ones when given (as from
+/// `--preset`), and the environment layer read from `vars` (variable name to
+/// value) instead of the process environment when given.
+pub fn load_with(
+    local_overrides: &[PathBuf],
+    presets: Option<Vec<String>>,
+    vars: Option<Map<String, String>>,
+) -> DumpResult<AppConfig> {
+    let mut layers = Vec::new();
 
     // --- Layer 1: Global config ---
     if let Some(home) = home_dir() {
         let global: PathBuf = home.join(".config").join("dump-dir").join("config.toml");
         if global.exists() {
-            builder = builder.add_source(
-                File::from(global.as_path())
-                    .format(FileFormat::Toml)
-                    .required(false),
-            );
+            layers.push(global);
         }
     }
+    let global_layers = layers.len();
 
-    // --- Layer 2: Local config (./dump.toml or --config path) ---
-    let local_path: PathBuf = local_override
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|| PathBuf::from("dump.toml"));
+    // --- Layer 2: Local config (./dump.toml, or each --config path in order) ---
+    if local_overrides.is_empty() {
+        let local_path = Path::new("dump.toml");
+        if local_path.exists() {
+            layers.push(local_path.to_path_buf());
+        }
+    }
+    for path in local_overrides {
+        if !path.exists() {
+            // User explicitly passed --config but the file doesn't exist — typed error
+            return ConfigNotFoundSnafu {
+                path: path.display().to_string(),
+            }
+            .fail();
+        }
+        layers.push(path.clone());
+    }
 
-    if local_path.exists() {
-        builder = builder.add_source(
-            File::from(local_path.as_path())
-                .format(FileFormat::Toml)
-                .required(false),
-        );
-    } else if local_override.is_some() {
-        // User explicitly passed --config but the file doesn't exist — typed error
-        return ConfigNotFoundSnafu {
-            path: local_path.display().to_string(),
+    let source = |path: &PathBuf| File::from(path.as_path()).format(FileFormat::Toml);
+    for path in &layers[global_layers..] {
+        let local = ConfigRs::builder()
+            .add_source(source(path))
+            .build()
+            .context(ConfigLoadSnafu)?;
+        if local.get_string("printer.filter_cmd").is_ok() {
+            return LocalFilterCmdSnafu {
+                path: path.display().to_string(),
+            }
+            .fail();
         }
-        .fail();
     }
+    let env = environment(vars);
+    let raw = layers
+        .iter()
+        .fold(ConfigRs::builder(), |builder, path| {
+            builder.add_source(source(path))
+        })
+        .add_source(env.clone())
+        .build()
+        .context(ConfigLoadSnafu)?;
 
-    let raw = builder.build().context(ConfigLoadSnafu)?;
+    let mut cfg: AppConfig = raw.try_deserialize().context(ConfigLoadSnafu)?;
+    if let Some(presets) = presets {
+        cfg.presets = presets;
+    }
+    let presets = cfg
+        .presets
+        .iter()
+        .map(|name| presets::by_name(name))
+        .collect::<DumpResult<Vec<_>>>()?;
 
-    let cfg: AppConfig = raw.try_deserialize().context(ConfigLoadSnafu)?;
+    // Re-resolve the skip lists from the defaults up, one layer at a time.
+    let defaults = AppConfig::default();
+    cfg.skip_extensions = defaults.skip_extensions;
+    cfg.skip_patterns = defaults.skip_patterns;
+    cfg.skip_filenames = defaults.skip_filenames;
+    cfg.skip_filenames_exact = defaults.skip_filenames_exact;
+    cfg.skip_path_components = defaults.skip_path_components;
+    cfg.skip_globs = defaults.skip_globs;
+    // The symlink mode too, as any layer may spell it `follow_links`.
+    cfg.symlinks = defaults.symlinks;
+    let file_layers = layers
+        .iter()
+        .map(|path| ConfigRs::builder().add_source(source(path)));
+    for layer in file_layers.chain([ConfigRs::builder().add_source(env)]) {
+        let layer = layer.build().context(ConfigLoadSnafu)?;
+        cfg.apply_symlink_keys(layer.clone().try_deserialize().context(ConfigLoadSnafu)?);
+        cfg.apply_list_overrides(layer.try_deserialize().context(ConfigLoadSnafu)?);
+    }
+    for preset in &presets {
+        cfg.apply_list_overrides(preset.list_overrides());
+        cfg.include_extensions
+            .extend(preset.include_extensions.iter().map(|ext| ext.to_string()));
+    }
 
     Ok(cfg)
 }
 
+/// Prefix of the environment variables that override config keys: the key
+/// follows in upper case, `DUMP_DIR_SKIP_BINARY=false`, with `__` stepping into
+/// a table, `DUMP_DIR_PRINTER__FORMAT=markdown`. Lists are comma-separated,
+/// `DUMP_DIR_SKIP_EXTENSIONS_ADD=pdf,zip`; an empty variable counts as unset.
+pub const ENV_PREFIX: &str = "DUMP_DIR";
+
+/// The environment layer of [`load`], from `vars` or the process environment.
+fn environment(vars: Option<Map<String, String>>) -> Environment {
+    let env = Environment::with_prefix(ENV_PREFIX)
+        .prefix_separator("_")
+        .separator("__")
+        .ignore_empty(true)
+        .try_parsing(true)
+        .list_separator(",");
+    list_keys()
+        .iter()
+        .fold(env, |env, key| env.with_list_parse_key(key))
+        .source(vars)
+}
+
+/// Every list-valued key, dotted inside tables (`binary.always_text_extensions`),
+/// and its `*_add` name; only these split on commas, so a comma in any other
+/// value (a regex, say) is kept.
+fn list_keys() -> Vec<String> {
+    fn collect(prefix: &str, value: &serde_json::Value, keys: &mut Vec<String>) {
+        let Some(table) = value.as_object() else {
+            return;
+        };
+        for (name, value) in table {
+            let key = match prefix {
+                "" => name.clone(),
+                _ => format!("{prefix}.{name}"),
+            };
+            if value.is_array() {
+                keys.push(format!("{key}_add"));
+                keys.push(key);
+            } else {
+                collect(&key, value, keys);
+            }
+        }
+    }
+    let mut keys = Vec::new();
+    if let Ok(defaults) = serde_json::to_value(AppConfig::default()) {
+        collect("", &defaults, &mut keys);
+    }
+    keys
+}
+
+/// Read a prompt prefix/suffix file as text, undecodable bytes replaced.
+pub fn read_prompt_file(path: &Path) -> DumpResult<String> {
+    if !path.exists() {
+        return PathNotFoundSnafu {
+            path: path.display().to_string(),
+        }
+        .fail();
+    }
+
+    let bytes = std::fs::read(path).context(IoSnafu {
+        path: path.display().to_string(),
+    })?;
+    Ok(decode(&bytes).into_owned())
+}
+
+/// Read newline-separated glob patterns from an exclude file (for `--exclude-from`).
+///
+/// Blank lines and lines starting with `#` are ignored; surrounding whitespace is trimmed.
+pub fn read_exclude_file(path: &Path) -> DumpResult<Vec<String>> {
+    if !path.exists() {
+        return PathNotFoundSnafu {
+            path: path.display().to_string(),
+        }
+        .fail();
+    }
+
+    let content = std::fs::read_to_string(path).context(IoSnafu {
+        path: path.display().to_string(),
+    })?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// A JSON Schema (draft 2020-12) describing `dump.toml`, pretty-printed, for
+/// editors to complete and validate config files against. Field docs become
+/// descriptions. The `*_add` companion keys are not part of it.
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(AppConfig);
+    serde_json::to_string_pretty(&schema).expect("a JSON Schema serializes to JSON")
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -122,6 +847,17 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn json_schema_describes_the_config_keys() {
+        let schema: serde_json::Value = serde_json::from_str(&json_schema()).unwrap();
+        let props = &schema["properties"];
+        assert_eq!(props["skip_extensions"]["type"], "array");
+        assert_eq!(props["skip_extensions"]["items"]["type"], "string");
+        assert_eq!(props["max_lines_per_file"]["type"], "integer");
+        assert!(props["printer"].is_object());
+        assert!(schema["required"].is_null());
+    }
+
     fn write_toml(dir: &TempDir, name: &str, content: &str) -> PathBuf {
         let path = dir.path().join(name);
         fs::write(&path, content).unwrap();
@@ -147,18 +883,43 @@ mod tests {
         assert!(AppConfig::default().skip_hidden);
     }
 
+    #[test]
+    fn default_respect_dumpignore_is_true() {
+        assert!(AppConfig::default().respect_dumpignore);
+    }
+
+    #[test]
+    fn default_respect_gitignore_is_true() {
+        assert!(AppConfig::default().respect_gitignore);
+    }
+
     #[test]
     fn default_skip_globs_is_empty() {
         assert!(AppConfig::default().skip_globs.is_empty());
     }
 
+    #[test]
+    fn default_walk_is_serial() {
+        assert_eq!(AppConfig::default().threads, 1);
+    }
+
+    #[test]
+    fn default_printer_jobs_is_serial() {
+        assert_eq!(AppConfig::default().printer.jobs, 1);
+    }
+
+    #[test]
+    fn default_dedupe_content_is_false() {
+        assert!(!AppConfig::default().printer.dedupe_content);
+    }
+
     // ── Local config loading ───────────────────────────────────────────────
 
     #[test]
     fn loads_local_config_overriding_extensions() {
         let dir = TempDir::new().unwrap();
         write_toml(&dir, "dump.toml", r#"skip_extensions = ["foo", "bar"]"#);
-        let cfg = load(Some(&dir.path().join("dump.toml"))).unwrap();
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
         assert_eq!(cfg.skip_extensions, vec!["foo", "bar"]);
     }
 
@@ -166,7 +927,7 @@ mod tests {
     fn loads_local_config_skip_binary_false() {
         let dir = TempDir::new().unwrap();
         write_toml(&dir, "dump.toml", "skip_binary = false");
-        let cfg = load(Some(&dir.path().join("dump.toml"))).unwrap();
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
         assert!(!cfg.skip_binary);
     }
 
@@ -178,16 +939,63 @@ mod tests {
             "dump.toml",
             r#"skip_globs = ["**/target/**", "**/*.min.js"]"#,
         );
-        let cfg = load(Some(&dir.path().join("dump.toml"))).unwrap();
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
         assert_eq!(cfg.skip_globs.len(), 2);
         assert!(cfg.skip_globs.contains(&"**/target/**".to_string()));
     }
 
+    #[test]
+    fn loads_printer_table() {
+        let dir = TempDir::new().unwrap();
+        write_toml(&dir, "dump.toml", "[printer]\ndedupe_content = true");
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert!(cfg.printer.dedupe_content);
+        assert!(cfg.skip_binary);
+    }
+
+    #[test]
+    fn bat_theme_is_read_as_theme() {
+        let dir = TempDir::new().unwrap();
+        write_toml(&dir, "dump.toml", "[printer]\nbat_theme = \"Dracula\"");
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert_eq!(cfg.printer.theme.as_deref(), Some("Dracula"));
+    }
+
+    #[test]
+    fn loads_printer_line_limits() {
+        let dir = TempDir::new().unwrap();
+        write_toml(&dir, "dump.toml", "[printer]\nmax_line_length = 120");
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert_eq!(cfg.printer.max_line_length, Some(120));
+        assert_eq!(cfg.printer.wrap, None);
+    }
+
+    #[test]
+    fn loads_printer_budget() {
+        let dir = TempDir::new().unwrap();
+        write_toml(
+            &dir,
+            "dump.toml",
+            "[printer]\nmax_total_lines = 500\nmax_total_bytes = 65536",
+        );
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert_eq!(cfg.printer.max_total_lines, Some(500));
+        assert_eq!(cfg.printer.max_total_bytes, Some(65536));
+    }
+
+    #[test]
+    fn loads_breadth_order() {
+        let dir = TempDir::new().unwrap();
+        write_toml(&dir, "dump.toml", r#"order = "breadth""#);
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert_eq!(cfg.order, Order::Breadth);
+    }
+
     #[test]
     fn missing_explicit_config_returns_error() {
         let dir = TempDir::new().unwrap();
         let nonexistent = dir.path().join("nope.toml");
-        let result = load(Some(&nonexistent));
+        let result = load(&[nonexistent]);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains("Config file not found"));
@@ -198,9 +1006,212 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn later_config_files_override_earlier_ones() {
+        let dir = TempDir::new().unwrap();
+        let base = write_toml(
+            &dir,
+            "base.toml",
+            "skip_binary = true\nskip_extensions = [\"lock\"]",
+        );
+        let over = write_toml(&dir, "override.toml", "skip_binary = false");
+        let cfg = load(&[base, over]).unwrap();
+        assert!(!cfg.skip_binary);
+        // Keys the override doesn't set keep the base's value.
+        assert_eq!(cfg.skip_extensions, vec!["lock"]);
+    }
+
+    // ── Environment variables ──────────────────────────────────────────────
+
+    fn vars(pairs: &[(&str, &str)]) -> Option<Map<String, String>> {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn environment_overrides_config_files() {
+        let dir = TempDir::new().unwrap();
+        let local = write_toml(
+            &dir,
+            "dump.toml",
+            "skip_binary = true\nmax_lines_per_file = 10\n[printer]\nformat = \"ndjson\"",
+        );
+        let cfg = load_with(
+            &[local],
+            None,
+            vars(&[
+                ("DUMP_DIR_SKIP_BINARY", "false"),
+                ("DUMP_DIR_PRINTER__FORMAT", "markdown"),
+                ("DUMP_DIR_MAX_LINES_PER_FILE", ""),
+                ("OTHER_SKIP_HIDDEN", "false"),
+            ]),
+        )
+        .unwrap();
+        assert!(!cfg.skip_binary);
+        assert_eq!(cfg.printer.format, OutputFormat::Markdown);
+        // Empty variables and other prefixes are ignored.
+        assert_eq!(cfg.max_lines_per_file, 10);
+        assert!(cfg.skip_hidden);
+    }
+
+    #[test]
+    fn environment_lists_split_on_commas() {
+        let cfg = load_with(
+            &[],
+            None,
+            vars(&[
+                ("DUMP_DIR_SKIP_EXTENSIONS", "lock,map"),
+                ("DUMP_DIR_SKIP_GLOBS_ADD", "**/dist/**"),
+                ("DUMP_DIR_BINARY__ALWAYS_TEXT_EXTENSIONS", "svg"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(cfg.skip_extensions, vec!["lock", "map"]);
+        let mut globs = AppConfig::default().skip_globs;
+        globs.push("**/dist/**".into());
+        assert_eq!(cfg.skip_globs, globs);
+        assert_eq!(cfg.binary.always_text_extensions, vec!["svg"]);
+    }
+
+    #[test]
+    fn filter_cmd_is_refused_in_project_configs() {
+        let dir = TempDir::new().unwrap();
+        let local = write_toml(&dir, "dump.toml", "[printer]\nfilter_cmd = \"rm -rf ~\"");
+        assert!(matches!(
+            load(&[local]),
+            Err(crate::errors::DumpError::LocalFilterCmd { .. })
+        ));
+
+        let plain = write_toml(&dir, "plain.toml", "skip_binary = true");
+        let cfg = load_with(
+            &[plain],
+            None,
+            vars(&[("DUMP_DIR_PRINTER__FILTER_CMD", "tr a-z A-Z")]),
+        )
+        .unwrap();
+        assert_eq!(cfg.printer.filter_cmd.as_deref(), Some("tr a-z A-Z"));
+    }
+
+    #[test]
+    fn follow_links_sets_the_symlink_mode() {
+        let dir = TempDir::new().unwrap();
+        let follow = write_toml(&dir, "follow.toml", "follow_links = true");
+        assert_eq!(load(&[follow]).unwrap().symlinks, Symlinks::Follow);
+        let list = write_toml(&dir, "list.toml", "symlinks = \"list\"");
+        let off = write_toml(&dir, "off.toml", "follow_links = false");
+        assert_eq!(load(&[list, off]).unwrap().symlinks, Symlinks::Skip);
+        let cfg = load_with(&[], None, vars(&[("DUMP_DIR_FOLLOW_LINKS", "true")])).unwrap();
+        assert_eq!(cfg.symlinks, Symlinks::Follow);
+    }
+
+    // ── Presets ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn presets_add_to_the_lists_config_files_set() {
+        let dir = TempDir::new().unwrap();
+        let local = write_toml(
+            &dir,
+            "dump.toml",
+            "presets = [\"rust\", \"docs-only\"]\nskip_globs_add = [\"**/out/**\"]\n\
+             skip_path_components = [\"vendor\"]",
+        );
+        let cfg = load(std::slice::from_ref(&local)).unwrap();
+        let mut globs = AppConfig::default().skip_globs;
+        globs.extend(["**/out/**".into(), "**/*.rs.bk".into()]);
+        assert_eq!(cfg.skip_globs, globs);
+        // A plain list in a config file keeps the preset's entries.
+        assert_eq!(cfg.skip_path_components, vec!["vendor", "target"]);
+        assert!(cfg.include_extensions.contains(&"md".to_string()));
+
+        // Presets given on the command line replace the configured ones.
+        let cfg = load_with(&[local], Some(vec!["node".into()]), None).unwrap();
+        assert!(cfg.skip_globs.contains(&"**/*.min.js".to_string()));
+        assert!(!cfg.skip_globs.contains(&"**/*.rs.bk".to_string()));
+        assert!(cfg.include_extensions.is_empty());
+    }
+
+    #[test]
+    fn unknown_preset_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let local = write_toml(&dir, "dump.toml", "presets = [\"cobol\"]");
+        assert!(matches!(
+            load(&[local]),
+            Err(crate::errors::DumpError::UnknownPreset { .. })
+        ));
+    }
+
+    // ── Additive skip lists ────────────────────────────────────────────────
+
+    #[test]
+    fn add_keys_extend_the_defaults() {
+        let dir = TempDir::new().unwrap();
+        let local = write_toml(&dir, "dump.toml", "skip_extensions_add = [\"pdf\"]");
+        let cfg = load(&[local]).unwrap();
+        let mut expected = AppConfig::default().skip_extensions;
+        expected.push("pdf".into());
+        assert_eq!(cfg.skip_extensions, expected);
+    }
+
+    #[test]
+    fn add_keys_stack_across_layers_and_the_command_line() {
+        let dir = TempDir::new().unwrap();
+        let global = write_toml(
+            &dir,
+            "global.toml",
+            "skip_globs_add = [\"**/dist/**\"]\nskip_extensions = [\"lock\"]",
+        );
+        let local = write_toml(
+            &dir,
+            "local.toml",
+            "skip_globs_add = [\"**/*.min.js\"]\nskip_extensions_add = [\"pdf\"]",
+        );
+        let mut cfg = load(&[global, local]).unwrap();
+        assert_eq!(cfg.skip_globs, vec!["**/dist/**", "**/*.min.js"]);
+        assert_eq!(cfg.skip_extensions, vec!["lock", "pdf"]);
+
+        // The command line is one more layer on top.
+        cfg.apply_list_overrides(ListOverrides {
+            skip_globs_add: vec!["**/out/**".into()],
+            ..ListOverrides::default()
+        });
+        assert_eq!(cfg.skip_globs, vec![
+            "**/dist/**",
+            "**/*.min.js",
+            "**/out/**"
+        ]);
+    }
+
+    #[test]
+    fn a_replacing_layer_drops_additions_below_it() {
+        let dir = TempDir::new().unwrap();
+        let global = write_toml(&dir, "global.toml", "skip_extensions_add = [\"pdf\"]");
+        let local = write_toml(
+            &dir,
+            "local.toml",
+            "skip_extensions = [\"bin\"]\nskip_extensions_add = [\"tmp\"]",
+        );
+        let cfg = load(&[global, local]).unwrap();
+        assert_eq!(cfg.skip_extensions, vec!["bin", "tmp"]);
+    }
+
+    #[test]
+    fn missing_file_in_config_list_returns_error() {
+        let dir = TempDir::new().unwrap();
+        let base = write_toml(&dir, "base.toml", "skip_binary = true");
+        let result = load(&[base, dir.path().join("nope.toml")]);
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::ConfigNotFound { path } if path.ends_with("nope.toml")
+        ));
+    }
+
     #[test]
     fn missing_default_local_config_uses_defaults() {
-        let cfg = load(None);
+        let cfg = load(&[]);
         drop(cfg);
     }
 
@@ -208,7 +1219,7 @@ mod tests {
     fn invalid_toml_returns_error() {
         let dir = TempDir::new().unwrap();
         write_toml(&dir, "bad.toml", "this is not [ valid toml !!!");
-        let result = load(Some(&dir.path().join("bad.toml")));
+        let result = load(&[dir.path().join("bad.toml")]);
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -216,11 +1227,56 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn exclude_file_skips_comments_and_blank_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = write_toml(
+            &dir,
+            "excludes",
+            "# shared excludes\n**/target/**\n\n  **/*.min.js  \n#**/dist/**\n",
+        );
+        let globs = read_exclude_file(&path).unwrap();
+        assert_eq!(globs, vec!["**/target/**", "**/*.min.js"]);
+    }
+
+    #[test]
+    fn missing_exclude_file_returns_path_not_found() {
+        let dir = TempDir::new().unwrap();
+        let result = read_exclude_file(&dir.path().join("nope"));
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::PathNotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn missing_prompt_file_returns_path_not_found() {
+        let dir = TempDir::new().unwrap();
+        let result = read_prompt_file(&dir.path().join("nope.md"));
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::PathNotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn loads_prompt_file_keys() {
+        let dir = TempDir::new().unwrap();
+        write_toml(
+            &dir,
+            "dump.toml",
+            "prompt_prefix_file = \"pre.md\"\nprompt_suffix_file = \"post.md\"",
+        );
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
+        assert_eq!(cfg.prompt_prefix_file, Some(PathBuf::from("pre.md")));
+        assert_eq!(cfg.prompt_suffix_file, Some(PathBuf::from("post.md")));
+    }
+
     #[test]
     fn partial_config_fills_missing_fields_from_defaults() {
         let dir = TempDir::new().unwrap();
         write_toml(&dir, "dump.toml", "skip_binary = false");
-        let cfg = load(Some(&dir.path().join("dump.toml"))).unwrap();
+        let cfg = load(&[dir.path().join("dump.toml")]).unwrap();
         assert!(!cfg.skip_binary);
         assert!(cfg.skip_hidden);
         assert!(!cfg.skip_extensions.is_empty());
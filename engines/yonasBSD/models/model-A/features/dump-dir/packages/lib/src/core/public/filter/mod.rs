@@ -1,416 +1,2637 @@
-use std::path::Path;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
 use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 
 use crate::{
-    config::AppConfig,
+    config::{self, AppConfig},
+    enums::{Order, SkipReason, Symlinks},
     errors::{DumpResult, GlobSetBuildSnafu, InvalidGlobSnafu, InvalidRegexSnafu},
+    groups::{self, Group},
+    language::{self, Language},
+    utils::{
+        encoding,
+        size::parse_size,
+        time::{format_utc, parse_time},
+    },
 };
 
-#[derive(Debug)]
+/// The per-directory config file honoured with `nested_configs`.
+pub const NESTED_CONFIG_FILENAME: &str = "dump.toml";
+
+/// A file or pruned directory the filter rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+    pub is_dir: bool,
+}
+
+/// What a walk excluded and why, for reporting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterStats {
+    /// Files seen by the walk and skipped, by reason.
+    pub files_skipped: BTreeMap<SkipReason, usize>,
+    /// Directories pruned before descending, by reason. Files beneath them are
+    /// never visited and not counted in `files_skipped`.
+    pub dirs_pruned: BTreeMap<SkipReason, usize>,
+    /// Every skipped file and pruned directory.
+    pub skipped: Vec<Skipped>,
+}
+
+impl FilterStats {
+    pub fn record_file(&mut self, path: &Path, reason: SkipReason) {
+        *self.files_skipped.entry(reason).or_default() += 1;
+        self.skipped.push(Skipped {
+            path: path.to_path_buf(),
+            reason,
+            is_dir: false,
+        });
+    }
+
+    pub fn record_dir(&mut self, path: &Path, reason: SkipReason) {
+        *self.dirs_pruned.entry(reason).or_default() += 1;
+        self.skipped.push(Skipped {
+            path: path.to_path_buf(),
+            reason,
+            is_dir: true,
+        });
+    }
+
+    /// Total files skipped across all reasons.
+    pub fn total_files_skipped(&self) -> usize {
+        self.files_skipped.values().sum()
+    }
+
+    /// Fold another walk's counts into these.
+    pub fn merge(&mut self, other: &Self) {
+        for (&reason, &n) in &other.files_skipped {
+            *self.files_skipped.entry(reason).or_default() += n;
+        }
+        for (&reason, &n) in &other.dirs_pruned {
+            *self.dirs_pruned.entry(reason).or_default() += n;
+        }
+        self.skipped.extend(other.skipped.iter().cloned());
+    }
+}
+
+/// `── Skipped:` totals, then each skipped path grouped by reason and sorted.
+/// Pruned directories end in `/`.
+impl fmt::Display for FilterStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = self.total_files_skipped();
+        let dirs: usize = self.dirs_pruned.values().sum();
+        write!(
+            f,
+            "── Skipped: {files} file{}, {dirs} director{}",
+            if files == 1 { "" } else { "s" },
+            if dirs == 1 { "y" } else { "ies" },
+        )?;
+
+        let mut by_reason: BTreeMap<SkipReason, Vec<&Skipped>> = BTreeMap::new();
+        for entry in &self.skipped {
+            by_reason.entry(entry.reason).or_default().push(entry);
+        }
+        for (reason, mut entries) in by_reason {
+            entries.sort_by(|a, b| a.path.cmp(&b.path));
+            write!(f, "\n{reason}:")?;
+            for entry in entries {
+                let slash = if entry.is_dir { "/" } else { "" };
+                write!(f, "\n  {}{slash}", entry.path.display())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Filter {
     skip_extensions: Vec<String>,
     skip_filenames: Vec<String>,
+    skip_filenames_exact: Vec<String>,
     skip_path_components: Vec<String>,
-    skip_patterns: Vec<Regex>,
+    /// `skip_patterns`, each with its source as configured.
+    skip_patterns: Vec<(String, Regex)>,
     skip_globs: GlobSet,
+    /// The non-`!` `skip_globs` entries, indexed like `skip_globs`.
+    skip_glob_patterns: Vec<String>,
+    /// `!`-prefixed `skip_globs` entries: exceptions to the glob stage.
+    keep_globs: GlobSet,
+    /// Per exception, its literal leading components and whether a wildcard
+    /// follows, like `always_include_prefixes`; only a directory none of them
+    /// could match below is pruned by a glob.
+    keep_glob_prefixes: Vec<(PathBuf, bool)>,
     skip_binary: bool,
+    /// `[binary] sniff_bytes`.
+    sniff_bytes: usize,
+    /// `[binary]` extension overrides, folded like `skip_extensions`.
+    always_text_extensions: Vec<String>,
+    always_binary_extensions: Vec<String>,
+    /// `skip_mime_prefixes` and `include_mime_prefixes`, lowercased.
+    skip_mime_prefixes: Vec<String>,
+    include_mime_prefixes: Vec<String>,
     skip_hidden: bool,
+    skip_generated: bool,
+    /// Built-in generated file names, compiled only with `skip_generated`.
+    generated_names: GlobSet,
+    /// `generated_markers`, lowercased.
+    generated_markers: Vec<String>,
+    skip_minified: bool,
+    minified_line_length: usize,
+    /// `minified_extensions`, folded like `skip_extensions`.
+    minified_extensions: Vec<String>,
+    skip_content_patterns: Vec<(String, Regex)>,
+    /// `content_scan_limit`, resolved to bytes.
+    content_scan_limit: u64,
+    /// `[filter] case_insensitive`: names are stored, and compared, lowercased.
+    case_insensitive: bool,
+    /// `skip_larger_than`, resolved to bytes.
+    skip_larger_than: Option<u64>,
+    /// `max_lines_per_file`; 0 disables it.
+    max_lines_per_file: usize,
+    /// Files read whole to count their lines and kept, so the printer need not
+    /// read them again; see [`Filter::take_counted`]. Shared with nested filters.
+    counted: Arc<Mutex<HashMap<PathBuf, Vec<u8>>>>,
+    /// `changed_since` and `changed_before`, resolved when the filter is built.
+    changed_since: Option<SystemTime>,
+    changed_before: Option<SystemTime>,
+    respect_dumpignore: bool,
+    respect_gitignore: bool,
+    custom_ignore_files: Vec<String>,
+    symlinks: Symlinks,
+    threads: usize,
+    max_depth: Option<usize>,
+    order: Order,
+    /// With `nested_configs`, the config this filter was built from, so a nested
+    /// `dump.toml` can be layered on top of it.
+    nested_base: Option<Box<AppConfig>>,
+    include_extensions: Vec<String>,
+    include_filenames: Vec<String>,
+    include_globs: GlobSet,
+    include_languages: Vec<&'static Language>,
+    skip_languages: Vec<&'static Language>,
+    /// `always_include`: matching files bypass every rule.
+    always_include: GlobSet,
+    /// Per `always_include` entry, its literal leading components, and whether a
+    /// wildcard follows; directories along (or, if so, below) them are kept.
+    /// Entries starting with a wildcard have none, and keep no directory.
+    always_include_prefixes: Vec<(PathBuf, bool)>,
+    /// The walked root globs and regexes are matched relative to; see
+    /// [`Filter::for_root`].
+    root: Option<PathBuf>,
 }
 
 impl Filter {
+    /// Start a [`FilterBuilder`] with no rules set.
+    pub fn builder() -> FilterBuilder {
+        FilterBuilder::new()
+    }
+
     pub fn new(cfg: &AppConfig) -> DumpResult<Self> {
-        let skip_patterns = cfg
-            .skip_patterns
-            .iter()
-            .map(|p| {
-                Regex::new(&format!("(?i){p}")).context(InvalidRegexSnafu {
-                    pattern: p.clone(),
+        let ci = cfg.filter.case_insensitive;
+        let fold = |s: &String| if ci { s.to_lowercase() } else { s.clone() };
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| {
+                    let flags = if ci { "(?i)" } else { "" };
+                    Regex::new(&format!("{flags}{p}"))
+                        .map(|re| (p.clone(), re))
+                        .context(InvalidRegexSnafu {
+                            pattern: p.clone(),
+                        })
                 })
-            })
+                .collect::<DumpResult<Vec<_>>>()
+        };
+        let skip_patterns = compile(&cfg.skip_patterns)?;
+        let skip_content_patterns = compile(&cfg.skip_content_patterns)?;
+        let content_scan_limit = parse_size(&cfg.content_scan_limit)?;
+        let languages = |names: &[String]| {
+            names
+                .iter()
+                .map(|name| language::by_name(name))
+                .collect::<DumpResult<Vec<_>>>()
+        };
+        let include_languages = languages(&cfg.include_languages)?;
+        let skip_languages = languages(&cfg.skip_languages)?;
+        let groups = cfg
+            .skip_groups
+            .iter()
+            .map(|name| groups::by_name(name))
             .collect::<DumpResult<Vec<_>>>()?;
+        // A skip list as configured, then the entries each group adds to it.
+        let with_groups = |list: &[String], entries: fn(&Group) -> &[&str]| {
+            list.iter()
+                .cloned()
+                .chain(
+                    groups
+                        .iter()
+                        .flat_map(|group| entries(group).iter().map(|s| s.to_string())),
+                )
+                .collect::<Vec<_>>()
+        };
+        let skip_extensions = with_groups(&cfg.skip_extensions, |g| g.skip_extensions);
+        let skip_filenames = with_groups(&cfg.skip_filenames, |g| g.skip_filenames);
+        let skip_path_components =
+            with_groups(&cfg.skip_path_components, |g| g.skip_path_components);
 
-        let mut glob_builder = GlobSetBuilder::new();
-        for pattern in &cfg.skip_globs {
-            let glob = GlobBuilder::new(pattern)
-                .case_insensitive(true)
-                .literal_separator(true)
-                .build()
-                .context(InvalidGlobSnafu {
-                    pattern: pattern.clone(),
-                })?;
-            glob_builder.add(glob);
+        let mut skip = Vec::new();
+        let mut keep = Vec::new();
+        for glob in &with_groups(&cfg.skip_globs, |g| g.skip_globs) {
+            match glob.strip_prefix('!') {
+                Some(exception) => keep.push(exception.to_string()),
+                None => skip.push(glob.clone()),
+            }
         }
-        let skip_globs = glob_builder.build().context(GlobSetBuildSnafu)?;
+        let skip_larger_than = cfg
+            .skip_larger_than
+            .as_deref()
+            .map(parse_size)
+            .transpose()?;
+        let now = SystemTime::now();
+        let resolve = |t: &Option<String>| t.as_deref().map(|t| parse_time(t, now)).transpose();
+        let changed_since = resolve(&cfg.changed_since)?;
+        let changed_before = resolve(&cfg.changed_before)?;
+        let skip_globs = build_glob_set(&skip, ci)?;
+        let keep_globs = build_glob_set(&keep, ci)?;
+        let include_globs = build_glob_set(&cfg.include_globs, ci)?;
+        let always_include = build_glob_set(&cfg.always_include, ci)?;
+        let generated_names = if cfg.skip_generated {
+            let names: Vec<String> = GENERATED_NAMES.iter().map(|g| g.to_string()).collect();
+            build_glob_set(&names, false)?
+        } else {
+            GlobSet::empty()
+        };
 
         Ok(Self {
-            skip_extensions: cfg
-                .skip_extensions
+            skip_extensions: skip_extensions.iter().map(fold).collect(),
+            skip_filenames: skip_filenames.iter().map(fold).collect(),
+            skip_filenames_exact: cfg.skip_filenames_exact.iter().map(fold).collect(),
+            skip_path_components: skip_path_components.iter().map(fold).collect(),
+            skip_patterns,
+            skip_globs,
+            skip_glob_patterns: skip,
+            keep_globs,
+            keep_glob_prefixes: keep
                 .iter()
-                .map(|s| s.to_lowercase())
+                .map(|glob| literal_prefix(&fold(glob)))
                 .collect(),
-            skip_filenames: cfg
-                .skip_filenames
+            skip_binary: cfg.skip_binary,
+            sniff_bytes: cfg.binary.sniff_bytes,
+            always_text_extensions: cfg.binary.always_text_extensions.iter().map(fold).collect(),
+            always_binary_extensions: cfg
+                .binary
+                .always_binary_extensions
                 .iter()
-                .map(|s| s.to_lowercase())
+                .map(fold)
                 .collect(),
-            skip_path_components: cfg
-                .skip_path_components
+            skip_mime_prefixes: cfg
+                .skip_mime_prefixes
                 .iter()
-                .map(|s| s.to_lowercase())
+                .map(|p| p.to_lowercase())
+                .collect(),
+            include_mime_prefixes: cfg
+                .include_mime_prefixes
+                .iter()
+                .map(|p| p.to_lowercase())
                 .collect(),
-            skip_patterns,
-            skip_globs,
-            skip_binary: cfg.skip_binary,
             skip_hidden: cfg.skip_hidden,
+            skip_generated: cfg.skip_generated,
+            generated_names,
+            generated_markers: cfg
+                .generated_markers
+                .iter()
+                .map(|m| m.to_lowercase())
+                .collect(),
+            skip_minified: cfg.skip_minified,
+            minified_line_length: cfg.minified_line_length,
+            minified_extensions: cfg.minified_extensions.iter().map(fold).collect(),
+            skip_content_patterns,
+            content_scan_limit,
+            case_insensitive: ci,
+            skip_larger_than,
+            max_lines_per_file: cfg.max_lines_per_file,
+            counted: Arc::default(),
+            changed_since,
+            changed_before,
+            respect_dumpignore: cfg.respect_dumpignore,
+            respect_gitignore: cfg.respect_gitignore,
+            custom_ignore_files: cfg.custom_ignore_files.clone(),
+            symlinks: cfg.symlinks,
+            threads: cfg.threads,
+            max_depth: cfg.max_depth,
+            order: cfg.order,
+            nested_base: cfg.nested_configs.then(|| Box::new(cfg.clone())),
+            include_extensions: cfg.include_extensions.iter().map(fold).collect(),
+            include_filenames: cfg.include_filenames.iter().map(fold).collect(),
+            include_globs,
+            include_languages,
+            skip_languages,
+            always_include,
+            always_include_prefixes: cfg
+                .always_include
+                .iter()
+                .map(|pattern| literal_prefix(&fold(pattern)))
+                .filter(|(prefix, _)| !prefix.as_os_str().is_empty())
+                .collect(),
+            root: None,
         })
     }
 
+    /// This filter, matching globs and regexes against paths relative to `root`
+    /// (falling back to the path as given for one outside it). The walker
+    /// anchors each root's filter this way, so `src/**` means `<root>/src/**`
+    /// whatever the cwd.
+    pub fn for_root(&self, root: &Path) -> Filter {
+        Filter {
+            root: Some(root.to_path_buf()),
+            ..self.clone()
+        }
+    }
+
+    /// `path` relative to the root, or else to `.` (so `./src/a.rs` becomes
+    /// `src/a.rs`); `None` if it lies outside both or is the root itself.
+    fn relative<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        let non_empty = |rel: &&Path| !rel.as_os_str().is_empty();
+        self.root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .filter(non_empty)
+            .or_else(|| path.strip_prefix(".").ok().filter(non_empty))
+    }
+
+    /// The part of `path` the name rules look at: the path below the root (see
+    /// [`Self::relative`]); else a relative path as given; else, for an absolute
+    /// path outside the root, or the root itself, just its file name. Whatever
+    /// lies above the walk never matches.
+    fn below_root<'a>(&self, path: &'a Path) -> &'a Path {
+        match self.relative(path) {
+            Some(rel) => rel,
+            None if path.is_absolute() => path.file_name().map_or(Path::new(""), Path::new),
+            None => path,
+        }
+    }
+
+    /// Whether the walk should honour `.dumpignore` files.
+    pub fn respects_dumpignore(&self) -> bool {
+        self.respect_dumpignore
+    }
+
+    /// Whether the walk should honour `.gitignore` and the git exclude files.
+    pub fn respects_gitignore(&self) -> bool {
+        self.respect_gitignore
+    }
+
+    /// Extra ignore-file names the walk should honour (`custom_ignore_files`).
+    pub fn custom_ignore_files(&self) -> &[String] {
+        &self.custom_ignore_files
+    }
+
+    /// How the walk treats symbolic links (`symlinks`).
+    pub fn symlinks(&self) -> Symlinks {
+        self.symlinks
+    }
+
+    /// How many threads walk each root (`threads`); 0 means one per CPU.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// The size limit in bytes (`skip_larger_than`), if set.
+    pub fn skip_larger_than(&self) -> Option<u64> {
+        self.skip_larger_than
+    }
+
+    /// How many levels below the root the walk descends (`max_depth`).
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// The order files are printed in (`order`); the walk reads the metadata
+    /// of kept files when it sorts by size or modification time.
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    /// The modification-time window as `(since, before)`; either bound may be unset.
+    pub fn modified_window(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        (self.changed_since, self.changed_before)
+    }
+
+    /// `Modified` if the file's mtime falls outside the window. `modified` is
+    /// only called when a bound is set; a file whose mtime is unknown is kept.
+    pub fn mtime_skip_reason(
+        &self,
+        modified: impl FnOnce() -> Option<SystemTime>,
+    ) -> Option<SkipReason> {
+        if self.changed_since.is_none() && self.changed_before.is_none() {
+            return None;
+        }
+        let mtime = modified()?;
+        let too_old = self.changed_since.is_some_and(|since| mtime < since);
+        let too_new = self.changed_before.is_some_and(|before| mtime >= before);
+        (too_old || too_new).then_some(SkipReason::Modified)
+    }
+
+    /// The filter for the subtree at `dir`, if `nested_configs` is on and `dir`
+    /// holds a `dump.toml`. A nested config that fails to load or compile is
+    /// reported as a warning and ignored.
+    pub fn nested(&self, dir: &Path) -> Option<Filter> {
+        let base = self.nested_base.as_deref()?;
+        let file = dir.join(NESTED_CONFIG_FILENAME);
+        if !file.is_file() {
+            return None;
+        }
+        let mut cfg = base.clone();
+        let nested = config::read_filter_overrides(&file).and_then(|overrides| {
+            cfg.apply_filter_overrides(overrides);
+            Filter::new(&cfg).map(|filter| Filter {
+                root: self.root.clone(),
+                counted: Arc::clone(&self.counted),
+                ..filter
+            })
+        });
+        match nested {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                crate::warning!("ignoring {}: {e}", file.display());
+                None
+            },
+        }
+    }
+
+    /// A path part as the name lists store it: lowercased unless matching is
+    /// case-sensitive.
+    fn fold(&self, part: &std::ffi::OsStr) -> String {
+        let part = part.to_string_lossy();
+        if self.case_insensitive {
+            part.to_lowercase()
+        } else {
+            part.into_owned()
+        }
+    }
+
+    /// True if no include rule is set, or `path` matches at least one.
+    fn is_included(&self, path: &Path) -> bool {
+        if self.include_extensions.is_empty()
+            && self.include_filenames.is_empty()
+            && self.include_globs.is_empty()
+            && self.include_languages.is_empty()
+        {
+            return true;
+        }
+
+        if extension(path).is_some_and(|ext| self.include_extensions.contains(&self.fold(ext))) {
+            return true;
+        }
+        if path
+            .file_name()
+            .is_some_and(|name| self.include_filenames.contains(&self.fold(name)))
+        {
+            return true;
+        }
+        if self.include_languages.iter().any(|lang| lang.matches(path)) {
+            return true;
+        }
+
+        // Walked paths start with the root as given, e.g. `./src/main.rs`; a
+        // glob like `src/**` should still match.
+        self.relative(path)
+            .into_iter()
+            .chain([path])
+            .any(|candidate| self.include_globs.is_match(candidate))
+    }
+
+    /// True if `path` matches `always_include`, so no rule can leave it out.
+    pub fn always_includes(&self, path: &Path) -> bool {
+        !self.always_include.is_empty()
+            && self
+                .relative(path)
+                .into_iter()
+                .chain([path])
+                .any(|candidate| self.always_include.is_match(candidate))
+    }
+
+    /// True if an `always_include` entry could match below the directory `path`,
+    /// which must then stay in the walk.
+    fn leads_to_always_included(&self, path: &Path) -> bool {
+        self.leads_to(&self.always_include_prefixes, path)
+    }
+
+    /// True if a pattern with one of `prefixes` (see [`literal_prefix`]) could
+    /// match the directory `path` or something below it.
+    fn leads_to(&self, prefixes: &[(PathBuf, bool)], path: &Path) -> bool {
+        if prefixes.is_empty() {
+            return false;
+        }
+        let dir = PathBuf::from(self.fold(self.relative(path).unwrap_or(path).as_os_str()));
+        prefixes
+            .iter()
+            .any(|(prefix, open)| prefix.starts_with(&dir) || (*open && dir.starts_with(prefix)))
+    }
+
     /// Returns `true` if an entire directory should be pruned from the walk.
     pub fn should_skip_dir(&self, path: &Path) -> bool {
-        if let Some(name) = path.file_name() {
-            let name_lower = name.to_string_lossy().to_lowercase();
+        self.dir_skip_reason(path).is_some()
+    }
+
+    /// The rule that prunes `path` from the walk, if any.
+    pub fn dir_skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        self.dir_rule(path).map(|(reason, _)| reason)
+    }
 
-            if self.skip_hidden && name_lower.starts_with('.') {
-                return true;
+    /// Why `path` would be kept or left out, naming the first matching rule and
+    /// the value that matched. A directory gets the pruning rules; a file also
+    /// gets the modification-time window. Ignore files are the walk's business
+    /// (see `walker::explain`), and nested `dump.toml` files are not consulted.
+    pub fn check(&self, path: &Path) -> FilterDecision {
+        let rule = if path.is_dir() {
+            self.dir_rule(path)
+        } else if self.always_includes(path) {
+            None
+        } else {
+            self.file_rule(path, Source::Disk)
+                .or_else(|| self.mtime_rule(path))
+        };
+        match rule {
+            Some((reason, rule)) => FilterDecision::Skipped {
+                reason,
+                rule,
+            },
+            None => FilterDecision::Included,
+        }
+    }
+
+    fn dir_rule(&self, path: &Path) -> Option<(SkipReason, String)> {
+        if self.leads_to_always_included(path) {
+            return None;
+        }
+        // Below the root, every component: an ancestor kept in the walk for
+        // `always_include` leaves its other subdirectories to be pruned here.
+        for name in self.below_root(path) {
+            let name = self.fold(name);
+            if self.skip_hidden && name.starts_with('.') && name != "." && name != ".." {
+                return Some((SkipReason::Hidden, format!("skip_hidden: {name}")));
             }
-            if self.skip_path_components.contains(&name_lower) {
-                return true;
+            if self.skip_path_components.contains(&name) {
+                return Some((
+                    SkipReason::PathComponent,
+                    format!("skip_path_components: {name}"),
+                ));
             }
         }
+        if self.skip_hidden && has_hidden_attribute(path) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            return Some((
+                SkipReason::Hidden,
+                format!("skip_hidden: {name} (hidden attribute)"),
+            ));
+        }
 
-        let synthetic = path.join("_");
-        for candidate in [path, synthetic.as_path()] {
-            if self.skip_globs.is_match(candidate) {
-                return true;
-            }
-            if let Ok(rel) = candidate.strip_prefix(std::env::current_dir().unwrap_or_default()) {
-                if self.skip_globs.is_match(rel) {
-                    return true;
+        // Where a `!` exception could match below the directory, the glob stage
+        // leaves it to the per-file check. One starting with a wildcard could
+        // match below any directory.
+        if !self.leads_to(&self.keep_glob_prefixes, path) {
+            let synthetic = path.join("_");
+            for candidate in [path, synthetic.as_path()] {
+                if let Some(glob) = self.glob_skip(candidate) {
+                    return Some((SkipReason::Glob, format!("skip_globs: {glob}")));
                 }
             }
         }
 
-        false
+        None
+    }
+
+    /// The glob stage: the first skip glob `path` (relative to the root, or as
+    /// walked) matches, unless a `!` exception matches too. Exceptions only
+    /// override globs; every other rule still applies to a kept path.
+    fn glob_skip(&self, path: &Path) -> Option<&str> {
+        let rel = self.relative(path);
+        let matches = |set: &GlobSet| set.is_match(path) || rel.is_some_and(|r| set.is_match(r));
+        if !matches(&self.skip_globs) || matches(&self.keep_globs) {
+            return None;
+        }
+        let mut hits = self.skip_globs.matches(path);
+        if let Some(rel) = rel {
+            hits.extend(self.skip_globs.matches(rel));
+        }
+        hits.into_iter()
+            .min()
+            .map(|i| self.skip_glob_patterns[i].as_str())
     }
 
     /// Returns `true` if the file should be skipped.
     pub fn should_skip(&self, path: &Path) -> bool {
-        let path_str = path.to_string_lossy();
+        self.skip_reason(path).is_some()
+    }
+
+    /// The first rule that excludes the file, if any. Rules are checked in a fixed
+    /// order, cheapest first; the content checks (which open the file) run last.
+    pub fn skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        self.file_rule(path, Source::Disk).map(|(reason, _)| reason)
+    }
+
+    /// [`skip_reason`](Self::skip_reason) for a file the walk found, which
+    /// already knows whether it `is_symlink`, so the file is not stat'ed again
+    /// to find out.
+    pub fn walked_skip_reason(&self, path: &Path, is_symlink: bool) -> Option<SkipReason> {
+        self.file_rule(path, Source::Walked {
+            is_symlink,
+        })
+        .map(|(reason, _)| reason)
+    }
+
+    /// [`skip_reason`](Self::skip_reason) for a file that exists only in memory,
+    /// such as an archive entry: `path` is its name, and the size and content
+    /// checks look at `content` instead of the disk.
+    pub fn content_skip_reason(&self, path: &Path, content: &[u8]) -> Option<SkipReason> {
+        self.file_rule(path, Source::Memory(content))
+            .map(|(reason, _)| reason)
+    }
+
+    /// The rules [`skip_reason`](Self::skip_reason) checks by name alone, for a
+    /// file that exists only in memory: its size and content are not looked at,
+    /// so an archive entry can be left out before it is read.
+    pub fn name_skip_reason(&self, path: &Path) -> Option<SkipReason> {
+        if self.always_includes(path) {
+            return None;
+        }
+        self.name_rule(path, false).map(|(reason, _)| reason)
+    }
+
+    /// The rule excluding `path`, looking at it in `source`.
+    fn file_rule(&self, path: &Path, source: Source) -> Option<(SkipReason, String)> {
+        // First, as it overrides every rule below.
+        if self.always_includes(path) {
+            return None;
+        }
+        let on_disk = !matches!(source, Source::Memory(_));
+        if let Some(rule) = self.name_rule(path, on_disk) {
+            return Some(rule);
+        }
+        self.content_rule(path, source)
+    }
+
+    /// The rules that need only `path`; with `on_disk`, the file's hidden
+    /// attribute is read too.
+    fn name_rule(&self, path: &Path, on_disk: bool) -> Option<(SkipReason, String)> {
+        // Regexes see the path relative to the root, so a root named like
+        // `tests/` doesn't match every file under it.
+        let path_str = self.relative(path).unwrap_or(path).to_string_lossy();
 
-        for component in path.components() {
-            let c = component.as_os_str().to_string_lossy().to_lowercase();
+        // Only components below the root: a project living under some
+        // `node_modules` directory is not itself skipped.
+        let below_root = self.below_root(path);
+        for component in below_root {
+            let c = self.fold(component);
             if self.skip_path_components.contains(&c) {
-                return true;
+                return Some((
+                    SkipReason::PathComponent,
+                    format!("skip_path_components: {c}"),
+                ));
             }
         }
 
         if self.skip_hidden {
-            if path.is_absolute() {
-                if let Some(name) = path.file_name() {
-                    if name.to_string_lossy().starts_with('.') {
-                        return true;
-                    }
-                }
-            } else {
-                for component in path.components() {
-                    let c = component.as_os_str().to_string_lossy();
-                    if c.starts_with('.') && c != "." && c != ".." {
-                        return true;
-                    }
-                }
+            // Below the root every component counts: a hidden directory kept in
+            // the walk for `always_include` still hides its other files.
+            let hidden = below_root
+                .iter()
+                .map(|component| component.to_string_lossy())
+                .find(|c| c.starts_with('.') && c != "." && c != "..");
+            if let Some(hidden) = hidden {
+                return Some((SkipReason::Hidden, format!("skip_hidden: {hidden}")));
             }
-        }
-
-        if let Some(ext) = path.extension() {
-            let ext_lower = ext.to_string_lossy().to_lowercase();
-            if self.skip_extensions.contains(&ext_lower) {
-                return true;
+            // Hidden directories are pruned by `dir_rule`, so only the file's own
+            // attribute is left to check.
+            if on_disk && has_hidden_attribute(path) {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                return Some((
+                    SkipReason::Hidden,
+                    format!("skip_hidden: {name} (hidden attribute)"),
+                ));
             }
         }
 
-        if let Some(name) = path.file_stem() {
-            let name_lower = name.to_string_lossy().to_lowercase();
-            if self.skip_filenames.contains(&name_lower) {
-                return true;
+        if let Some(ext) = extension(path) {
+            let ext = self.fold(ext);
+            if self.skip_extensions.contains(&ext) {
+                return Some((SkipReason::Extension, format!("skip_extensions: {ext}")));
             }
         }
+
         if let Some(name) = path.file_name() {
-            let name_lower = name.to_string_lossy().to_lowercase();
-            if self.skip_filenames.contains(&name_lower) {
-                return true;
+            let name = self.fold(name);
+            if self.skip_filenames_exact.contains(&name) {
+                return Some((
+                    SkipReason::Filename,
+                    format!("skip_filenames_exact: {name}"),
+                ));
             }
+            // The stem, then the full name; a name without an extension is its
+            // own stem, so it is looked up once. A file an `include_languages`
+            // entry names outright (`Makefile` for "make") was asked for by name.
+            let named = self
+                .include_languages
+                .iter()
+                .any(|lang| lang.matches_filename(path));
+            let stem = path
+                .file_stem()
+                .map(|stem| self.fold(stem))
+                .filter(|stem| *stem != name);
+            let hit = stem
+                .into_iter()
+                .chain([name])
+                .find(|name| !named && self.skip_filenames.contains(name));
+            if let Some(name) = hit {
+                return Some((SkipReason::Filename, format!("skip_filenames: {name}")));
+            }
+        }
+
+        if let Some(lang) = self.skip_languages.iter().find(|lang| lang.matches(path)) {
+            return Some((
+                SkipReason::Language,
+                format!("skip_languages: {}", lang.name),
+            ));
         }
 
-        for re in &self.skip_patterns {
+        for (pattern, re) in &self.skip_patterns {
             if re.is_match(&path_str) {
-                return true;
+                return Some((SkipReason::Pattern, format!("skip_patterns: {pattern}")));
             }
         }
 
-        if self.skip_globs.is_match(path) {
-            return true;
+        if let Some(glob) = self.glob_skip(path) {
+            return Some((SkipReason::Glob, format!("skip_globs: {glob}")));
+        }
+
+        if !self.is_included(path) {
+            return Some((
+                SkipReason::NotIncluded,
+                "include rules: no include_extensions, include_filenames, include_globs or \
+                 include_languages entry matches"
+                    .into(),
+            ));
+        }
+
+        None
+    }
+
+    /// The rules that look at the file's metadata or bytes, in `source`.
+    fn content_rule(&self, path: &Path, source: Source) -> Option<(SkipReason, String)> {
+        let content = match source {
+            Source::Memory(content) => Some(content),
+            Source::Disk
+            | Source::Walked {
+                ..
+            } => None,
+        };
+        let is_symlink = || match source {
+            Source::Disk => fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()),
+            Source::Walked {
+                is_symlink,
+            } => is_symlink,
+            Source::Memory(_) => false,
+        };
+        // Unless followed, a link is dropped here or listed without opening its
+        // target, so none of the checks below apply to it.
+        if self.symlinks != Symlinks::Follow && is_symlink() {
+            return match self.symlinks {
+                Symlinks::Skip => Some((SkipReason::Symlink, "symlinks: skip".into())),
+                _ => None,
+            };
+        }
+
+        let size = || match content {
+            Some(content) => Some(content.len() as u64),
+            None => fs::metadata(path).ok().map(|m| m.len()),
+        };
+        if let Some(limit) = self.skip_larger_than
+            && let Some(size) = size()
+            && size > limit
+        {
+            return Some((
+                SkipReason::TooLarge,
+                format!("skip_larger_than: {size} bytes, over {limit}"),
+            ));
+        }
+
+        if self.skip_generated
+            && let Some(i) = self.generated_names.matches(path).into_iter().min()
+        {
+            return Some((
+                SkipReason::Generated,
+                format!("skip_generated: name matches {}", GENERATED_NAMES[i]),
+            ));
         }
-        if let Ok(rel) = path.strip_prefix(std::env::current_dir().unwrap_or_default()) {
-            if self.skip_globs.is_match(rel) {
-                return true;
+
+        // An extension override settles binary detection without a read.
+        let mut sniff = self.skip_binary;
+        if sniff && let Some(ext) = extension(path).map(|ext| self.fold(ext)) {
+            if self.always_binary_extensions.contains(&ext) {
+                return Some((
+                    SkipReason::Binary,
+                    format!("[binary] always_binary_extensions: {ext}"),
+                ));
             }
+            sniff = !self.always_text_extensions.contains(&ext);
         }
 
-        if self.skip_binary && is_binary(path) {
-            return true;
+        let minified = self.skip_minified
+            && extension(path)
+                .is_some_and(|ext| self.minified_extensions.contains(&self.fold(ext)));
+
+        // One read serves every content check: the sniffed head (at least the
+        // first 8KB), or the whole file when content patterns are set and it is
+        // within `content_scan_limit`. Lines are counted by a separate streaming
+        // pass, only once the head checks have passed; a file it reads to the end
+        // is kept for the printer if no later check skips it.
+        let scan = !self.skip_content_patterns.is_empty()
+            && size().is_some_and(|size| size <= self.content_scan_limit);
+        let count = self.max_lines_per_file > 0;
+        let head_len = HEAD_LEN.max(self.sniff_bytes as u64);
+        let limit = if scan {
+            self.content_scan_limit.max(head_len)
+        } else {
+            head_len
+        };
+        let read = || match content {
+            Some(content) => {
+                let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+                Some(Cow::Borrowed(&content[..content.len().min(limit)]))
+            },
+            None => read_head(path, limit).map(Cow::Owned),
+        };
+        let mime_lists =
+            !self.skip_mime_prefixes.is_empty() || !self.include_mime_prefixes.is_empty();
+        let mut counted = None;
+        if (sniff || mime_lists || self.skip_generated || minified || scan || count)
+            && let Some(bytes) = read()
+        {
+            let head = &bytes[..bytes.len().min(HEAD_LEN as usize)];
+            let sniffed = &bytes[..bytes.len().min(self.sniff_bytes)];
+            let mime = mime_lists.then(|| sniff_mime(sniffed)).flatten();
+            if let Some(mime) = mime
+                && let Some(rule) = self.mime_rule(mime)
+            {
+                return Some((SkipReason::MimeType, rule));
+            }
+            // A recognized type the MIME lists let through is not binary.
+            if sniff && mime.is_none() && looks_binary(sniffed) {
+                return Some((
+                    SkipReason::Binary,
+                    "skip_binary: content looks binary".into(),
+                ));
+            }
+            if self.skip_generated
+                && let Some(marker) = self.generated_marker(head)
+            {
+                return Some((
+                    SkipReason::Generated,
+                    format!("skip_generated: marker \"{marker}\""),
+                ));
+            }
+            if minified
+                && let Some(why) = minified_reason(
+                    &bytes[..bytes.len().min(HEAD_LEN as usize)],
+                    self.minified_line_length,
+                )
+            {
+                return Some((SkipReason::Minified, format!("skip_minified: {why}")));
+            }
+            if count {
+                let max = self.max_lines_per_file;
+                let over = match content {
+                    Some(content) => count_lines(content) > max,
+                    None => match fs::File::open(path).map(|file| read_within_lines(file, max)) {
+                        Ok(Ok(Some(whole))) => {
+                            counted = Some(whole);
+                            false
+                        },
+                        Ok(Ok(None)) => true,
+                        Ok(Err(_)) | Err(_) => false,
+                    },
+                };
+                if over {
+                    return Some((
+                        SkipReason::TooManyLines,
+                        format!("max_lines_per_file: more than {max} lines"),
+                    ));
+                }
+            }
+            if scan {
+                let text = String::from_utf8_lossy(&bytes);
+                if let Some((pattern, _)) = self
+                    .skip_content_patterns
+                    .iter()
+                    .find(|(_, re)| re.is_match(&text))
+                {
+                    return Some((
+                        SkipReason::Content,
+                        format!("skip_content_patterns: {pattern}"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(whole) = counted
+            && let Ok(mut kept) = self.counted.lock()
+        {
+            kept.insert(path.to_path_buf(), whole);
+        }
+        None
+    }
+
+    /// The files `max_lines_per_file` read whole and let through, by path, to
+    /// be printed from memory (see `Printer::add_in_memory`); taking them
+    /// empties the store.
+    pub fn take_counted(&self) -> HashMap<PathBuf, Vec<u8>> {
+        self.counted
+            .lock()
+            .map(|mut kept| std::mem::take(&mut *kept))
+            .unwrap_or_default()
+    }
+
+    /// Why the MIME lists skip a file of the sniffed type `mime`, if they do.
+    /// `include_mime_prefixes` always keeps `text/` types.
+    fn mime_rule(&self, mime: &str) -> Option<String> {
+        let hit = |prefixes: &[String]| {
+            prefixes
+                .iter()
+                .find(|prefix| mime.starts_with(prefix.as_str()))
+                .cloned()
+        };
+        if let Some(prefix) = hit(&self.skip_mime_prefixes) {
+            return Some(format!("skip_mime_prefixes: {prefix} ({mime})"));
+        }
+        if !self.include_mime_prefixes.is_empty()
+            && !mime.starts_with("text/")
+            && hit(&self.include_mime_prefixes).is_none()
+        {
+            return Some(format!("include_mime_prefixes: no entry matches {mime}"));
+        }
+        None
+    }
+
+    /// The modification-time rule for `path`, read from its metadata.
+    fn mtime_rule(&self, path: &Path) -> Option<(SkipReason, String)> {
+        let mtime = fs::metadata(path).ok()?.modified().ok()?;
+        let modified = format_utc(mtime);
+        if let Some(since) = self.changed_since.filter(|&since| mtime < since) {
+            let rule = format!(
+                "changed_since: modified {modified}, before {}",
+                format_utc(since)
+            );
+            return Some((SkipReason::Modified, rule));
         }
+        let before = self.changed_before.filter(|&before| mtime >= before)?;
+        let rule = format!(
+            "changed_before: modified {modified}, not before {}",
+            format_utc(before)
+        );
+        Some((SkipReason::Modified, rule))
+    }
 
-        false
+    /// The first `generated_markers` entry in the first 2KB of `head`, ignoring case.
+    fn generated_marker(&self, head: &[u8]) -> Option<&str> {
+        let sniff = String::from_utf8_lossy(&head[..head.len().min(2048)]).to_lowercase();
+        self.generated_markers
+            .iter()
+            .find(|marker| sniff.contains(marker.as_str()))
+            .map(String::as_str)
     }
 }
 
-/// Sniff the first 8KB of the file to detect binary content.
-fn is_binary(path: &Path) -> bool {
-    use std::{fs::File, io::Read};
+/// What [`Filter::check`] (or `walker::explain`) decided for one path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterDecision {
+    Included,
+    /// Left out by `reason`; `rule` names the config key (or ignore file) and
+    /// the value that matched, e.g. `skip_globs: **/target/**`.
+    Skipped {
+        reason: SkipReason,
+        rule: String,
+    },
+}
 
-    let Ok(mut f) = File::open(path) else {
-        return false;
-    };
+/// `included`, or `skipped by <rule>`.
+impl fmt::Display for FilterDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Included => f.write_str("included"),
+            Self::Skipped {
+                rule, ..
+            } => write!(f, "skipped by {rule}"),
+        }
+    }
+}
 
-    let mut buf = [0u8; 8192];
-    let Ok(n) = f.read(&mut buf) else {
-        return false;
-    };
+/// Fluent construction of a [`Filter`] for library use. Unlike
+/// `AppConfig::default()`, it starts with every rule off (no skip lists, binary
+/// and hidden files kept), so only what is added applies. Patterns are
+/// validated by [`FilterBuilder::build`], exactly as [`Filter::new`] does.
+#[derive(Debug, Clone)]
+pub struct FilterBuilder {
+    cfg: AppConfig,
+}
 
-    if let Some(kind) = infer::get(&buf[..n]) {
-        let mime = kind.mime_type();
-        if !mime.starts_with("text/") {
-            return true;
+impl Default for FilterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterBuilder {
+    pub fn new() -> Self {
+        Self {
+            cfg: AppConfig {
+                skip_extensions: vec![],
+                skip_patterns: vec![],
+                skip_filenames: vec![],
+                skip_path_components: vec![],
+                skip_globs: vec![],
+                skip_binary: false,
+                skip_hidden: false,
+                ..AppConfig::default()
+            },
         }
     }
 
-    buf[..n].contains(&0u8)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::AppConfig;
+    /// Skip files with this extension (without leading dot).
+    pub fn skip_extension(mut self, ext: impl Into<String>) -> Self {
+        self.cfg.skip_extensions.push(ext.into());
+        self
+    }
+
+    /// Skip paths matching this regex.
+    pub fn skip_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.cfg.skip_patterns.push(pattern.into());
+        self
+    }
+
+    /// Skip files with this exact name or stem.
+    pub fn skip_filename(mut self, name: impl Into<String>) -> Self {
+        self.cfg.skip_filenames.push(name.into());
+        self
+    }
+
+    /// Skip files with exactly this name, extension included.
+    pub fn skip_filename_exact(mut self, name: impl Into<String>) -> Self {
+        self.cfg.skip_filenames_exact.push(name.into());
+        self
+    }
+
+    /// Skip anything under a directory with this name, at any depth.
+    pub fn skip_path_component(mut self, component: impl Into<String>) -> Self {
+        self.cfg.skip_path_components.push(component.into());
+        self
+    }
+
+    /// Skip paths matching this glob; a leading `!` makes it an exception.
+    pub fn skip_glob(mut self, glob: impl Into<String>) -> Self {
+        self.cfg.skip_globs.push(glob.into());
+        self
+    }
+
+    pub fn skip_binary(mut self, skip: bool) -> Self {
+        self.cfg.skip_binary = skip;
+        self
+    }
+
+    pub fn skip_hidden(mut self, skip: bool) -> Self {
+        self.cfg.skip_hidden = skip;
+        self
+    }
+
+    /// Skip files whose content matches this regex.
+    pub fn skip_content_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.cfg.skip_content_patterns.push(pattern.into());
+        self
+    }
+
+    /// Skip, follow or list symbolic links.
+    pub fn symlinks(mut self, symlinks: Symlinks) -> Self {
+        self.cfg.symlinks = symlinks;
+        self
+    }
+
+    /// Walk each root on `threads` threads; 0 means one per CPU.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.cfg.threads = threads;
+        self
+    }
+
+    /// Descend at most `depth` levels below the root; 1 is its own entries only.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.cfg.max_depth = Some(depth);
+        self
+    }
+
+    /// Print files in `order`.
+    pub fn order(mut self, order: Order) -> Self {
+        self.cfg.order = order;
+        self
+    }
+
+    /// Skip generated files, by name or by a marker near the top.
+    pub fn skip_generated(mut self, skip: bool) -> Self {
+        self.cfg.skip_generated = skip;
+        self
+    }
+
+    /// Skip minified and bundled assets among `minified_extensions`.
+    pub fn skip_minified(mut self, skip: bool) -> Self {
+        self.cfg.skip_minified = skip;
+        self
+    }
+
+    /// Match names, regexes and globs ignoring case (the default) or not.
+    pub fn case_insensitive(mut self, on: bool) -> Self {
+        self.cfg.filter.case_insensitive = on;
+        self
+    }
+
+    /// Skip files larger than a human-readable size such as `"2MiB"`.
+    pub fn skip_larger_than(mut self, size: impl Into<String>) -> Self {
+        self.cfg.skip_larger_than = Some(size.into());
+        self
+    }
+
+    /// Skip files with more than `max` lines; 0 disables the check.
+    pub fn max_lines_per_file(mut self, max: usize) -> Self {
+        self.cfg.max_lines_per_file = max;
+        self
+    }
+
+    /// Skip files last modified before `when` (`"7d"` or an RFC 3339 date).
+    pub fn changed_since(mut self, when: impl Into<String>) -> Self {
+        self.cfg.changed_since = Some(when.into());
+        self
+    }
+
+    /// Skip files last modified at or after `when`.
+    pub fn changed_before(mut self, when: impl Into<String>) -> Self {
+        self.cfg.changed_before = Some(when.into());
+        self
+    }
+
+    /// Keep only files with one of the included extensions, names or globs.
+    pub fn include_extension(mut self, ext: impl Into<String>) -> Self {
+        self.cfg.include_extensions.push(ext.into());
+        self
+    }
+
+    /// Keep files with this exact name (case-insensitive); see [`Self::include_extension`].
+    pub fn include_filename(mut self, name: impl Into<String>) -> Self {
+        self.cfg.include_filenames.push(name.into());
+        self
+    }
+
+    /// Keep paths matching this glob; see [`Self::include_extension`].
+    pub fn include_glob(mut self, glob: impl Into<String>) -> Self {
+        self.cfg.include_globs.push(glob.into());
+        self
+    }
+
+    /// Keep files of this language, e.g. `"rust"`; see [`Self::include_extension`].
+    pub fn include_language(mut self, name: impl Into<String>) -> Self {
+        self.cfg.include_languages.push(name.into());
+        self
+    }
+
+    /// Skip files of this language, by extension or well-known file name.
+    pub fn skip_language(mut self, name: impl Into<String>) -> Self {
+        self.cfg.skip_languages.push(name.into());
+        self
+    }
+
+    /// Add a built-in group of skip rules, e.g. `"lockfiles"`.
+    pub fn skip_group(mut self, name: impl Into<String>) -> Self {
+        self.cfg.skip_groups.push(name.into());
+        self
+    }
+
+    /// Always dump files matching this path or glob, whatever the other rules say.
+    pub fn always_include(mut self, pattern: impl Into<String>) -> Self {
+        self.cfg.always_include.push(pattern.into());
+        self
+    }
+
+    /// Whether the walker honours `.dumpignore` files (on by default).
+    pub fn respect_dumpignore(mut self, respect: bool) -> Self {
+        self.cfg.respect_dumpignore = respect;
+        self
+    }
+
+    /// Whether the walker honours `.gitignore` and the git exclude files (on by
+    /// default).
+    pub fn respect_gitignore(mut self, respect: bool) -> Self {
+        self.cfg.respect_gitignore = respect;
+        self
+    }
+
+    /// Also honour ignore files with this name, e.g. `.aiignore`.
+    pub fn custom_ignore_file(mut self, name: impl Into<String>) -> Self {
+        self.cfg.custom_ignore_files.push(name.into());
+        self
+    }
+
+    /// Compile the rules. Fails like [`Filter::new`] on a bad regex, glob, size
+    /// or time.
+    pub fn build(self) -> DumpResult<Filter> {
+        Filter::new(&self.cfg)
+    }
+}
+
+/// Where the file rules find a file's metadata and bytes.
+#[derive(Debug, Clone, Copy)]
+enum Source<'a> {
+    /// On disk; whether it is a symlink takes an lstat.
+    Disk,
+    /// On disk, found by the walk, whose entry says whether it is a symlink.
+    Walked { is_symlink: bool },
+    /// Only in memory, such as an archive entry.
+    Memory(&'a [u8]),
+}
+
+/// Compile `patterns` the way all filter globs are: with `*` stopping at `/`,
+/// and ignoring case when `case_insensitive` is on.
+fn build_glob_set(patterns: &[String], case_insensitive: bool) -> DumpResult<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .literal_separator(true)
+            .build()
+            .context(InvalidGlobSnafu {
+                pattern: pattern.clone(),
+            })?;
+        builder.add(glob);
+    }
+    builder.build().context(GlobSetBuildSnafu)
+}
+
+/// The components of `pattern` before its first wildcard, and whether one follows.
+fn literal_prefix(pattern: &str) -> (PathBuf, bool) {
+    let mut prefix = PathBuf::new();
+    let pattern = pattern.trim_start_matches("./");
+    for part in pattern.split('/') {
+        if part.contains(['*', '?', '[', '{', '\\']) {
+            return (prefix, true);
+        }
+        prefix.push(part);
+    }
+    (prefix, false)
+}
+
+/// File names that are generated code whatever they contain, for `skip_generated`.
+const GENERATED_NAMES: &[&str] = &[
+    "**/*_pb2.py",
+    "**/*_pb2.pyi",
+    "**/*_pb2_grpc.py",
+    "**/*.pb.go",
+    "**/*.pb.cc",
+    "**/*.pb.h",
+    "**/*_pb.js",
+    "**/*_pb.d.ts",
+    "**/*.g.dart",
+    "**/*.freezed.dart",
+    "**/*.generated.*",
+];
+
+/// How much of a file binary and generated-code sniffing look at.
+const HEAD_LEN: u64 = 8192;
+
+/// With fewer than [`MINIFIED_MAX_LINES`] lines, more bytes than this make a
+/// file minified whatever `minified_line_length` says.
+const MINIFIED_DENSE_BYTES: usize = 5 * 1024;
+const MINIFIED_MAX_LINES: usize = 3;
+
+/// Why `head` (the start of a file) looks minified, if it does: over
+/// [`MINIFIED_DENSE_BYTES`] in fewer than [`MINIFIED_MAX_LINES`] lines, or lines
+/// averaging more than `max_average` bytes.
+fn minified_reason(head: &[u8], max_average: usize) -> Option<String> {
+    let lines = count_lines(head).max(1);
+    if lines < MINIFIED_MAX_LINES && head.len() > MINIFIED_DENSE_BYTES {
+        let s = if lines == 1 { "" } else { "s" };
+        return Some(format!("{} bytes in {lines} line{s}", head.len()));
+    }
+    let average = head.len() / lines;
+    (average > max_average).then(|| format!("lines average {average} bytes, over {max_average}"))
+}
+
+/// Up to `limit` bytes from the start of the file, or `None` if it cannot be read.
+fn read_head(path: &Path, limit: u64) -> Option<Vec<u8>> {
+    use std::{fs::File, io::Read};
+
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(limit)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(head)
+}
+
+/// The extension the `*_extensions` lists match: `Path::extension`, or for a
+/// dotfile without one (`.gitignore`, `.npmrc`) the name after the dot.
+fn extension(path: &Path) -> Option<&std::ffi::OsStr> {
+    path.extension().or_else(|| {
+        let name = path.file_name()?.to_str()?;
+        let rest = name.strip_prefix('.')?;
+        (!rest.is_empty() && !rest.contains('.')).then_some(std::ffi::OsStr::new(rest))
+    })
+}
+
+/// Whether Windows marks `path` hidden with `FILE_ATTRIBUTE_HIDDEN`. A file
+/// whose metadata cannot be read is not hidden.
+#[cfg(windows)]
+fn has_hidden_attribute(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    fs::metadata(path).is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+/// Elsewhere only the dot-prefix rule applies, at no metadata cost.
+#[cfg(not(windows))]
+fn has_hidden_attribute(_path: &Path) -> bool {
+    false
+}
+
+/// Lines in `bytes` as `str::lines` would count them, from newline bytes alone
+/// so the content need not be decoded.
+fn count_lines(bytes: &[u8]) -> usize {
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    newlines + usize::from(bytes.last().is_some_and(|&b| b != b'\n'))
+}
+
+/// All of `reader` if it holds at most `max` lines, as [`count_lines`] counts
+/// them, or `None` if it holds more. Newlines are counted 64KB at a time, and
+/// reading stops as soon as `max` is passed.
+fn read_within_lines(
+    mut reader: impl std::io::Read,
+    max: usize,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut whole = Vec::new();
+    let mut newlines = 0;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        newlines += buf[..n].iter().filter(|&&b| b == b'\n').count();
+        if newlines > max {
+            return Ok(None);
+        }
+        whole.extend_from_slice(&buf[..n]);
+    }
+    Ok((count_lines(&whole) <= max).then_some(whole))
+}
+
+/// Whether a file's head (see [`read_head`]) looks binary.
+///
+/// UTF-16 (with or without a byte order mark) and BOM'd UTF-8 are text, even though
+/// UTF-16 interleaves NUL bytes. Other non-UTF-8 text such as Latin-1 contains no
+/// NULs and passes the null byte check. A script opening with a printable
+/// `#!` line is not judged by its sniffed MIME type, but a null byte still
+/// makes it binary.
+fn looks_binary(head: &[u8]) -> bool {
+    if encoding::sniff_unicode(head).is_some() {
+        return false;
+    }
+
+    if !has_shebang(head) && sniff_mime(head).is_some_and(|mime| !mime.starts_with("text/")) {
+        return true;
+    }
+
+    head.contains(&0u8)
+}
+
+/// Whether `head` opens with a `#!` line of printable ASCII, e.g.
+/// `#!/usr/bin/env bash`.
+fn has_shebang(head: &[u8]) -> bool {
+    let Some(rest) = head.strip_prefix(b"#!") else {
+        return false;
+    };
+    let line = &rest[..rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len())];
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    !line.is_empty()
+        && line
+            .iter()
+            .all(|&b| b == b'\t' || (b' '..=b'~').contains(&b))
+}
+
+/// The MIME type `infer` recognizes from a file's first bytes, e.g.
+/// "image/png". Unicode text marked by a byte order mark has none.
+pub fn sniff_mime(head: &[u8]) -> Option<&'static str> {
+    if encoding::sniff_unicode(head).is_some() {
+        return None;
+    }
+    infer::get(head).map(|kind| kind.mime_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, BinaryConfig, FilterConfig};
+
+    fn filter_from(cfg: AppConfig) -> Filter {
+        Filter::new(&cfg).expect("Filter::new failed")
+    }
+
+    fn bare() -> AppConfig {
+        AppConfig {
+            skip_extensions: vec![],
+            skip_patterns: vec![],
+            skip_filenames: vec![],
+            skip_path_components: vec![],
+            skip_globs: vec![],
+            skip_binary: false,
+            skip_hidden: false,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn skips_matching_extension() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("Cargo.lock")));
+    }
+
+    #[test]
+    fn keeps_non_matching_extension() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("main.rs")));
+    }
+
+    #[test]
+    fn extension_check_is_case_insensitive() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("Cargo.LOCK")));
+    }
+
+    #[test]
+    fn skips_exact_filename_no_extension() {
+        let f = filter_from(AppConfig {
+            skip_filenames: vec!["makefile".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn skips_exact_filename_with_extension() {
+        let f = filter_from(AppConfig {
+            skip_filenames: vec!["readme".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("README.md")));
+    }
+
+    #[test]
+    fn filename_check_is_case_insensitive() {
+        let f = filter_from(AppConfig {
+            skip_filenames: vec!["dockerfile".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("DOCKERFILE")));
+    }
+
+    #[test]
+    fn keeps_non_matching_filename() {
+        let f = filter_from(AppConfig {
+            skip_filenames: vec!["makefile".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("main.rs")));
+    }
+
+    #[test]
+    fn exact_filenames_match_the_complete_name_only() {
+        let f = filter_from(AppConfig {
+            skip_filenames: vec!["readme".into()],
+            skip_filenames_exact: vec!["package-lock.json".into(), ".env.local".into()],
+            ..bare()
+        });
+        assert_eq!(
+            skipped_by(&f, "web/Package-Lock.json"),
+            "skip_filenames_exact: package-lock.json"
+        );
+        assert!(!f.should_skip(Path::new("package-lock.rs")));
+        assert!(!f.should_skip(Path::new("package-lock")));
+        assert_eq!(
+            skipped_by(&f, ".env.local"),
+            "skip_filenames_exact: .env.local"
+        );
+        assert!(!f.should_skip(Path::new(".env")));
+        assert!(!f.should_skip(Path::new(".env.local.example")));
+        // Stem matching stays with skip_filenames; a name without an extension
+        // is its own stem and matches once.
+        assert_eq!(skipped_by(&f, "README"), "skip_filenames: readme");
+        assert_eq!(skipped_by(&f, "README.md"), "skip_filenames: readme");
+    }
+
+    #[test]
+    fn skips_file_inside_blocked_component() {
+        let f = filter_from(AppConfig {
+            skip_path_components: vec!["node_modules".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("node_modules/lodash/index.js")));
+    }
+
+    #[test]
+    fn skips_deeply_nested_blocked_component() {
+        let f = filter_from(AppConfig {
+            skip_path_components: vec![".github".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("project/.github/workflows/ci.yml")));
+    }
+
+    #[test]
+    fn keeps_file_with_no_blocked_component() {
+        let f = filter_from(AppConfig {
+            skip_path_components: vec!["node_modules".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("src/index.js")));
+    }
+
+    #[test]
+    fn components_above_the_root_never_match() {
+        let f = filter_from(AppConfig {
+            skip_path_components: vec!["node_modules".into()],
+            skip_hidden: true,
+            ..bare()
+        });
+        let root = Path::new("/home/me/node_modules/.work/proj");
+        let rooted = f.for_root(root);
+        assert!(!rooted.should_skip(&root.join("src/index.js")));
+        assert!(rooted.should_skip(&root.join("web/node_modules/pkg/index.js")));
+        assert!(!rooted.should_skip_dir(&root.join("src")));
+        // Outside any root, an absolute path is judged by its file name alone.
+        assert!(!f.should_skip(&root.join("src/index.js")));
+        assert!(f.should_skip(&root.join(".env")));
+    }
+
+    #[test]
+    fn skips_hidden_file_when_enabled() {
+        let f = filter_from(AppConfig {
+            skip_hidden: true,
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new(".env")));
+    }
+
+    #[test]
+    fn skips_file_inside_hidden_dir() {
+        let f = filter_from(AppConfig {
+            skip_hidden: true,
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new(".config/something.toml")));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn skips_files_and_dirs_with_hidden_attribute() {
+        use std::os::windows::fs::OpenOptionsExt;
+
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("desktop.ini");
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .attributes(FILE_ATTRIBUTE_HIDDEN)
+            .open(&file)
+            .unwrap();
+        let sub = dir.path().join("cache");
+        fs::create_dir(&sub).unwrap();
+        let status = std::process::Command::new("attrib")
+            .arg("+h")
+            .arg(&sub)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let f = filter_from(AppConfig {
+            skip_hidden: true,
+            ..bare()
+        });
+        assert_eq!(f.skip_reason(&file), Some(SkipReason::Hidden));
+        assert_eq!(f.dir_skip_reason(&sub), Some(SkipReason::Hidden));
+        assert!(!f.should_skip(&dir.path().join("visible.txt")));
+        let off = filter_from(bare());
+        assert!(!off.should_skip(&file));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn only_dot_names_are_hidden_off_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("desktop.ini");
+        fs::write(&file, "x").unwrap();
+        let f = filter_from(AppConfig {
+            skip_hidden: true,
+            ..bare()
+        });
+        assert!(!f.should_skip(&file));
+        assert!(f.should_skip(&dir.path().join(".env")));
+    }
+
+    #[test]
+    fn dotfile_name_counts_as_extension_with_hidden_filtering_off() {
+        let f = filter_from(AppConfig {
+            skip_hidden: false,
+            ..AppConfig::default()
+        });
+        assert_eq!(skipped_by(&f, ".gitignore"), "skip_extensions: gitignore");
+        assert_eq!(
+            skipped_by(&f, "sub/.gitignore"),
+            "skip_extensions: gitignore"
+        );
+        assert!(!f.should_skip(Path::new(".env")));
+        assert!(!f.should_skip(Path::new(".env.local")));
+    }
+
+    #[test]
+    fn dotfiles_can_be_included_and_skipped_by_extension() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["npmrc".into()],
+            include_extensions: vec!["dockerignore".into(), "rs".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new(".dockerignore")));
+        assert_eq!(
+            f.skip_reason(Path::new(".npmrc")),
+            Some(SkipReason::Extension)
+        );
+        assert_eq!(
+            f.skip_reason(Path::new(".editorconfig")),
+            Some(SkipReason::NotIncluded)
+        );
+        // "." and ".." are not dotfiles.
+        assert_eq!(extension(Path::new("..")), None);
+        assert_eq!(extension(Path::new("a/.")), None);
+    }
+
+    #[test]
+    fn keeps_hidden_file_when_disabled() {
+        let f = filter_from(AppConfig {
+            skip_hidden: false,
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new(".env")));
+    }
+
+    #[test]
+    fn dot_single_not_treated_as_hidden() {
+        let f = filter_from(AppConfig {
+            skip_hidden: true,
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("./src/main.rs")));
+    }
+
+    #[test]
+    fn skips_file_matching_regex_pattern() {
+        let f = filter_from(AppConfig {
+            skip_patterns: vec![r".*test.*\.rs$".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("src/foo_test.rs")));
+    }
+
+    #[test]
+    fn regex_pattern_is_case_insensitive() {
+        let f = filter_from(AppConfig {
+            skip_patterns: vec![r".*test.*\.rs$".into()],
+            ..bare()
+        });
+        assert!(f.should_skip(Path::new("src/FooTEST.rs")));
+    }
+
+    #[test]
+    fn keeps_file_not_matching_regex() {
+        let f = filter_from(AppConfig {
+            skip_patterns: vec![r".*test.*\.rs$".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn invalid_regex_returns_typed_error() {
+        let result = Filter::new(&AppConfig {
+            skip_patterns: vec!["[invalid".into()],
+            ..bare()
+        });
+        let err = result.unwrap_err();
+        assert!(matches!(err, crate::errors::DumpError::InvalidRegex { .. }));
+
+        let code = miette::Diagnostic::code(&err).map(|c| c.to_string());
+        assert_eq!(code.as_deref(), Some("dump_dir::filter::invalid_regex"));
+        assert!(miette::Diagnostic::help(&err).is_some());
+    }
+
+    #[test]
+    fn invalid_content_regex_names_the_pattern() {
+        let result = Filter::new(&AppConfig {
+            skip_content_patterns: vec!["(unclosed".into()],
+            ..bare()
+        });
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::InvalidRegex { pattern, .. } if pattern == "(unclosed"
+        ));
+    }
+
+    #[test]
+    fn skips_file_whose_content_matches() {
+        let f = filter_from(AppConfig {
+            skip_content_patterns: vec!["PROPRIETARY".into(), "do not distribute".into()],
+            ..bare()
+        });
+        let secret = temp_file(b"fn main() {}\n// Proprietary: ACME Corp\n");
+        assert_eq!(f.skip_reason(secret.path()), Some(SkipReason::Content));
+        let open = temp_file(b"fn main() {}\n");
+        assert_eq!(f.skip_reason(open.path()), None);
+    }
+
+    #[test]
+    fn content_match_beyond_the_sniffed_head_is_found() {
+        let f = filter_from(AppConfig {
+            skip_content_patterns: vec!["PROPRIETARY".into()],
+            ..bare()
+        });
+        let mut content = vec![b'x'; 3 * HEAD_LEN as usize];
+        content.extend_from_slice(b"\nPROPRIETARY\n");
+        assert_eq!(
+            f.skip_reason(temp_file(&content).path()),
+            Some(SkipReason::Content)
+        );
+    }
+
+    #[test]
+    fn files_over_content_scan_limit_are_not_scanned() {
+        let f = filter_from(AppConfig {
+            skip_content_patterns: vec!["PROPRIETARY".into()],
+            content_scan_limit: "16".into(),
+            ..bare()
+        });
+        let small = temp_file(b"PROPRIETARY\n");
+        assert_eq!(f.skip_reason(small.path()), Some(SkipReason::Content));
+        let large = temp_file(b"PROPRIETARY and then some more\n");
+        assert_eq!(f.skip_reason(large.path()), None);
+    }
+
+    #[test]
+    fn binary_check_wins_over_content_patterns() {
+        let f = filter_from(AppConfig {
+            skip_binary: true,
+            skip_content_patterns: vec!["ELF".into()],
+            ..bare()
+        });
+        let blob = temp_file(b"ELF\x00\x01\x02");
+        assert_eq!(f.skip_reason(blob.path()), Some(SkipReason::Binary));
+    }
+
+    #[test]
+    fn walked_files_take_the_symlink_flag_from_the_walk() {
+        let f = Filter::builder().symlinks(Symlinks::Skip).build().unwrap();
+        let file = temp_file(b"fn main() {}\n");
+        assert_eq!(f.skip_reason(file.path()), None);
+        assert_eq!(
+            f.walked_skip_reason(file.path(), true),
+            Some(SkipReason::Symlink)
+        );
+        assert_eq!(f.walked_skip_reason(file.path(), false), None);
+    }
+
+    fn skipped_by(f: &Filter, path: &str) -> String {
+        match f.check(Path::new(path)) {
+            FilterDecision::Skipped {
+                rule, ..
+            } => rule,
+            FilterDecision::Included => panic!("{path} was included"),
+        }
+    }
+
+    #[test]
+    fn check_names_the_rule_and_matching_value() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            skip_filenames: vec!["makefile".into()],
+            skip_patterns: vec![r"_test\.go$".into()],
+            skip_globs: vec!["**/*.min.js".into(), "**/target/**".into()],
+            skip_path_components: vec!["node_modules".into()],
+            ..bare()
+        });
+        assert_eq!(skipped_by(&f, "Cargo.lock"), "skip_extensions: lock");
+        assert_eq!(skipped_by(&f, "Makefile"), "skip_filenames: makefile");
+        assert_eq!(
+            skipped_by(&f, "pkg/a_test.go"),
+            r"skip_patterns: _test\.go$"
+        );
+        assert_eq!(
+            skipped_by(&f, "project/target/out.rs"),
+            "skip_globs: **/target/**"
+        );
+        assert_eq!(
+            skipped_by(&f, "web/node_modules/x/index.js"),
+            "skip_path_components: node_modules"
+        );
+        assert_eq!(f.check(Path::new("src/main.rs")), FilterDecision::Included);
+    }
+
+    #[test]
+    fn check_names_the_exact_glob_entry_around_exceptions() {
+        let f = filter_from(AppConfig {
+            skip_globs: vec![
+                "!**/keep/**".into(),
+                "**/*.log".into(),
+                "!**/important.tmp".into(),
+                "**/*.tmp".into(),
+            ],
+            ..bare()
+        });
+        assert_eq!(skipped_by(&f, "out/run.log"), "skip_globs: **/*.log");
+        assert_eq!(skipped_by(&f, "out/scratch.tmp"), "skip_globs: **/*.tmp");
+        assert!(!f.should_skip(Path::new("keep/run.log")));
+        assert!(!f.should_skip(Path::new("out/important.tmp")));
+    }
+
+    #[test]
+    fn check_reports_include_and_content_rules() {
+        let f = filter_from(AppConfig {
+            include_extensions: vec!["rs".into()],
+            skip_content_patterns: vec!["PROPRIETARY".into()],
+            ..bare()
+        });
+        assert!(skipped_by(&f, "notes.md").starts_with("include rules: "));
+        let file = tempfile::Builder::new().suffix(".rs").tempfile().unwrap();
+        fs::write(file.path(), "// PROPRIETARY\n").unwrap();
+        assert_eq!(f.check(file.path()), FilterDecision::Skipped {
+            reason: SkipReason::Content,
+            rule: "skip_content_patterns: PROPRIETARY".into(),
+        });
+    }
+
+    #[test]
+    fn check_reports_the_mtime_window() {
+        let f = filter_from(AppConfig {
+            changed_before: Some("2000-01-01".into()),
+            ..bare()
+        });
+        let file = temp_file(b"x");
+        let rule = match f.check(file.path()) {
+            FilterDecision::Skipped {
+                reason: SkipReason::Modified,
+                rule,
+            } => rule,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert!(rule.starts_with("changed_before: modified "), "{rule}");
+        assert!(rule.ends_with("not before 2000-01-01T00:00:00Z"), "{rule}");
+    }
+
+    #[test]
+    fn skip_reason_names_the_matching_rule() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            skip_filenames: vec!["license".into()],
+            skip_path_components: vec!["node_modules".into()],
+            skip_patterns: vec![r"\.gen\.rs$".into()],
+            skip_globs: vec!["**/*.min.js".into()],
+            skip_hidden: true,
+            ..bare()
+        });
+        let reason = |p: &str| f.skip_reason(Path::new(p));
+        assert_eq!(reason("Cargo.lock"), Some(SkipReason::Extension));
+        assert_eq!(reason("LICENSE"), Some(SkipReason::Filename));
+        assert_eq!(reason("node_modules/a.js"), Some(SkipReason::PathComponent));
+        assert_eq!(reason("src/api.gen.rs"), Some(SkipReason::Pattern));
+        assert_eq!(reason("web/app.min.js"), Some(SkipReason::Glob));
+        assert_eq!(reason(".env"), Some(SkipReason::Hidden));
+        assert_eq!(reason("src/main.rs"), None);
+    }
+
+    #[test]
+    fn skip_reason_names_the_language_and_content_stages() {
+        let stage = |builder: FilterBuilder, file: &tempfile::NamedTempFile| {
+            builder.build().unwrap().skip_reason(file.path())
+        };
+        let text = temp_file_with_ext("rs", b"fn main() {}\n// SECRET\n");
+        let blob = temp_file(b"\x7fELF\x00\x01");
+
+        assert_eq!(
+            stage(Filter::builder().skip_language("rust"), &text),
+            Some(SkipReason::Language)
+        );
+        assert_eq!(
+            stage(Filter::builder().include_language("toml"), &text),
+            Some(SkipReason::NotIncluded)
+        );
+        assert_eq!(
+            stage(Filter::builder().skip_larger_than("10"), &text),
+            Some(SkipReason::TooLarge)
+        );
+        assert_eq!(
+            stage(Filter::builder().max_lines_per_file(1), &text),
+            Some(SkipReason::TooManyLines)
+        );
+        assert_eq!(
+            stage(Filter::builder().skip_content_pattern("SECRET"), &text),
+            Some(SkipReason::Content)
+        );
+        assert_eq!(
+            stage(Filter::builder().skip_binary(true), &blob),
+            Some(SkipReason::Binary)
+        );
+        assert_eq!(stage(Filter::builder(), &text), None);
+    }
+
+    #[test]
+    fn skip_reasons_serialize_as_snake_case_names() {
+        let json = serde_json::to_string(&[
+            SkipReason::PathComponent,
+            SkipReason::Pattern,
+            SkipReason::TooManyLines,
+            SkipReason::NotIncluded,
+        ])
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"["path_component","pattern","too_many_lines","not_included"]"#
+        );
+        let back: Vec<SkipReason> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back[2], SkipReason::TooManyLines);
+    }
+
+    #[test]
+    fn dir_skip_reason_names_the_matching_rule() {
+        let f = filter_from(AppConfig {
+            skip_path_components: vec!["node_modules".into()],
+            skip_globs: vec!["**/target/**".into()],
+            skip_hidden: true,
+            ..bare()
+        });
+        let reason = |p: &str| f.dir_skip_reason(Path::new(p));
+        assert_eq!(reason("web/node_modules"), Some(SkipReason::PathComponent));
+        assert_eq!(reason("proj/target"), Some(SkipReason::Glob));
+        assert_eq!(reason("proj/.cache"), Some(SkipReason::Hidden));
+        assert_eq!(reason("proj/src"), None);
+    }
+
+    #[test]
+    fn include_lists_keep_only_matching_files() {
+        let f = filter_from(AppConfig {
+            include_extensions: vec!["RS".into()],
+            include_filenames: vec!["cargo.toml".into()],
+            include_globs: vec!["docs/**".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("src/main.rs")));
+        assert!(!f.should_skip(Path::new("Cargo.toml")));
+        assert!(!f.should_skip(Path::new("./docs/guide.md")));
+        assert_eq!(
+            f.skip_reason(Path::new("src/other.toml")),
+            Some(SkipReason::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn skip_rules_still_apply_to_included_files() {
+        let f = filter_from(AppConfig {
+            include_extensions: vec!["rs".into()],
+            skip_patterns: vec![r"_test\.rs$".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("src/lib.rs")));
+        assert_eq!(
+            f.skip_reason(Path::new("src/lib_test.rs")),
+            Some(SkipReason::Pattern)
+        );
+    }
+
+    #[test]
+    fn include_languages_cover_extensions_and_filenames() {
+        let f = filter_from(AppConfig {
+            include_languages: vec!["rust".into(), "Make".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip(Path::new("src/main.rs")));
+        assert!(!f.should_skip(Path::new("Makefile")));
+        assert!(!f.should_skip(Path::new("build/rules.mk")));
+        assert_eq!(
+            f.skip_reason(Path::new("Cargo.toml")),
+            Some(SkipReason::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn include_languages_override_the_default_skip_filenames() {
+        let f = filter_from(AppConfig {
+            include_languages: vec!["make".into(), "docker".into(), "markdown".into()],
+            ..AppConfig::default()
+        });
+        assert!(!f.should_skip(Path::new("Makefile")));
+        assert!(!f.should_skip(Path::new("ci/Dockerfile")));
+        // Only a language's own file names get through, not its extensions.
+        assert_eq!(
+            f.skip_reason(Path::new("README.md")),
+            Some(SkipReason::Filename)
+        );
+    }
+
+    #[test]
+    fn always_include_bypasses_every_file_rule() {
+        let f = Filter::builder()
+            .skip_hidden(true)
+            .skip_extension("lock")
+            .skip_path_component("target")
+            .include_extension("rs")
+            .skip_binary(true)
+            .changed_since("2999-01-01")
+            .always_include("crates/cli/Cargo.lock")
+            .always_include(".env")
+            .always_include("**/*.bin")
+            .build()
+            .unwrap();
+        assert_eq!(f.skip_reason(Path::new("crates/cli/Cargo.lock")), None);
+        assert_eq!(f.skip_reason(Path::new("./.env")), None);
+        assert_eq!(
+            f.skip_reason(Path::new("crates/lib/Cargo.lock")),
+            Some(SkipReason::Extension)
+        );
+        assert_eq!(
+            f.skip_reason(Path::new("README.md")),
+            Some(SkipReason::NotIncluded)
+        );
+
+        // Binary, hidden (a `.tmp` name) and outside the modification window.
+        let blob = temp_file_with_ext("bin", b"\x00\x01\x02");
+        assert_eq!(f.check(blob.path()), FilterDecision::Included);
+    }
+
+    #[test]
+    fn always_include_keeps_its_ancestor_directories() {
+        let f = Filter::builder()
+            .skip_hidden(true)
+            .skip_path_component("node_modules")
+            .skip_glob("vendor/**")
+            .always_include("node_modules/pkg/index.js")
+            .always_include(".github/workflows/*.yml")
+            .always_include("vendor/*/LICENSE")
+            .build()
+            .unwrap();
+        let pruned = |p: &str| f.dir_skip_reason(Path::new(p));
+        assert_eq!(pruned("node_modules"), None);
+        assert_eq!(pruned("node_modules/pkg"), None);
+        assert_eq!(
+            pruned("./node_modules/other"),
+            Some(SkipReason::PathComponent)
+        );
+        assert_eq!(pruned("web/node_modules"), Some(SkipReason::PathComponent));
+        assert_eq!(pruned(".github/workflows"), None);
+        assert_eq!(pruned(".cache"), Some(SkipReason::Hidden));
+        // A wildcard may match at any depth below its literal prefix.
+        assert_eq!(pruned("vendor/serde/src"), None);
+        assert_eq!(f.skip_reason(Path::new("vendor/serde/LICENSE")), None);
+        assert_eq!(
+            f.skip_reason(Path::new("vendor/serde/src/lib.rs")),
+            Some(SkipReason::Glob)
+        );
+    }
+
+    #[test]
+    fn always_include_starting_with_a_wildcard_keeps_pruning() {
+        let f = Filter::builder()
+            .skip_hidden(true)
+            .skip_path_component("target")
+            .always_include("**/Cargo.lock")
+            .always_include("*.md")
+            .build()
+            .unwrap();
+        let pruned = |p: &str| f.dir_skip_reason(Path::new(p));
+        assert_eq!(pruned(".cache"), Some(SkipReason::Hidden));
+        assert_eq!(pruned("crates/target"), Some(SkipReason::PathComponent));
+        assert_eq!(pruned("crates/cli"), None);
+        // Files in directories the walk still visits match as usual.
+        assert_eq!(f.skip_reason(Path::new("crates/cli/Cargo.lock")), None);
+        assert_eq!(f.skip_reason(Path::new(".hidden.md")), None);
+    }
+
+    #[test]
+    fn skip_languages_name_the_language() {
+        let f = Filter::builder()
+            .case_insensitive(false)
+            .skip_language("docker")
+            .build()
+            .unwrap();
+        assert_eq!(skipped_by(&f, "ci/Dockerfile"), "skip_languages: docker");
+        assert_eq!(skipped_by(&f, "app.dockerfile"), "skip_languages: docker");
+        assert!(!f.should_skip(Path::new("src/main.rs")));
+    }
+
+    #[test]
+    fn unknown_language_fails_at_build() {
+        let err = Filter::builder().include_language("klingon").build();
+        assert!(matches!(
+            err,
+            Err(crate::errors::DumpError::UnknownLanguage { .. })
+        ));
+    }
+
+    #[test]
+    fn skip_groups_add_to_the_configured_skip_lists() {
+        let f = filter_from(AppConfig {
+            skip_extensions: vec!["log".into()],
+            skip_groups: vec!["lockfiles".into(), "editor-junk".into()],
+            ..bare()
+        });
+        // The configured list still applies; each group adds its entries.
+        assert!(f.should_skip(Path::new("debug.log")));
+        assert!(f.should_skip(Path::new("web/yarn.lock")));
+        assert!(f.should_skip(Path::new("Gemfile.lock")));
+        assert!(f.should_skip(Path::new("src/main.rs.swp")));
+        assert!(f.should_skip(Path::new("src/main.rs~")));
+        assert!(f.should_skip_dir(Path::new(".idea")));
+        assert!(!f.should_skip(Path::new("src/main.rs")));
+        assert!(!f.should_skip(Path::new("logo.png")));
+    }
+
+    #[test]
+    fn unknown_skip_group_fails_at_build() {
+        let err = Filter::builder().skip_group("videos").build();
+        assert!(matches!(
+            err,
+            Err(crate::errors::DumpError::UnknownGroup { .. })
+        ));
+    }
+
+    #[test]
+    fn include_globs_never_prune_directories() {
+        let f = filter_from(AppConfig {
+            include_globs: vec!["**/*.rs".into()],
+            ..bare()
+        });
+        assert!(!f.should_skip_dir(Path::new("src")));
+    }
 
-    fn filter_from(cfg: AppConfig) -> Filter {
-        Filter::new(&cfg).expect("Filter::new failed")
+    #[test]
+    fn filter_stats_merge_adds_counts() {
+        let mut a = FilterStats::default();
+        a.record_file(Path::new("a.lock"), SkipReason::Extension);
+        a.record_dir(Path::new("dist"), SkipReason::Glob);
+        let mut b = FilterStats::default();
+        b.record_file(Path::new("b.lock"), SkipReason::Extension);
+        b.record_file(Path::new("blob"), SkipReason::Binary);
+        a.merge(&b);
+        assert_eq!(a.files_skipped[&SkipReason::Extension], 2);
+        assert_eq!(a.files_skipped[&SkipReason::Binary], 1);
+        assert_eq!(a.dirs_pruned[&SkipReason::Glob], 1);
+        assert_eq!(a.total_files_skipped(), 3);
+        assert_eq!(a.skipped.len(), 4);
     }
 
-    fn bare() -> AppConfig {
-        AppConfig {
-            skip_extensions: vec![],
-            skip_patterns: vec![],
-            skip_filenames: vec![],
-            skip_path_components: vec![],
-            skip_globs: vec![],
-            skip_binary: false,
-            skip_hidden: false,
-        }
+    #[test]
+    fn filter_stats_display_groups_paths_by_reason() {
+        let mut stats = FilterStats::default();
+        stats.record_file(Path::new("Cargo.lock"), SkipReason::Extension);
+        stats.record_dir(Path::new("node_modules"), SkipReason::PathComponent);
+        stats.record_file(Path::new("yarn.lock"), SkipReason::Extension);
+        stats.record_file(Path::new("debug.log"), SkipReason::Gitignore);
+
+        assert_eq!(
+            stats.to_string(),
+            "── Skipped: 3 files, 1 directory\n\
+             component:\n  node_modules/\n\
+             extension:\n  Cargo.lock\n  yarn.lock\n\
+             gitignore:\n  debug.log"
+        );
     }
 
     #[test]
-    fn skips_matching_extension() {
+    fn skips_file_matching_double_star_glob() {
         let f = filter_from(AppConfig {
-            skip_extensions: vec!["lock".into()],
+            skip_globs: vec!["**/target/**".into()],
             ..bare()
         });
-        assert!(f.should_skip(Path::new("Cargo.lock")));
+        assert!(f.should_skip(Path::new("my_project/target/debug/dump-dir")));
     }
 
     #[test]
-    fn keeps_non_matching_extension() {
+    fn skips_file_matching_extension_glob() {
         let f = filter_from(AppConfig {
-            skip_extensions: vec!["lock".into()],
+            skip_globs: vec!["**/*.min.js".into()],
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("main.rs")));
+        assert!(f.should_skip(Path::new("static/app.min.js")));
     }
 
     #[test]
-    fn extension_check_is_case_insensitive() {
+    fn glob_is_case_insensitive() {
         let f = filter_from(AppConfig {
-            skip_extensions: vec!["lock".into()],
+            skip_globs: vec!["**/TARGET/**".into()],
             ..bare()
         });
-        assert!(f.should_skip(Path::new("Cargo.LOCK")));
+        assert!(f.should_skip(Path::new("project/target/release/bin")));
+    }
+
+    fn case_sensitive() -> FilterConfig {
+        FilterConfig {
+            case_insensitive: false,
+        }
     }
 
     #[test]
-    fn skips_exact_filename_no_extension() {
+    fn case_sensitive_filename_distinguishes_makefile() {
         let f = filter_from(AppConfig {
             skip_filenames: vec!["makefile".into()],
+            filter: case_sensitive(),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("Makefile")));
+        assert!(f.should_skip(Path::new("makefile")));
+        assert!(!f.should_skip(Path::new("Makefile")));
     }
 
     #[test]
-    fn skips_exact_filename_with_extension() {
+    fn case_sensitive_filename_does_not_fold_readme() {
         let f = filter_from(AppConfig {
-            skip_filenames: vec!["readme".into()],
+            skip_filenames: vec!["README".into()],
+            filter: case_sensitive(),
             ..bare()
         });
         assert!(f.should_skip(Path::new("README.md")));
+        assert!(!f.should_skip(Path::new("readme.md")));
     }
 
     #[test]
-    fn filename_check_is_case_insensitive() {
+    fn case_sensitive_extension_and_component() {
         let f = filter_from(AppConfig {
-            skip_filenames: vec!["dockerfile".into()],
+            skip_extensions: vec!["lock".into()],
+            skip_path_components: vec!["build".into()],
+            filter: case_sensitive(),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("DOCKERFILE")));
+        assert!(f.should_skip(Path::new("Cargo.lock")));
+        assert!(!f.should_skip(Path::new("Cargo.LOCK")));
+        assert!(f.should_skip_dir(Path::new("build")));
+        assert!(!f.should_skip_dir(Path::new("Build")));
+        assert!(!f.should_skip(Path::new("Build/out.rs")));
     }
 
     #[test]
-    fn keeps_non_matching_filename() {
+    fn case_sensitive_regex_and_glob() {
         let f = filter_from(AppConfig {
-            skip_filenames: vec!["makefile".into()],
+            skip_patterns: vec![r"\.min\.js$".into()],
+            skip_globs: vec!["**/target/**".into()],
+            filter: case_sensitive(),
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("main.rs")));
+        assert!(f.should_skip(Path::new("app.min.js")));
+        assert!(!f.should_skip(Path::new("app.MIN.js")));
+        assert!(f.should_skip(Path::new("project/target/release/bin")));
+        assert!(!f.should_skip(Path::new("project/TARGET/release/bin")));
     }
 
     #[test]
-    fn skips_file_inside_blocked_component() {
+    fn case_sensitive_include_rules() {
         let f = filter_from(AppConfig {
-            skip_path_components: vec!["node_modules".into()],
+            include_filenames: vec!["Makefile".into()],
+            filter: case_sensitive(),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("node_modules/lodash/index.js")));
+        assert!(!f.should_skip(Path::new("Makefile")));
+        assert_eq!(
+            f.skip_reason(Path::new("makefile")),
+            Some(SkipReason::NotIncluded)
+        );
     }
 
     #[test]
-    fn skips_deeply_nested_blocked_component() {
+    fn keeps_file_not_matching_glob() {
         let f = filter_from(AppConfig {
-            skip_path_components: vec![".github".into()],
+            skip_globs: vec!["**/target/**".into()],
             ..bare()
         });
-        assert!(f.should_skip(Path::new("project/.github/workflows/ci.yml")));
+        assert!(!f.should_skip(Path::new("src/main.rs")));
     }
 
     #[test]
-    fn keeps_file_with_no_blocked_component() {
+    fn negated_glob_carves_out_an_exception() {
         let f = filter_from(AppConfig {
-            skip_path_components: vec!["node_modules".into()],
+            skip_globs: vec!["vendor/**".into(), "!vendor/patches/**".into()],
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("src/index.js")));
+        assert_eq!(
+            f.skip_reason(Path::new("vendor/lib/a.rs")),
+            Some(SkipReason::Glob)
+        );
+        assert!(!f.should_skip(Path::new("vendor/patches/fix.diff")));
     }
 
     #[test]
-    fn skips_hidden_file_when_enabled() {
+    fn negated_glob_only_keeps_the_directories_it_could_match_below() {
         let f = filter_from(AppConfig {
-            skip_hidden: true,
+            skip_globs: vec![
+                "vendor/**".into(),
+                "dist/**".into(),
+                "!vendor/patches/**".into(),
+            ],
             ..bare()
         });
-        assert!(f.should_skip(Path::new(".env")));
+        assert!(!f.should_skip_dir(Path::new("vendor")));
+        assert!(!f.should_skip_dir(Path::new("vendor/patches")));
+        assert!(!f.should_skip_dir(Path::new("vendor/patches/deep")));
+        assert_eq!(
+            f.dir_skip_reason(Path::new("vendor/lib")),
+            Some(SkipReason::Glob)
+        );
+        assert_eq!(f.dir_skip_reason(Path::new("dist")), Some(SkipReason::Glob));
     }
 
     #[test]
-    fn skips_file_inside_hidden_dir() {
+    fn negated_glob_keeps_nested_directory() {
         let f = filter_from(AppConfig {
-            skip_hidden: true,
+            skip_globs: vec!["**/target/**".into(), "!**/target/doc/**".into()],
             ..bare()
         });
-        assert!(f.should_skip(Path::new(".config/something.toml")));
+        assert!(f.should_skip(Path::new("proj/target/debug/bin")));
+        assert!(!f.should_skip(Path::new("proj/target/doc/index.html")));
+        assert!(!f.should_skip(Path::new("a/b/target/doc/x/y.html")));
+        assert!(!f.should_skip_dir(Path::new("proj/target")));
     }
 
     #[test]
-    fn keeps_hidden_file_when_disabled() {
+    fn negated_glob_order_does_not_matter() {
         let f = filter_from(AppConfig {
-            skip_hidden: false,
+            skip_globs: vec!["!vendor/patches/**".into(), "vendor/**".into()],
             ..bare()
         });
-        assert!(!f.should_skip(Path::new(".env")));
+        assert!(!f.should_skip(Path::new("vendor/patches/fix.diff")));
+        assert!(f.should_skip(Path::new("vendor/lib/a.rs")));
     }
 
     #[test]
-    fn dot_single_not_treated_as_hidden() {
+    fn negated_glob_does_not_override_other_rules() {
         let f = filter_from(AppConfig {
-            skip_hidden: true,
+            skip_extensions: vec!["orig".into()],
+            skip_globs: vec!["vendor/**".into(), "!vendor/patches/**".into()],
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("./src/main.rs")));
+        assert_eq!(
+            f.skip_reason(Path::new("vendor/patches/fix.orig")),
+            Some(SkipReason::Extension)
+        );
     }
 
     #[test]
-    fn skips_file_matching_regex_pattern() {
+    fn skips_files_larger_than_limit() {
+        let small = temp_file(&[b'a'; 10]);
+        let large = temp_file(&[b'a'; 2048]);
         let f = filter_from(AppConfig {
-            skip_patterns: vec![r".*test.*\.rs$".into()],
+            skip_larger_than: Some("1KiB".into()),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("src/foo_test.rs")));
+        assert_eq!(f.skip_reason(small.path()), None);
+        assert_eq!(f.skip_reason(large.path()), Some(SkipReason::TooLarge));
     }
 
     #[test]
-    fn regex_pattern_is_case_insensitive() {
+    fn skips_files_with_too_many_lines() {
+        let short = temp_file(b"a\nb\nc");
+        let long = temp_file(b"a\nb\nc\nd\n");
+        let f = Filter::builder().max_lines_per_file(3).build().unwrap();
+        assert_eq!(f.skip_reason(short.path()), None);
+        assert_eq!(
+            skipped_by(&f, long.path().to_str().unwrap()),
+            "max_lines_per_file: more than 3 lines"
+        );
+        let off = Filter::builder().max_lines_per_file(0).build().unwrap();
+        assert_eq!(off.skip_reason(long.path()), None);
+    }
+
+    #[test]
+    fn counted_files_are_kept_for_the_printer() {
+        let short = temp_file(b"a\nb\nc");
+        let long = temp_file(b"a\nb\nc\nd\n");
+        let f = Filter::builder().max_lines_per_file(3).build().unwrap();
+        assert_eq!(f.skip_reason(short.path()), None);
+        assert_eq!(f.skip_reason(long.path()), Some(SkipReason::TooManyLines));
+        let counted = f.take_counted();
+        assert_eq!(counted.len(), 1);
+        assert_eq!(counted[short.path()], b"a\nb\nc");
+        assert!(f.take_counted().is_empty());
+        // A file a later check skips is not kept.
         let f = filter_from(AppConfig {
-            skip_patterns: vec![r".*test.*\.rs$".into()],
+            max_lines_per_file: 3,
+            skip_content_patterns: vec!["b".into()],
             ..bare()
         });
-        assert!(f.should_skip(Path::new("src/FooTEST.rs")));
+        assert_eq!(f.skip_reason(short.path()), Some(SkipReason::Content));
+        assert!(f.take_counted().is_empty());
     }
 
     #[test]
-    fn keeps_file_not_matching_regex() {
+    fn skips_minified_assets_by_their_line_lengths() {
+        let f = Filter::builder().skip_minified(true).build().unwrap();
+        let bundle = temp_file_with_ext("js", &[b'x'; 6000]);
+        assert_eq!(
+            skipped_by(&f, bundle.path().to_str().unwrap()),
+            "skip_minified: 6000 bytes in 1 line"
+        );
+        let long_lines = format!("{}\n", "a".repeat(700)).repeat(4);
+        let stylesheet = temp_file_with_ext("css", long_lines.as_bytes());
+        assert_eq!(
+            skipped_by(&f, stylesheet.path().to_str().unwrap()),
+            "skip_minified: lines average 701 bytes, over 500"
+        );
+
+        let source = temp_file_with_ext("js", "let x = 1;\n".repeat(800).as_bytes());
+        assert_eq!(f.skip_reason(source.path()), None);
+        // Only `minified_extensions` are looked at; JSON is opt-in.
+        let data = temp_file_with_ext("json", &[b'1'; 6000]);
+        assert_eq!(f.skip_reason(data.path()), None);
+        let off = Filter::builder().build().unwrap();
+        assert_eq!(off.skip_reason(bundle.path()), None);
+    }
+
+    #[test]
+    fn minified_extensions_and_threshold_are_configurable() {
         let f = filter_from(AppConfig {
-            skip_patterns: vec![r".*test.*\.rs$".into()],
+            skip_minified: true,
+            minified_line_length: 50,
+            minified_extensions: vec!["JSON".into()],
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("src/main.rs")));
+        let record = "{\"key\": \"a fairly long value here\", \"n\": 1234567890}\n";
+        let data = temp_file_with_ext("json", record.repeat(3).as_bytes());
+        assert_eq!(f.skip_reason(data.path()), Some(SkipReason::Minified));
+        let bundle = temp_file_with_ext("js", &[b'x'; 6000]);
+        assert_eq!(f.skip_reason(bundle.path()), None);
     }
 
     #[test]
-    fn invalid_regex_returns_typed_error() {
-        let result = Filter::new(&AppConfig {
-            skip_patterns: vec!["[invalid".into()],
-            ..bare()
-        });
-        assert!(result.is_err());
-        assert!(matches!(
-            result.unwrap_err(),
-            crate::errors::DumpError::InvalidRegex { .. }
-        ));
+    fn count_lines_matches_str_lines() {
+        for text in ["", "a", "a\n", "a\nb", "a\n\nb\n", "\n"] {
+            let lines = text.lines().count();
+            assert_eq!(count_lines(text.as_bytes()), lines, "{text:?}");
+            let within = read_within_lines(text.as_bytes(), lines).unwrap();
+            assert_eq!(within.as_deref(), Some(text.as_bytes()), "{text:?}");
+            if lines > 0 {
+                let over = read_within_lines(text.as_bytes(), lines - 1).unwrap();
+                assert_eq!(over, None, "{text:?}");
+            }
+        }
+        // Reading stops once the limit is passed, even on endless input.
+        assert_eq!(
+            read_within_lines(std::io::repeat(b'\n'), 1000).unwrap(),
+            None
+        );
     }
 
     #[test]
-    fn skips_file_matching_double_star_glob() {
+    fn mtime_window_skips_files_outside_it() {
+        use std::time::{Duration, UNIX_EPOCH};
+
         let f = filter_from(AppConfig {
-            skip_globs: vec!["**/target/**".into()],
+            changed_since: Some("2024-03-01".into()),
+            changed_before: Some("2024-03-08".into()),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("my_project/target/debug/dump-dir")));
+        let day = |d: u64| Some(UNIX_EPOCH + Duration::from_secs(1_709_251_200 + d * 86_400));
+        assert_eq!(f.mtime_skip_reason(|| day(0)), None);
+        assert_eq!(f.mtime_skip_reason(|| day(6)), None);
+        assert_eq!(f.mtime_skip_reason(|| day(7)), Some(SkipReason::Modified));
+        assert_eq!(
+            f.mtime_skip_reason(|| Some(UNIX_EPOCH)),
+            Some(SkipReason::Modified)
+        );
+        assert_eq!(f.mtime_skip_reason(|| None), None);
+        assert_eq!(
+            filter_from(bare()).mtime_skip_reason(|| unreachable!()),
+            None
+        );
     }
 
     #[test]
-    fn skips_file_matching_extension_glob() {
-        let f = filter_from(AppConfig {
-            skip_globs: vec!["**/*.min.js".into()],
+    fn invalid_time_returns_typed_error() {
+        let result = Filter::new(&AppConfig {
+            changed_since: Some("last tuesday".into()),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("static/app.min.js")));
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::InvalidTime { .. }
+        ));
     }
 
     #[test]
-    fn glob_is_case_insensitive() {
-        let f = filter_from(AppConfig {
-            skip_globs: vec!["**/TARGET/**".into()],
+    fn invalid_size_returns_typed_error() {
+        let result = Filter::new(&AppConfig {
+            skip_larger_than: Some("huge".into()),
             ..bare()
         });
-        assert!(f.should_skip(Path::new("project/target/release/bin")));
+        assert!(matches!(
+            result.unwrap_err(),
+            crate::errors::DumpError::InvalidSize { .. }
+        ));
     }
 
     #[test]
-    fn keeps_file_not_matching_glob() {
-        let f = filter_from(AppConfig {
+    fn builder_matches_equivalent_config() {
+        let built = Filter::builder()
+            .skip_extension("lock")
+            .skip_glob("**/target/**")
+            .skip_pattern(r"_test\.rs$")
+            .skip_hidden(true)
+            .build()
+            .unwrap();
+        let configured = filter_from(AppConfig {
+            skip_extensions: vec!["lock".into()],
             skip_globs: vec!["**/target/**".into()],
+            skip_patterns: vec![r"_test\.rs$".into()],
+            skip_hidden: true,
             ..bare()
         });
-        assert!(!f.should_skip(Path::new("src/main.rs")));
+        for path in [
+            "Cargo.lock",
+            "proj/target/debug/bin",
+            "src/a_test.rs",
+            ".env",
+            "src/main.rs",
+        ] {
+            let path = Path::new(path);
+            assert_eq!(
+                built.skip_reason(path),
+                configured.skip_reason(path),
+                "{path:?}"
+            );
+        }
+        assert_eq!(
+            built.dir_skip_reason(Path::new("proj/target")),
+            Some(SkipReason::Glob)
+        );
+    }
+
+    #[test]
+    fn builder_starts_with_no_rules() {
+        let f = FilterBuilder::new().build().unwrap();
+        for path in ["Cargo.lock", ".env", "node_modules/a.js", "README.md"] {
+            assert!(!f.should_skip(Path::new(path)), "{path}");
+        }
+    }
+
+    #[test]
+    fn builder_include_and_exception_chain() {
+        let f = Filter::builder()
+            .include_extension("rs")
+            .skip_glob("vendor/**")
+            .skip_glob("!vendor/patches/**")
+            .build()
+            .unwrap();
+        assert!(!f.should_skip(Path::new("vendor/patches/fix.rs")));
+        assert_eq!(
+            f.skip_reason(Path::new("vendor/lib/a.rs")),
+            Some(SkipReason::Glob)
+        );
+        assert_eq!(
+            f.skip_reason(Path::new("src/notes.md")),
+            Some(SkipReason::NotIncluded)
+        );
+    }
+
+    #[test]
+    fn builder_reports_compile_errors() {
+        let err = Filter::builder().skip_glob("[invalid").build().unwrap_err();
+        assert!(matches!(err, crate::errors::DumpError::InvalidGlob { .. }));
     }
 
     #[test]
@@ -426,6 +2647,254 @@ mod tests {
         ));
     }
 
+    fn temp_file(bytes: &[u8]) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    fn is_binary(path: &Path) -> bool {
+        read_head(path, HEAD_LEN).is_some_and(|head| looks_binary(&head))
+    }
+
+    fn generated() -> AppConfig {
+        AppConfig {
+            skip_generated: true,
+            ..bare()
+        }
+    }
+
+    #[test]
+    fn skips_generated_file_names() {
+        let f = filter_from(generated());
+        for name in [
+            "api/service_pb2.py",
+            "api/service.pb.go",
+            "lib/model.g.dart",
+        ] {
+            assert_eq!(
+                f.skip_reason(Path::new(name)),
+                Some(SkipReason::Generated),
+                "{name}"
+            );
+        }
+        assert_eq!(f.skip_reason(Path::new("api/service.py")), None);
+    }
+
+    #[test]
+    fn skips_generated_marker_ignoring_case() {
+        let f = filter_from(generated());
+        let file = temp_file(b"// Code generated by protoc-gen-go. Do Not Edit.\npackage api\n");
+        assert_eq!(f.skip_reason(file.path()), Some(SkipReason::Generated));
+        let file = temp_file(b"# @Generated by sqlc\n");
+        assert_eq!(f.skip_reason(file.path()), Some(SkipReason::Generated));
+    }
+
+    #[test]
+    fn generated_marker_past_the_sniffed_head_is_kept() {
+        let f = filter_from(generated());
+        let mut content = vec![b'x'; 4096];
+        content.extend_from_slice(b"\n// @generated\n");
+        assert_eq!(f.skip_reason(temp_file(&content).path()), None);
+    }
+
+    #[test]
+    fn generated_markers_are_configurable() {
+        let f = filter_from(AppConfig {
+            generated_markers: vec!["Built By Tool".into()],
+            ..generated()
+        });
+        let file = temp_file(b"/* built by tool v2 */\n");
+        assert_eq!(f.skip_reason(file.path()), Some(SkipReason::Generated));
+        assert_eq!(f.skip_reason(temp_file(b"// @generated\n").path()), None);
+    }
+
+    #[test]
+    fn generated_files_are_kept_by_default() {
+        let f = filter_from(bare());
+        assert_eq!(f.skip_reason(temp_file(b"// @generated\n").path()), None);
+        assert_eq!(f.skip_reason(Path::new("service_pb2.py")), None);
+    }
+
+    #[test]
+    fn null_bytes_are_binary() {
+        assert!(is_binary(temp_file(b"ELF\x00\x01\x02").path()));
+    }
+
+    #[test]
+    fn utf16le_with_bom_is_not_binary() {
+        assert!(!is_binary(temp_file(b"\xFF\xFEh\x00i\x00").path()));
+    }
+
+    #[test]
+    fn utf16be_with_bom_is_not_binary() {
+        assert!(!is_binary(temp_file(b"\xFE\xFF\x00h\x00i").path()));
+    }
+
+    #[test]
+    fn bomless_utf16le_is_not_binary() {
+        assert!(!is_binary(temp_file(b"h\x00e\x00l\x00l\x00o\x00").path()));
+    }
+
+    #[test]
+    fn latin1_is_not_binary() {
+        assert!(!is_binary(temp_file(b"caf\xE9 cr\xE8me").path()));
+    }
+
+    #[test]
+    fn utf8_with_bom_is_not_binary() {
+        assert!(!is_binary(temp_file(b"\xEF\xBB\xBFhi").path()));
+    }
+
+    #[test]
+    fn shebang_scripts_are_text() {
+        let bash = temp_file(b"#!/usr/bin/env bash\ntail -n +3 \"$0\" | tar x\nexit\n");
+        assert!(!is_binary(bash.path()));
+        // A self-extracting installer: its payload still fails the null byte check.
+        let installer =
+            temp_file(b"#!/usr/bin/env bash\ntail -n +3 \"$0\" | tar x\nexit\n\x00\x01payload");
+        assert!(is_binary(installer.path()));
+        let python = temp_file(b"#!/usr/bin/python3\r\nprint('hi')\r\n");
+        assert!(!is_binary(python.path()));
+        assert!(is_binary(temp_file(b"#!\x00\x01\x02").path()));
+    }
+
+    fn temp_file_with_ext(ext: &str, bytes: &[u8]) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{ext}"))
+            .tempfile()
+            .unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    fn binary_config(text: &[&str], binary: &[&str]) -> AppConfig {
+        AppConfig {
+            skip_binary: true,
+            binary: BinaryConfig {
+                always_text_extensions: text.iter().map(|e| e.to_string()).collect(),
+                always_binary_extensions: binary.iter().map(|e| e.to_string()).collect(),
+                ..BinaryConfig::default()
+            },
+            ..bare()
+        }
+    }
+
+    #[test]
+    fn always_text_extension_wins_over_the_sniffer() {
+        let svg = temp_file_with_ext("svg", b"<svg>\x00odd prefix</svg>");
+        assert_eq!(
+            filter_from(binary_config(&[], &[])).skip_reason(svg.path()),
+            Some(SkipReason::Binary)
+        );
+        assert_eq!(
+            filter_from(binary_config(&["SVG"], &[])).skip_reason(svg.path()),
+            None
+        );
+    }
+
+    #[test]
+    fn always_binary_extension_wins_over_the_sniffer() {
+        let dat = temp_file_with_ext("dat", b"plain text after all\n");
+        let f = filter_from(binary_config(&[], &["dat"]));
+        assert_eq!(
+            skipped_by(&f, dat.path().to_str().unwrap()),
+            "[binary] always_binary_extensions: dat"
+        );
+        // Decided by name alone: the file need not even exist.
+        assert!(f.should_skip(Path::new("missing.dat")));
+    }
+
+    #[test]
+    fn sniff_bytes_bounds_the_sniffed_head() {
+        let mut content = vec![b'x'; 64];
+        content.push(0);
+        let file = temp_file(&content);
+        let mut cfg = binary_config(&[], &[]);
+        assert_eq!(
+            filter_from(cfg.clone()).skip_reason(file.path()),
+            Some(SkipReason::Binary)
+        );
+        cfg.binary.sniff_bytes = 32;
+        assert_eq!(filter_from(cfg).skip_reason(file.path()), None);
+    }
+
+    #[test]
+    fn mime_prefixes_decide_for_recognized_types() {
+        let png = temp_file_with_ext("bin", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let pdf = temp_file_with_ext("bin", b"%PDF-1.7\n% plain enough\n");
+        let unknown = temp_file_with_ext("bin", b"no magic\0here");
+        let png_path = png.path().to_str().unwrap();
+        assert_eq!(
+            filter_from(binary_config(&[], &[])).skip_reason(pdf.path()),
+            Some(SkipReason::Binary)
+        );
+
+        let mut cfg = binary_config(&[], &[]);
+        cfg.skip_mime_prefixes = vec!["Image/".into()];
+        let f = filter_from(cfg.clone());
+        assert_eq!(f.skip_reason(png.path()), Some(SkipReason::MimeType));
+        assert_eq!(
+            skipped_by(&f, png_path),
+            "skip_mime_prefixes: image/ (image/png)"
+        );
+        // A recognized type no list names is kept...
+        assert_eq!(f.skip_reason(pdf.path()), None);
+        // ...and an unrecognized one still goes through the null byte check.
+        assert_eq!(f.skip_reason(unknown.path()), Some(SkipReason::Binary));
+
+        cfg.skip_mime_prefixes = vec![];
+        cfg.include_mime_prefixes = vec!["application/pdf".into()];
+        let f = filter_from(cfg);
+        assert_eq!(f.skip_reason(pdf.path()), None);
+        assert_eq!(
+            skipped_by(&f, png_path),
+            "include_mime_prefixes: no entry matches image/png"
+        );
+    }
+
+    #[test]
+    fn mime_prefixes_apply_without_skip_binary_and_keep_text() {
+        let png = temp_file_with_ext("bin", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let pdf = temp_file_with_ext("bin", b"%PDF-1.7\n% plain enough\n");
+        let html = temp_file_with_ext("html", b"<!DOCTYPE html>\n<html></html>\n");
+
+        let f = filter_from(AppConfig {
+            skip_mime_prefixes: vec!["image/".into()],
+            ..bare()
+        });
+        assert_eq!(f.skip_reason(png.path()), Some(SkipReason::MimeType));
+        // Other binaries are kept, as skip_binary is off.
+        assert_eq!(f.skip_reason(pdf.path()), None);
+
+        let f = filter_from(AppConfig {
+            include_mime_prefixes: vec!["application/pdf".into()],
+            ..bare()
+        });
+        assert_eq!(f.skip_reason(pdf.path()), None);
+        assert_eq!(f.skip_reason(html.path()), None);
+        assert_eq!(f.skip_reason(png.path()), Some(SkipReason::MimeType));
+    }
+
+    #[test]
+    fn long_text_preamble_hides_binary_from_the_default_sample() {
+        let mut content = vec![b'x'; 10_000];
+        content.push(0);
+        content.extend_from_slice(b"tail");
+        let file = temp_file(&content);
+        let mut cfg = binary_config(&[], &[]);
+        assert_eq!(filter_from(cfg.clone()).skip_reason(file.path()), None);
+        cfg.binary.sniff_bytes = 16 * 1024;
+        assert_eq!(
+            filter_from(cfg).skip_reason(file.path()),
+            Some(SkipReason::Binary)
+        );
+    }
+
     #[test]
     fn default_config_skips_lock_files() {
         assert!(filter_from(AppConfig::default()).should_skip(Path::new("Cargo.lock")));
@@ -0,0 +1,379 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::errors::{DumpError, DumpResult};
+
+/// Field separator for `git log --format`; cannot appear in a commit subject line.
+const FIELD_SEP: char = '\x1f';
+
+/// The last commit that touched a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub short_hash: String,
+    /// Author date, `YYYY-MM-DD`.
+    pub date: String,
+    pub subject: String,
+}
+
+/// What git knows about a file inside a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileMeta {
+    Commit(CommitInfo),
+    /// Inside a repository, but never committed.
+    Untracked,
+}
+
+impl fmt::Display for FileMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Commit(c) => write!(f, "{} {} {}", c.short_hash, c.date, c.subject),
+            Self::Untracked => f.write_str("(untracked)"),
+        }
+    }
+}
+
+/// Per-run cache of last-commit lookups. Files outside any git repository (or
+/// when git is not installed) resolve to `None`, so callers can no-op silently.
+#[derive(Debug, Default)]
+pub struct GitMeta {
+    cache: HashMap<PathBuf, Option<FileMeta>>,
+}
+
+impl GitMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lookup(&mut self, path: &Path) -> Option<&FileMeta> {
+        self.cache
+            .entry(path.to_path_buf())
+            .or_insert_with(|| last_commit(path))
+            .as_ref()
+    }
+}
+
+fn last_commit(path: &Path) -> Option<FileMeta> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let name = path.file_name()?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "log",
+            "-1",
+            "--date=short",
+            "--format=%h%x1f%ad%x1f%s",
+            "--",
+        ])
+        .arg(name)
+        .output()
+        .ok()?;
+    // Non-zero exit: not a repository (or a broken one). Stay quiet.
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.trim_end();
+    if line.is_empty() {
+        return Some(FileMeta::Untracked);
+    }
+    parse_log_line(line).map(FileMeta::Commit)
+}
+
+/// Keep only the `files` (walked from `root`) that differ between `rev` and the
+/// working tree, per `git diff --name-only`. Errors if `root` is not inside a
+/// repository or the diff fails.
+pub fn retain_changed(root: &Path, rev: &str, files: &mut Vec<PathBuf>) -> DumpResult<()> {
+    let toplevel = toplevel(root)?;
+    retain_diff(&toplevel, rev, &[rev], files)
+}
+
+/// Keep only the `files` (walked from `root`) that differ between the commits
+/// `from` and `to`, per `git diff --name-only from to`. Both refs must name a
+/// commit; the working tree is not looked at.
+pub fn retain_changed_between(
+    root: &Path,
+    from: &str,
+    to: &str,
+    files: &mut Vec<PathBuf>,
+) -> DumpResult<()> {
+    let toplevel = toplevel(root)?;
+    for rev in [from, to] {
+        let commit = format!("{rev}^{{commit}}");
+        git_z(&toplevel, &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            "--end-of-options",
+            &commit,
+        ])
+        .map_err(|_| DumpError::GitDiff {
+            rev: rev.to_string(),
+            message: "no such commit in this repository".to_string(),
+        })?;
+    }
+    retain_diff(&toplevel, &format!("{from}..{to}"), &[from, to], files)
+}
+
+/// `git diff --name-only` over `revs`, with failures reported against `shown`.
+fn retain_diff(
+    toplevel: &Path,
+    shown: &str,
+    revs: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> DumpResult<()> {
+    let mut args = vec!["diff", "--name-only", "-z", "--end-of-options"];
+    args.extend_from_slice(revs);
+    args.push("--");
+    let listed = git_z(toplevel, &args).map_err(|message| DumpError::GitDiff {
+        rev: shown.to_string(),
+        message,
+    })?;
+    retain_listed(toplevel, &listed, files);
+    Ok(())
+}
+
+/// Keep only the `files` (walked from `root`) that git tracks. Errors if `root`
+/// is not inside a repository.
+pub fn retain_tracked(root: &Path, files: &mut Vec<PathBuf>) -> DumpResult<()> {
+    retain_ls_files(root, &["ls-files", "-z"], files)
+}
+
+/// Keep only the `files` (walked from `root`) that git neither tracks nor
+/// ignores: new work not yet added. Errors if `root` is not inside a repository.
+pub fn retain_untracked(root: &Path, files: &mut Vec<PathBuf>) -> DumpResult<()> {
+    retain_ls_files(
+        root,
+        &["ls-files", "-z", "--others", "--exclude-standard"],
+        files,
+    )
+}
+
+fn retain_ls_files(root: &Path, args: &[&str], files: &mut Vec<PathBuf>) -> DumpResult<()> {
+    let toplevel = toplevel(root)?;
+    let listed = git_z(&toplevel, args).map_err(|message| DumpError::GitLsFiles {
+        message,
+    })?;
+    retain_listed(&toplevel, &listed, files);
+    Ok(())
+}
+
+/// Keep the `files` whose canonical path is one of `listed`, which are
+/// relative to `toplevel`.
+fn retain_listed(toplevel: &Path, listed: &[String], files: &mut Vec<PathBuf>) {
+    let listed: HashSet<PathBuf> = listed.iter().map(|name| toplevel.join(name)).collect();
+    files.retain(|file| file.canonicalize().is_ok_and(|p| listed.contains(&p)));
+}
+
+/// The canonical top-level directory of the repository containing `root`.
+fn toplevel(root: &Path) -> DumpResult<PathBuf> {
+    let dir = if root.is_dir() {
+        root
+    } else {
+        match root.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p,
+            _ => Path::new("."),
+        }
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let toplevel = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim_end());
+            Ok(toplevel.canonicalize().unwrap_or(toplevel))
+        },
+        _ => Err(DumpError::NotAGitRepository {
+            path: root.display().to_string(),
+        }),
+    }
+}
+
+/// Run a git command printing NUL-separated paths (`-z`) in `dir`. The error is
+/// git's stderr, or why it could not be run.
+fn git_z(dir: &Path, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn parse_log_line(line: &str) -> Option<CommitInfo> {
+    let mut fields = line.splitn(3, FIELD_SEP);
+    Some(CommitInfo {
+        short_hash: fields.next()?.to_string(),
+        date: fields.next()?.to_string(),
+        subject: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .env("GIT_AUTHOR_DATE", "2024-03-05T12:00:00Z")
+            .env("GIT_COMMITTER_DATE", "2024-03-05T12:00:00Z")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn parses_log_line() {
+        assert_eq!(
+            parse_log_line("abc1234\x1f2024-03-05\x1fFix: a, b | c"),
+            Some(CommitInfo {
+                short_hash: "abc1234".into(),
+                date: "2024-03-05".into(),
+                subject: "Fix: a, b | c".into(),
+            })
+        );
+        assert_eq!(parse_log_line("abc1234"), None);
+    }
+
+    #[test]
+    fn reports_last_commit_and_untracked_files() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        fs::write(dir.path().join("tracked.txt"), "a").unwrap();
+        git(dir.path(), &["add", "tracked.txt"]);
+        git(dir.path(), &["commit", "-q", "-m", "Add tracked file"]);
+        fs::write(dir.path().join("new.txt"), "b").unwrap();
+
+        let mut meta = GitMeta::new();
+        match meta.lookup(&dir.path().join("tracked.txt")) {
+            Some(FileMeta::Commit(c)) => {
+                assert_eq!(c.date, "2024-03-05");
+                assert_eq!(c.subject, "Add tracked file");
+            },
+            other => panic!("expected a commit, got {other:?}"),
+        }
+        assert_eq!(
+            meta.lookup(&dir.path().join("new.txt")),
+            Some(&FileMeta::Untracked)
+        );
+    }
+
+    #[test]
+    fn retains_only_files_changed_since_rev() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        fs::create_dir(dir.path().join("src")).unwrap();
+        for name in ["src/a.txt", "src/b.txt", "c.txt"] {
+            fs::write(dir.path().join(name), "old").unwrap();
+        }
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+        fs::write(dir.path().join("src/b.txt"), "new").unwrap();
+        fs::write(dir.path().join("c.txt"), "new").unwrap();
+
+        let root = dir.path().join("src");
+        let mut files = vec![root.join("a.txt"), root.join("b.txt")];
+        retain_changed(&root, "HEAD", &mut files).unwrap();
+        assert_eq!(files, vec![root.join("b.txt")]);
+
+        let err = retain_changed(&root, "no-such-ref", &mut files).unwrap_err();
+        assert!(matches!(err, DumpError::GitDiff { .. }));
+    }
+
+    #[test]
+    fn retains_only_files_changed_between_two_refs() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        for name in ["a.txt", "b.txt"] {
+            fs::write(dir.path().join(name), "old").unwrap();
+        }
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+        git(dir.path(), &["tag", "v1"]);
+        fs::write(dir.path().join("b.txt"), "new").unwrap();
+        git(dir.path(), &["commit", "-q", "-am", "Edit b"]);
+        // Uncommitted edits are outside the range.
+        fs::write(dir.path().join("a.txt"), "dirty").unwrap();
+
+        let all = vec![dir.path().join("a.txt"), dir.path().join("b.txt")];
+        let mut files = all.clone();
+        retain_changed_between(dir.path(), "v1", "HEAD", &mut files).unwrap();
+        assert_eq!(files, vec![dir.path().join("b.txt")]);
+
+        let mut files = all;
+        match retain_changed_between(dir.path(), "v1", "v2", &mut files).unwrap_err() {
+            DumpError::GitDiff {
+                rev, ..
+            } => assert_eq!(rev, "v2"),
+            e => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn retains_tracked_or_untracked_files() {
+        let dir = TempDir::new().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        fs::write(dir.path().join("tracked.txt"), "a").unwrap();
+        git(dir.path(), &["add", "."]);
+        git(dir.path(), &["commit", "-q", "-m", "Initial"]);
+        fs::write(dir.path().join("notes.txt"), "b").unwrap();
+        fs::write(dir.path().join("debug.log"), "c").unwrap();
+
+        let all: Vec<PathBuf> = ["tracked.txt", "notes.txt", "debug.log"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        let mut tracked = all.clone();
+        retain_tracked(dir.path(), &mut tracked).unwrap();
+        assert_eq!(tracked, vec![dir.path().join("tracked.txt")]);
+
+        let mut untracked = all;
+        retain_untracked(dir.path(), &mut untracked).unwrap();
+        assert_eq!(untracked, vec![dir.path().join("notes.txt")]);
+    }
+
+    #[test]
+    fn changed_outside_a_repository_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let err = retain_changed(dir.path(), "HEAD", &mut vec![]).unwrap_err();
+        assert!(matches!(err, DumpError::NotAGitRepository { .. }));
+        let err = retain_tracked(dir.path(), &mut vec![]).unwrap_err();
+        assert!(matches!(err, DumpError::NotAGitRepository { .. }));
+    }
+
+    #[test]
+    fn outside_a_repository_is_none() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+
+        assert_eq!(GitMeta::new().lookup(&dir.path().join("a.txt")), None);
+    }
+}
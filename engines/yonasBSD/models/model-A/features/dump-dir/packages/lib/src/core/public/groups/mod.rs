@@ -0,0 +1,153 @@
+use crate::errors::{DumpResult, UnknownGroupSnafu};
+
+/// A named set of skip rules, as `skip_groups` and `--skip-groups` name it.
+/// Each list is added to the skip list of the same name when the filter is
+/// built, on top of whatever the config says.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub skip_extensions: &'static [&'static str],
+    pub skip_filenames: &'static [&'static str],
+    pub skip_path_components: &'static [&'static str],
+    pub skip_globs: &'static [&'static str],
+}
+
+/// The built-in groups, sorted by name.
+pub const GROUPS: &[Group] = &[
+    Group {
+        name: "ci-config",
+        description: "CI pipeline definitions",
+        skip_extensions: &[],
+        skip_filenames: &[
+            ".gitlab-ci.yml",
+            ".travis.yml",
+            ".drone.yml",
+            "appveyor.yml",
+            "azure-pipelines.yml",
+            "bitbucket-pipelines.yml",
+            "Jenkinsfile",
+        ],
+        skip_path_components: &[".circleci", ".buildkite"],
+        skip_globs: &["**/.github/workflows/**"],
+    },
+    Group {
+        name: "editor-junk",
+        description: "Editor settings, swap and backup files, and OS folder metadata",
+        skip_extensions: &["swp", "swo", "bak", "orig"],
+        skip_filenames: &[".DS_Store", "Thumbs.db", "desktop.ini"],
+        skip_path_components: &[".idea", ".vscode"],
+        skip_globs: &["**/*~", "**/.#*", "**/#*#"],
+    },
+    Group {
+        name: "fonts",
+        description: "Font files",
+        skip_extensions: &["ttf", "otf", "woff", "woff2", "eot"],
+        skip_filenames: &[],
+        skip_path_components: &[],
+        skip_globs: &[],
+    },
+    Group {
+        name: "images",
+        description: "Raster and vector images",
+        skip_extensions: &[
+            "png", "jpg", "jpeg", "gif", "bmp", "ico", "webp", "tif", "tiff", "psd", "heic",
+            "avif", "svg",
+        ],
+        skip_filenames: &[],
+        skip_path_components: &[],
+        skip_globs: &[],
+    },
+    Group {
+        name: "lockfiles",
+        description: "Dependency lockfiles of common package managers",
+        skip_extensions: &[],
+        skip_filenames: &[
+            "Cargo.lock",
+            "package-lock.json",
+            "npm-shrinkwrap.json",
+            "yarn.lock",
+            "pnpm-lock.yaml",
+            "bun.lockb",
+            "Gemfile.lock",
+            "poetry.lock",
+            "Pipfile.lock",
+            "uv.lock",
+            "composer.lock",
+            "go.sum",
+            "flake.lock",
+            "mix.lock",
+            "pubspec.lock",
+        ],
+        skip_path_components: &[],
+        skip_globs: &[],
+    },
+];
+
+impl Group {
+    /// The skip lists this group adds to, with their entries; lists it leaves
+    /// empty are left out.
+    pub fn entries(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        [
+            ("skip_extensions", self.skip_extensions),
+            ("skip_filenames", self.skip_filenames),
+            ("skip_path_components", self.skip_path_components),
+            ("skip_globs", self.skip_globs),
+        ]
+        .into_iter()
+        .filter(|(_, values)| !values.is_empty())
+        .collect()
+    }
+}
+
+/// The group called `name` (case-insensitive), or an error listing the
+/// available names.
+pub fn by_name(name: &str) -> DumpResult<&'static Group> {
+    let lower = name.trim().to_lowercase();
+    GROUPS
+        .iter()
+        .find(|group| group.name == lower)
+        .ok_or_else(|| {
+            UnknownGroupSnafu {
+                name: name.to_string(),
+                available: GROUPS
+                    .iter()
+                    .map(|group| group.name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+            .build()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_are_sorted_and_none_is_empty() {
+        let names: Vec<_> = GROUPS.iter().map(|group| group.name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+        assert!(GROUPS.iter().all(|group| !group.entries().is_empty()));
+    }
+
+    #[test]
+    fn by_name_ignores_case_and_lists_names_when_unknown() {
+        assert_eq!(by_name("LockFiles").unwrap().name, "lockfiles");
+        match by_name("videos").unwrap_err() {
+            crate::errors::DumpError::UnknownGroup {
+                name,
+                available,
+            } => {
+                assert_eq!(name, "videos");
+                assert_eq!(
+                    available,
+                    "ci-config, editor-junk, fonts, images, lockfiles"
+                );
+            },
+            e => panic!("{e}"),
+        }
+    }
+}
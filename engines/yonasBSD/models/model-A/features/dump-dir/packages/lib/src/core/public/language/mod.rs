@@ -0,0 +1,377 @@
+use std::path::Path;
+
+use crate::errors::{DumpResult, UnknownLanguageSnafu};
+
+/// Extension (lowercase, no dot) → language name as used for Markdown code fence
+/// info strings (the names GitHub's highlighter recognises).
+const BY_EXTENSION: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("c", "c"),
+    ("cc", "cpp"),
+    ("cjs", "javascript"),
+    ("cmake", "cmake"),
+    ("cpp", "cpp"),
+    ("cs", "csharp"),
+    ("css", "css"),
+    ("cts", "typescript"),
+    ("cxx", "cpp"),
+    ("dart", "dart"),
+    ("dockerfile", "dockerfile"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("go", "go"),
+    ("h", "c"),
+    ("hh", "cpp"),
+    ("hpp", "cpp"),
+    ("hs", "haskell"),
+    ("htm", "html"),
+    ("html", "html"),
+    ("hxx", "cpp"),
+    ("java", "java"),
+    ("js", "javascript"),
+    ("json", "json"),
+    ("jsx", "jsx"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("lua", "lua"),
+    ("markdown", "markdown"),
+    ("md", "markdown"),
+    ("mjs", "javascript"),
+    ("mk", "makefile"),
+    ("mts", "typescript"),
+    ("nix", "nix"),
+    ("php", "php"),
+    ("pl", "perl"),
+    ("pm", "perl"),
+    ("proto", "protobuf"),
+    ("ps1", "powershell"),
+    ("py", "python"),
+    ("pyi", "python"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("scala", "scala"),
+    ("scss", "scss"),
+    ("sh", "bash"),
+    ("sql", "sql"),
+    ("svelte", "svelte"),
+    ("swift", "swift"),
+    ("toml", "toml"),
+    ("ts", "typescript"),
+    ("tsx", "tsx"),
+    ("vue", "vue"),
+    ("xml", "xml"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("zig", "zig"),
+    ("zsh", "zsh"),
+];
+
+/// Files recognised by name rather than extension (compared lowercase).
+const BY_FILENAME: &[(&str, &str)] = &[
+    ("cmakelists.txt", "cmake"),
+    ("containerfile", "dockerfile"),
+    ("dockerfile", "dockerfile"),
+    ("gemfile", "ruby"),
+    ("gnumakefile", "makefile"),
+    ("justfile", "just"),
+    ("makefile", "makefile"),
+    ("rakefile", "ruby"),
+];
+
+/// Shebang interpreter (version suffix stripped) → language name, for scripts
+/// without an extension.
+const BY_INTERPRETER: &[(&str, &str)] = &[
+    ("ash", "bash"),
+    ("bash", "bash"),
+    ("bun", "javascript"),
+    ("dash", "bash"),
+    ("deno", "javascript"),
+    ("elixir", "elixir"),
+    ("ksh", "bash"),
+    ("lua", "lua"),
+    ("make", "makefile"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("pwsh", "powershell"),
+    ("python", "python"),
+    ("ruby", "ruby"),
+    ("sh", "bash"),
+    ("zsh", "zsh"),
+];
+
+/// A language as `--lang`, `include_languages` and `skip_languages` name it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Language {
+    pub name: &'static str,
+    /// Extensions (lowercase, no dot).
+    pub extensions: &'static [&'static str],
+    /// Exact file names (lowercase).
+    pub filenames: &'static [&'static str],
+}
+
+macro_rules! languages {
+    ($($name:literal => [$($ext:literal),*] [$($file:literal),*]),* $(,)?) => {
+        &[$(Language { name: $name, extensions: &[$($ext),*], filenames: &[$($file),*] }),*]
+    };
+}
+
+/// The language vocabulary, sorted by name. Every extension and file name here
+/// is also in [`BY_EXTENSION`] or [`BY_FILENAME`], so a selected file gets a
+/// code fence.
+pub const LANGUAGES: &[Language] = languages![
+    "bash" => ["sh", "bash"] [],
+    "c" => ["c", "h"] [],
+    "cmake" => ["cmake"] ["cmakelists.txt"],
+    "cpp" => ["cc", "cpp", "cxx", "hh", "hpp", "hxx"] [],
+    "csharp" => ["cs"] [],
+    "css" => ["css"] [],
+    "dart" => ["dart"] [],
+    "docker" => ["dockerfile"] ["dockerfile", "containerfile"],
+    "elixir" => ["ex", "exs"] [],
+    "go" => ["go"] [],
+    "haskell" => ["hs"] [],
+    "html" => ["html", "htm"] [],
+    "java" => ["java"] [],
+    "javascript" => ["js", "mjs", "cjs", "jsx"] [],
+    "json" => ["json"] [],
+    "just" => [] ["justfile"],
+    "kotlin" => ["kt", "kts"] [],
+    "lua" => ["lua"] [],
+    "make" => ["mk"] ["makefile", "gnumakefile"],
+    "markdown" => ["md", "markdown"] [],
+    "nix" => ["nix"] [],
+    "perl" => ["pl", "pm"] [],
+    "php" => ["php"] [],
+    "powershell" => ["ps1"] [],
+    "protobuf" => ["proto"] [],
+    "python" => ["py", "pyi"] [],
+    "ruby" => ["rb"] ["gemfile", "rakefile"],
+    "rust" => ["rs"] [],
+    "scala" => ["scala"] [],
+    "scss" => ["scss"] [],
+    "sql" => ["sql"] [],
+    "svelte" => ["svelte"] [],
+    "swift" => ["swift"] [],
+    "toml" => ["toml"] [],
+    "typescript" => ["ts", "tsx", "mts", "cts"] [],
+    "vue" => ["vue"] [],
+    "xml" => ["xml"] [],
+    "yaml" => ["yaml", "yml"] [],
+    "zig" => ["zig"] [],
+    "zsh" => ["zsh"] [],
+];
+
+impl Language {
+    /// Whether `path` has one of this language's file names or extensions,
+    /// ignoring case.
+    pub fn matches(&self, path: &Path) -> bool {
+        self.matches_filename(path)
+            || path
+                .extension()
+                .is_some_and(|ext| self.extensions.contains(&lower(ext).as_str()))
+    }
+
+    /// True if `path` is one of this language's file names, such as `Makefile`.
+    pub fn matches_filename(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.filenames.contains(&lower(name).as_str()))
+    }
+}
+
+fn lower(s: &std::ffi::OsStr) -> String {
+    s.to_string_lossy().to_lowercase()
+}
+
+/// The language called `name` (case-insensitive), or an error listing the
+/// supported names.
+pub fn by_name(name: &str) -> DumpResult<&'static Language> {
+    let lower = name.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|lang| lang.name == lower)
+        .ok_or_else(|| {
+            UnknownLanguageSnafu {
+                name: name.to_string(),
+                supported: LANGUAGES
+                    .iter()
+                    .map(|lang| lang.name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+            .build()
+        })
+}
+
+/// The language of `path`, guessed from its file name or extension. `None` if
+/// unknown.
+pub fn from_path(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if let Some((_, lang)) = BY_FILENAME.iter().find(|(n, _)| *n == name) {
+        return Some(lang);
+    }
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    BY_EXTENSION
+        .binary_search_by(|(e, _)| (*e).cmp(ext.as_str()))
+        .ok()
+        .map(|i| BY_EXTENSION[i].1)
+}
+
+/// The language named by a `#!` line at the start of `head`, such as `bash` for
+/// `#!/bin/sh` or `python` for `#!/usr/bin/env -S python3 -u`. `None` without a
+/// shebang or for an unknown interpreter.
+pub fn from_shebang(head: &[u8]) -> Option<&'static str> {
+    let rest = head.strip_prefix(b"#!")?;
+    let line = &rest[..rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len())];
+    let line = std::str::from_utf8(line).ok()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip env's flags (`-S`) and variable assignments.
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    BY_INTERPRETER
+        .binary_search_by(|(i, _)| (*i).cmp(name))
+        .ok()
+        .map(|i| BY_INTERPRETER[i].1)
+}
+
+/// The language of a file: by [`from_path`], else by a shebang in `head`, its
+/// first bytes.
+pub fn detect(path: &Path, head: &[u8]) -> Option<&'static str> {
+    from_path(path).or_else(|| from_shebang(head))
+}
+
+/// Opening and closing comment delimiters for `lang`, as returned by
+/// [`from_path`]. `None` where no comment syntax is safe to prepend: JSON has
+/// none, and a line before `<?php` would change what PHP prints.
+pub fn comment_syntax(lang: &str) -> Option<(&'static str, &'static str)> {
+    match lang {
+        "c" | "cpp" | "csharp" | "dart" | "go" | "java" | "javascript" | "jsx" | "kotlin"
+        | "protobuf" | "rust" | "scala" | "scss" | "swift" | "tsx" | "typescript" | "zig" => {
+            Some(("//", ""))
+        },
+        "bash" | "cmake" | "dockerfile" | "elixir" | "just" | "makefile" | "nix" | "perl"
+        | "powershell" | "python" | "ruby" | "toml" | "yaml" | "zsh" => Some(("#", "")),
+        "haskell" | "lua" | "sql" => Some(("--", "")),
+        "html" | "markdown" | "svelte" | "vue" | "xml" => Some(("<!--", " -->")),
+        "css" => Some(("/*", " */")),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_table_is_sorted() {
+        assert!(BY_EXTENSION.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(BY_INTERPRETER.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn detects_by_shebang_when_the_path_says_nothing() {
+        assert_eq!(from_shebang(b"#!/bin/bash\necho hi\n"), Some("bash"));
+        assert_eq!(from_shebang(b"#!/usr/bin/env python3\n"), Some("python"));
+        assert_eq!(
+            from_shebang(b"#!/usr/bin/env -S PYTHONUTF8=1 python3.12 -u\n"),
+            Some("python")
+        );
+        assert_eq!(from_shebang(b"#! /usr/bin/node"), Some("javascript"));
+        assert_eq!(from_shebang(b"#!/usr/bin/awk -f\n"), None);
+        assert_eq!(from_shebang(b"echo hi\n"), None);
+
+        assert_eq!(
+            detect(Path::new("bin/deploy"), b"#!/bin/sh\n"),
+            Some("bash")
+        );
+        // The extension wins over the shebang.
+        assert_eq!(detect(Path::new("run.rb"), b"#!/bin/sh\n"), Some("ruby"));
+    }
+
+    #[test]
+    fn detects_by_extension_case_insensitively() {
+        assert_eq!(from_path(Path::new("src/main.rs")), Some("rust"));
+        assert_eq!(from_path(Path::new("App.TSX")), Some("tsx"));
+    }
+
+    #[test]
+    fn detects_by_filename() {
+        assert_eq!(
+            from_path(Path::new("docker/Dockerfile")),
+            Some("dockerfile")
+        );
+    }
+
+    #[test]
+    fn every_known_language_is_classified_for_comments() {
+        let unsafe_langs = ["json", "php"];
+        for (_, lang) in BY_EXTENSION.iter().chain(BY_FILENAME).chain(BY_INTERPRETER) {
+            assert_eq!(
+                comment_syntax(lang).is_none(),
+                unsafe_langs.contains(lang),
+                "{lang}"
+            );
+        }
+    }
+
+    #[test]
+    fn language_table_is_sorted() {
+        assert!(LANGUAGES.windows(2).all(|w| w[0].name < w[1].name));
+    }
+
+    #[test]
+    fn every_language_entry_has_a_fence() {
+        for lang in LANGUAGES {
+            for ext in lang.extensions {
+                assert!(
+                    from_path(Path::new(&format!("x.{ext}"))).is_some(),
+                    "{}: {ext}",
+                    lang.name
+                );
+            }
+            for file in lang.filenames {
+                assert!(
+                    from_path(Path::new(file)).is_some(),
+                    "{}: {file}",
+                    lang.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn language_matches_names_and_extensions() {
+        let make = by_name("Make").unwrap();
+        assert!(make.matches(Path::new("Makefile")));
+        assert!(make.matches(Path::new("build/rules.mk")));
+        assert!(!make.matches(Path::new("Makefile.am")));
+        let docker = by_name("docker").unwrap();
+        assert!(docker.matches(Path::new("ci/Dockerfile")));
+        assert!(docker.matches(Path::new("app.dockerfile")));
+        assert_eq!(from_path(Path::new("rules.mk")), Some("makefile"));
+        assert_eq!(from_path(Path::new("index.mts")), Some("typescript"));
+    }
+
+    #[test]
+    fn unknown_language_lists_supported_names() {
+        let err = by_name("klingon").unwrap_err().to_string();
+        assert!(err.contains("klingon"), "{err}");
+        let supported = match by_name("klingon").unwrap_err() {
+            crate::errors::DumpError::UnknownLanguage {
+                supported, ..
+            } => supported,
+            e => panic!("{e}"),
+        };
+        assert!(supported.contains("rust, scala"), "{supported}");
+    }
+
+    #[test]
+    fn unknown_is_none() {
+        assert_eq!(from_path(Path::new("notes.xyz")), None);
+        assert_eq!(from_path(Path::new("LICENSE")), None);
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use crate::errors::{DumpResult, InvalidManifestSnafu, IoSnafu, PathNotFoundSnafu};
+
+/// Read size for streaming hashes; memory use stays flat regardless of file size.
+const HASH_CHUNK: usize = 64 * 1024;
+
+/// One manifest line: `<sha256>  <size>  <path>`, in the spirit of `sha256sum`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// Lowercase hex SHA-256 of the file content.
+    pub sha256: String,
+    pub size: u64,
+    /// Path relative to the walked root, `/`-separated; led by the root when
+    /// there are several (see [`rooted_path`]).
+    pub path: String,
+}
+
+impl fmt::Display for ManifestEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  {}  {}", self.sha256, self.size, self.path)
+    }
+}
+
+/// Files that differ between a saved manifest and the tree as it is now.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManifestDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ManifestDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Hash `files` (collected under `root`) into manifest entries, in input order.
+/// Up to `jobs` files are hashed concurrently (0 = one per CPU), as with
+/// `[printer] jobs`. With `rooted`, paths are led by `root` (see
+/// [`rooted_path`]), which a manifest of several roots needs.
+pub fn build(
+    root: &Path,
+    files: &[PathBuf],
+    jobs: usize,
+    rooted: bool,
+) -> DumpResult<Vec<ManifestEntry>> {
+    let entry = |path: &PathBuf| -> DumpResult<ManifestEntry> {
+        let (sha256, size) = hash_file(path).context(IoSnafu {
+            path: path.display().to_string(),
+        })?;
+        Ok(ManifestEntry {
+            sha256,
+            size,
+            path: if rooted {
+                rooted_path(root, path)
+            } else {
+                relative_path(root, path)
+            },
+        })
+    };
+
+    let jobs = match jobs {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let pool = (jobs > 1)
+        .then(|| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(jobs)
+                .build()
+                .ok()
+        })
+        .flatten();
+    match pool {
+        Some(pool) => pool.install(|| files.par_iter().map(entry).collect()),
+        None => files.iter().map(entry).collect(),
+    }
+}
+
+/// Streaming SHA-256 of a file, with its size in bytes.
+pub fn hash_file(path: &Path) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; HASH_CHUNK];
+    let mut size = 0u64;
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    let hex = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok((hex, size))
+}
+
+/// Read and parse a manifest written by `--format manifest`.
+pub fn read(path: &Path) -> DumpResult<Vec<ManifestEntry>> {
+    if !path.exists() {
+        return PathNotFoundSnafu {
+            path: path.display().to_string(),
+        }
+        .fail();
+    }
+    let text = std::fs::read_to_string(path).context(IoSnafu {
+        path: path.display().to_string(),
+    })?;
+    parse(&text).map_err(|(line, message)| {
+        InvalidManifestSnafu {
+            path: path.display().to_string(),
+            line,
+            message,
+        }
+        .build()
+    })
+}
+
+/// Parse manifest text. Blank lines are ignored. Errors carry the 1-based line
+/// number and a description.
+fn parse(text: &str) -> Result<Vec<ManifestEntry>, (usize, String)> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let malformed = || {
+            (
+                i + 1,
+                format!("expected '<sha256>  <size>  <path>', got '{line}'"),
+            )
+        };
+        let (sha256, rest) = line.split_once("  ").ok_or_else(malformed)?;
+        let (size, path) = rest.split_once("  ").ok_or_else(malformed)?;
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err((i + 1, format!("'{sha256}' is not a SHA-256 hex digest")));
+        }
+        let size = size
+            .parse()
+            .map_err(|_| (i + 1, format!("'{size}' is not a byte size")))?;
+        entries.push(ManifestEntry {
+            sha256: sha256.to_ascii_lowercase(),
+            size,
+            path: path.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Compare a saved manifest against freshly built entries. Each list is sorted.
+pub fn diff(expected: &[ManifestEntry], actual: &[ManifestEntry]) -> ManifestDiff {
+    let before: BTreeMap<&str, &ManifestEntry> =
+        expected.iter().map(|e| (e.path.as_str(), e)).collect();
+    let after: BTreeMap<&str, &ManifestEntry> =
+        actual.iter().map(|e| (e.path.as_str(), e)).collect();
+
+    let mut result = ManifestDiff::default();
+    for (path, old) in &before {
+        match after.get(path) {
+            None => result.removed.push(path.to_string()),
+            Some(new) if new.sha256 != old.sha256 || new.size != old.size => {
+                result.changed.push(path.to_string());
+            },
+            Some(_) => {},
+        }
+    }
+    result.added = after
+        .keys()
+        .filter(|path| !before.contains_key(*path))
+        .map(|path| path.to_string())
+        .collect();
+    result
+}
+
+/// `path` relative to `root` with `/` separators. A root that is itself a file
+/// yields its file name.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    let rel = match path.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => rel,
+        _ => path.file_name().map_or(path, Path::new),
+    };
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// `path` under `root` as `root` was given, `/`-separated, so `a/x.rs` and
+/// `b/x.rs` stay apart when both roots are hashed into one manifest. A root
+/// that is itself a file is just its own path.
+pub fn rooted_path(root: &Path, path: &Path) -> String {
+    let mut parts: Vec<_> = root
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|c| match c {
+            Component::RootDir => "".into(),
+            c => c.as_os_str().to_string_lossy(),
+        })
+        .collect();
+    if parts.is_empty() {
+        parts.push(".".into());
+    }
+    let label = parts.join("/");
+    match path.strip_prefix(root) {
+        Ok(rel) if !rel.as_os_str().is_empty() => {
+            format!("{label}/{}", relative_path(root, path))
+        },
+        _ => label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn entry(path: &str, sha: char, size: u64) -> ManifestEntry {
+        ManifestEntry {
+            sha256: sha.to_string().repeat(64),
+            size,
+            path: path.into(),
+        }
+    }
+
+    #[test]
+    fn hashes_known_content() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("abc.txt");
+        fs::write(&path, "abc").unwrap();
+        let (sha, size) = hash_file(&path).unwrap();
+        assert_eq!(
+            sha,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(size, 3);
+    }
+
+    #[test]
+    fn streaming_hash_matches_one_shot_hash_across_chunks() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("big.bin");
+        let data: Vec<u8> = (0..HASH_CHUNK * 3 + 17).map(|i| (i % 251) as u8).collect();
+        fs::write(&path, &data).unwrap();
+        let expected: String = Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        assert_eq!(hash_file(&path).unwrap(), (expected, data.len() as u64));
+    }
+
+    #[test]
+    fn build_uses_relative_paths_in_input_order() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+        let files = vec![dir.path().join("b.txt"), dir.path().join("sub/a.txt")];
+
+        let serial = build(dir.path(), &files, 1, false).unwrap();
+        let parallel = build(dir.path(), &files, 4, false).unwrap();
+        assert_eq!(serial, parallel);
+        let paths: Vec<&str> = serial.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["b.txt", "sub/a.txt"]);
+    }
+
+    #[test]
+    fn rooted_paths_lead_with_the_root_as_given() {
+        let file = Path::new("src/lib.rs");
+        assert_eq!(rooted_path(Path::new("src"), file), "src/lib.rs");
+        assert_eq!(
+            rooted_path(Path::new("./src/"), Path::new("./src/lib.rs")),
+            "src/lib.rs"
+        );
+        assert_eq!(rooted_path(Path::new("."), Path::new("./a.rs")), "./a.rs");
+        assert_eq!(
+            rooted_path(Path::new("/tmp/x"), Path::new("/tmp/x/a.rs")),
+            "/tmp/x/a.rs"
+        );
+        assert_eq!(rooted_path(file, file), "src/lib.rs");
+    }
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        let entries = vec![entry("a b.txt", 'a', 10), entry("src/main.rs", 'f', 0)];
+        let text: String = entries.iter().map(|e| format!("{e}\n")).collect();
+        assert_eq!(parse(&text).unwrap(), entries);
+    }
+
+    #[test]
+    fn parse_reports_line_of_malformed_entry() {
+        let text = format!("{}\n\nnot a manifest line\n", entry("a", 'a', 1));
+        let (line, _) = parse(&text).unwrap_err();
+        assert_eq!(line, 3);
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed() {
+        let before = vec![
+            entry("same", 'a', 1),
+            entry("edited", 'b', 1),
+            entry("gone", 'c', 1),
+        ];
+        let after = vec![
+            entry("same", 'a', 1),
+            entry("edited", 'd', 1),
+            entry("new", 'e', 1),
+        ];
+        assert_eq!(diff(&before, &after), ManifestDiff {
+            added: vec!["new".into()],
+            removed: vec!["gone".into()],
+            changed: vec!["edited".into()],
+        });
+        assert!(diff(&before, &before).is_empty());
+    }
+}
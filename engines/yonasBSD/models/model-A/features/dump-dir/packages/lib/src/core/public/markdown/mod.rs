@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+/// GitHub-style anchor slug: lowercase, every non-alphanumeric character
+/// replaced by `-`. `src/main.rs` becomes `src-main-rs`.
+pub fn slug(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Hands out unique slugs for one document. A repeated slug gets a `-1`, `-2`, …
+/// suffix, skipping any suffixed form that is already taken.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slug(text);
+        let mut candidate = base.clone();
+        let mut n = 0;
+        while !self.used.insert(candidate.clone()) {
+            n += 1;
+            candidate = format!("{base}-{n}");
+        }
+        candidate
+    }
+}
+
+/// A backtick fence long enough to wrap `content` without being closed early
+/// by a run of backticks inside it.
+pub fn fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slug_lowercases_and_dashes_punctuation() {
+        assert_eq!(slug("Src/Main.rs"), "src-main-rs");
+        assert_eq!(slug("a b_c"), "a-b-c");
+    }
+
+    #[test]
+    fn slugger_suffixes_repeats() {
+        let mut slugger = Slugger::new();
+        assert_eq!(slugger.slug("a.rs"), "a-rs");
+        assert_eq!(slugger.slug("a_rs"), "a-rs-1");
+        assert_eq!(slugger.slug("a-rs"), "a-rs-2");
+    }
+
+    #[test]
+    fn slugger_skips_suffixes_already_taken() {
+        let mut slugger = Slugger::new();
+        assert_eq!(slugger.slug("x-1"), "x-1");
+        assert_eq!(slugger.slug("x"), "x");
+        assert_eq!(slugger.slug("x"), "x-2");
+    }
+
+    #[test]
+    fn fence_outgrows_backticks_in_content() {
+        assert_eq!(fence("plain"), "```");
+        assert_eq!(fence("```rust\n```"), "````");
+        assert_eq!(fence("`````"), "``````");
+    }
+}
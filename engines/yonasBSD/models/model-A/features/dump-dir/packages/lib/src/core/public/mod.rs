@@ -1,6 +1,17 @@
+#[cfg(feature = "archives")]
+pub mod archive;
+pub mod cache;
 pub mod config;
 pub mod filter;
+pub mod git;
+pub mod groups;
+pub mod language;
+pub mod manifest;
+pub mod markdown;
+pub mod presets;
 pub mod printer;
+pub mod render;
+pub mod summary;
 pub mod walker;
 
 mod tests;
@@ -0,0 +1,155 @@
+use crate::{
+    config::ListOverrides,
+    errors::{DumpResult, UnknownPresetSnafu},
+};
+
+/// A named filter set, as `--preset` and `presets` name it. Each list is added
+/// to the config list of the same name.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub skip_extensions: &'static [&'static str],
+    pub skip_filenames: &'static [&'static str],
+    pub skip_path_components: &'static [&'static str],
+    pub skip_globs: &'static [&'static str],
+    /// With any entry set, only files these (or other include rules) match are
+    /// dumped.
+    pub include_extensions: &'static [&'static str],
+}
+
+const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "mdx", "rst", "txt", "adoc", "org"];
+
+/// The built-in presets, sorted by name.
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "code-only",
+        description: "Leave out prose: docs, licenses and changelogs",
+        skip_extensions: DOC_EXTENSIONS,
+        skip_filenames: &["LICENSE", "COPYING", "CHANGELOG", "AUTHORS"],
+        skip_path_components: &[],
+        skip_globs: &[],
+        include_extensions: &[],
+    },
+    Preset {
+        name: "docs-only",
+        description: "Only documentation and plain text files",
+        skip_extensions: &[],
+        skip_filenames: &[],
+        skip_path_components: &[],
+        skip_globs: &[],
+        include_extensions: DOC_EXTENSIONS,
+    },
+    Preset {
+        name: "node",
+        description: "Dependencies, build output, bundles and lockfiles of JavaScript projects",
+        skip_extensions: &[],
+        skip_filenames: &["package-lock.json", "yarn.lock", "pnpm-lock.yaml"],
+        skip_path_components: &["node_modules", "dist"],
+        skip_globs: &["**/*.min.js"],
+        include_extensions: &[],
+    },
+    Preset {
+        name: "python",
+        description: "Bytecode, caches and virtual environments of Python projects",
+        skip_extensions: &["pyc", "pyo"],
+        skip_filenames: &[],
+        skip_path_components: &[
+            "__pycache__",
+            ".venv",
+            "venv",
+            ".tox",
+            ".mypy_cache",
+            ".pytest_cache",
+        ],
+        skip_globs: &["**/*.egg-info/**"],
+        include_extensions: &[],
+    },
+    Preset {
+        name: "rust",
+        description: "Build output, lockfiles and rustfmt backups of Rust projects",
+        skip_extensions: &["lock"],
+        skip_filenames: &[],
+        skip_path_components: &["target"],
+        skip_globs: &["**/*.rs.bk"],
+        include_extensions: &[],
+    },
+];
+
+impl Preset {
+    /// The config keys this preset adds to, with their entries; lists it leaves
+    /// empty are left out.
+    pub fn entries(&self) -> Vec<(&'static str, &'static [&'static str])> {
+        [
+            ("skip_extensions", self.skip_extensions),
+            ("skip_filenames", self.skip_filenames),
+            ("skip_path_components", self.skip_path_components),
+            ("skip_globs", self.skip_globs),
+            ("include_extensions", self.include_extensions),
+        ]
+        .into_iter()
+        .filter(|(_, values)| !values.is_empty())
+        .collect()
+    }
+
+    /// The skip lists as a layer of `*_add` keys.
+    pub fn list_overrides(&self) -> ListOverrides {
+        let owned = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        ListOverrides {
+            skip_extensions_add: owned(self.skip_extensions),
+            skip_filenames_add: owned(self.skip_filenames),
+            skip_path_components_add: owned(self.skip_path_components),
+            skip_globs_add: owned(self.skip_globs),
+            ..ListOverrides::default()
+        }
+    }
+}
+
+/// The preset called `name` (case-insensitive), or an error listing the
+/// available names.
+pub fn by_name(name: &str) -> DumpResult<&'static Preset> {
+    let lower = name.trim().to_lowercase();
+    PRESETS
+        .iter()
+        .find(|preset| preset.name == lower)
+        .ok_or_else(|| {
+            UnknownPresetSnafu {
+                name: name.to_string(),
+                available: PRESETS
+                    .iter()
+                    .map(|preset| preset.name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+            .build()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_are_sorted_and_none_is_empty() {
+        let names: Vec<_> = PRESETS.iter().map(|preset| preset.name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+        assert!(PRESETS.iter().all(|preset| !preset.entries().is_empty()));
+    }
+
+    #[test]
+    fn by_name_ignores_case_and_lists_names_when_unknown() {
+        assert_eq!(by_name(" Node ").unwrap().name, "node");
+        match by_name("cobol").unwrap_err() {
+            crate::errors::DumpError::UnknownPreset {
+                name,
+                available,
+            } => {
+                assert_eq!(name, "cobol");
+                assert_eq!(available, "code-only, docs-only, node, python, rust");
+            },
+            e => panic!("{e}"),
+        }
+    }
+}
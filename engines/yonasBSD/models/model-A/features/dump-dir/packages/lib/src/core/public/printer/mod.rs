@@ -1,85 +1,827 @@
-use std::{fs, path::Path, process::Command};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashMap,
+    fs,
+    io::{self, BufWriter, Read, StdoutLock, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{Duration, Instant, SystemTime},
+};
 
-use colored::Colorize;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
 use snafu::ResultExt;
+use wait_timeout::ChildExt;
 
-use crate::errors::{DumpResult, IoSnafu};
+use crate::{
+    cache::{CacheKey, CachedFile, ContentCache},
+    config::PrinterConfig,
+    enums::{OutputFormat, SummaryFormat, Symlinks},
+    errors::{DumpResult, IoSnafu, TotalSizeExceededSnafu, UnknownThemeSnafu, WriteSnafu},
+    filter::{FilterStats, sniff_mime},
+    git::GitMeta,
+    language,
+    manifest::relative_path,
+    render::{self, Body, FileSection, FileStat, TocEntry},
+    summary::DumpSummary,
+    traits::Renderer,
+    utils::{encoding::decode, time, tokens},
+};
 
-const SEPARATOR: &str = "====================================================";
+/// Output buffer size. Large enough that dumping many small files costs few
+/// write syscalls; flushed at every file boundary regardless.
+const OUT_BUFFER: usize = 64 * 1024;
 
-pub struct Printer {
-    file_count: usize,
-    line_count: usize,
-    skipped_unreadable: usize,
+/// Files read ahead per worker thread when reading in parallel. Bounds memory use
+/// to roughly `jobs * READ_AHEAD` file bodies at a time.
+const READ_AHEAD: usize = 4;
+
+/// A callback run for each printed file; see [`Printer::set_on_file`].
+type FileHook = Box<dyn FnMut(&Path)>;
+
+/// The outcome of reading a file, produced before anything is printed.
+enum Loaded {
+    Unreadable,
+    /// Read from disk; with a cache, `key` is where to store the decoded content.
+    Read(std::io::Result<Vec<u8>>, Option<CacheKey>),
+    /// Unchanged since it was cached, so not read at all.
+    Cached(CachedFile),
+    /// A symlink listed by its target instead of read (`symlinks = "list"`).
+    Link(PathBuf),
+}
+
+/// Dumps files to `W` (buffered, locked stdout by default) through a
+/// [`Renderer`] for the output format. Every write is fallible, so a closed pipe
+/// surfaces as `DumpError::Write` instead of a panic.
+pub struct Printer<W: Write = BufWriter<StdoutLock<'static>>> {
+    out: W,
+    renderer: Box<dyn Renderer>,
+    jobs: usize,
+    dedupe_content: bool,
+    lines: LineOptions,
+    header_template: Option<String>,
+    path_comment: bool,
+    root_label: Option<String>,
+    /// Walked roots, for `root_label`; see [`Printer::set_roots`].
+    roots: Vec<PathBuf>,
+    /// Print symlinks as `-> target`; see [`Printer::set_symlinks`].
+    list_symlinks: bool,
+    /// Called with each file as it is printed; see [`Printer::set_on_file`].
+    on_file: Option<FileHook>,
+    /// Files printed from memory instead of disk; see [`Printer::add_in_memory`].
+    in_memory: HashMap<PathBuf, Vec<u8>>,
+    git_meta: Option<GitMeta>,
+    metadata: bool,
+    cache: Option<ContentCache>,
+    filter_cmd: Option<String>,
+    /// `filter_cmd_timeout`; `None` waits forever.
+    filter_timeout: Option<Duration>,
+    max_total_lines: Option<usize>,
+    max_total_bytes: Option<u64>,
+    abort_after_bytes: Option<u64>,
+    /// Files printed before the rest are passed over; see [`Printer::set_max_files`].
+    max_files: Option<usize>,
+    /// Lines and bytes of verbatim text (prompt prefix/suffix). They count toward
+    /// the output budget but not toward the per-file summary.
+    framing_lines: usize,
+    framing_bytes: u64,
+    seen: HashMap<[u8; 32], PathBuf>,
+    started: Instant,
+    summary: DumpSummary,
 }
 
 impl Printer {
-    pub fn new(_summary: bool) -> Self {
+    pub fn new(cfg: &PrinterConfig) -> Self {
+        // Lock once for the whole run instead of once per `println!`.
+        Self::with_bat_probe(
+            cfg,
+            BufWriter::with_capacity(OUT_BUFFER, io::stdout().lock()),
+            which_bat,
+        )
+    }
+}
+
+impl<W: Write> Printer<W> {
+    /// Render into `out`, highlighting with the bat `find_bat` names, if any.
+    /// Markdown must stay plain text, so bat's ANSI output is never used there,
+    /// nor with a filter command, whose output bat would not see; `find_bat` is
+    /// only called if bat could be used at all.
+    fn with_bat_probe(
+        cfg: &PrinterConfig,
+        out: W,
+        find_bat: impl FnOnce() -> Option<String>,
+    ) -> Self {
+        let bat = (cfg.use_bat && cfg.format == OutputFormat::Text && cfg.filter_cmd.is_none())
+            .then(find_bat)
+            .flatten();
+        Self::with_renderer(cfg, out, render::for_format(cfg, bat))
+    }
+
+    /// Render into `out`. bat writes to the real stdout, so it is never used here;
+    /// only [`Printer::new`] enables it.
+    pub fn with_writer(cfg: &PrinterConfig, out: W) -> Self {
+        Self::with_renderer(cfg, out, render::for_format(cfg, None))
+    }
+
+    /// Render into `out` with `renderer` instead of the one for `cfg.format`.
+    pub fn with_renderer(cfg: &PrinterConfig, out: W, renderer: Box<dyn Renderer>) -> Self {
+        let jobs = match cfg.jobs {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
         Self {
-            file_count: 0,
-            line_count: 0,
-            skipped_unreadable: 0,
+            out,
+            renderer,
+            jobs,
+            dedupe_content: cfg.dedupe_content,
+            lines: LineOptions::new(cfg),
+            header_template: cfg.header_template.clone(),
+            path_comment: cfg.path_comment,
+            root_label: cfg.root_label.clone(),
+            roots: Vec::new(),
+            list_symlinks: false,
+            on_file: None,
+            in_memory: HashMap::new(),
+            git_meta: cfg.git_meta.then(GitMeta::new),
+            metadata: cfg.metadata,
+            // A capped dump may read only part of a file, so it bypasses the cache.
+            cache: cfg
+                .cache_dir
+                .as_ref()
+                .filter(|_| cfg.abort_after_bytes.is_none())
+                .map(ContentCache::new),
+            filter_cmd: cfg.filter_cmd.clone(),
+            filter_timeout: (cfg.filter_cmd_timeout > 0)
+                .then(|| Duration::from_secs(cfg.filter_cmd_timeout)),
+            max_total_lines: cfg.max_total_lines,
+            max_total_bytes: cfg.max_total_bytes,
+            abort_after_bytes: cfg.abort_after_bytes,
+            max_files: None,
+            framing_lines: 0,
+            framing_bytes: 0,
+            seen: HashMap::new(),
+            started: Instant::now(),
+            summary: DumpSummary {
+                tokens: cfg.tokens.then_some(0),
+                ..DumpSummary::default()
+            },
+        }
+    }
+
+    /// The roots the printed files were walked from. With `root_label`, a path
+    /// under one of them is shown relative to the longest match, behind the label.
+    pub fn set_roots(&mut self, roots: &[PathBuf]) {
+        self.roots = roots.to_vec();
+    }
+
+    /// How the files were walked with respect to symlinks. With
+    /// [`Symlinks::List`], a symlink prints as a header and a `-> target` line.
+    pub fn set_symlinks(&mut self, symlinks: Symlinks) {
+        self.list_symlinks = symlinks == Symlinks::List;
+    }
+
+    /// Call `hook` with each file the printer handles, e.g. to drive a progress
+    /// display. It runs before the file is written.
+    pub fn set_on_file(&mut self, hook: impl FnMut(&Path) + 'static) {
+        self.on_file = Some(Box::new(hook));
+    }
+
+    /// Note the modification-time cutoffs the files were filtered by, for the summary.
+    pub fn set_modified_window(&mut self, since: Option<SystemTime>, before: Option<SystemTime>) {
+        self.summary.changed_since = since.map(time::format_utc);
+        self.summary.changed_before = before.map(time::format_utc);
+    }
+
+    /// Print `path` from `content` instead of reading it, e.g. for a file inside
+    /// an archive. `path` still has to be passed to `print_files`, in order.
+    pub fn add_in_memory(&mut self, path: PathBuf, content: Vec<u8>) {
+        self.in_memory.insert(path, content);
+    }
+
+    /// Stop once `max` files have been printed (`--limit`). A file that fails to
+    /// read doesn't count, and the files passed over aren't counted as omitted.
+    pub fn set_max_files(&mut self, max: usize) {
+        self.max_files = Some(max);
+    }
+
+    /// Note how many files were left out as duplicate paths, for the summary.
+    pub fn set_duplicate_paths(&mut self, count: usize) {
+        self.summary.duplicate_paths = count;
+    }
+
+    /// `path` as it appears in the output.
+    fn shown(&self, path: &Path) -> String {
+        let root = self
+            .roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count());
+        match (&self.root_label, root) {
+            (Some(label), Some(root)) => format!("{label}/{}", relative_path(root, path)),
+            _ => path.display().to_string(),
         }
     }
 
     pub fn print_file(&mut self, path: &Path) -> DumpResult<()> {
-        if !is_readable(path) {
-            eprintln!(
-                "Warning: cannot read '{}' (permission denied)",
-                path.display()
-            );
-            self.skipped_unreadable += 1;
+        if self.limit_reached() {
             return Ok(());
         }
+        // Checked before reading, so omitted files cost no I/O.
+        if self.budget_reached() {
+            self.summary.files_omitted += 1;
+            return Ok(());
+        }
+        if let Some(content) = self.in_memory.remove(path) {
+            return self.emit(path, Loaded::Read(Ok(content), None));
+        }
+        let loaded = load(
+            path,
+            self.abort_after_bytes,
+            self.list_symlinks,
+            self.cache.as_ref(),
+        );
+        self.emit(path, loaded)
+    }
+
+    /// True once the printed totals have hit `max_total_lines` or `max_total_bytes`.
+    pub fn budget_reached(&self) -> bool {
+        self.max_total_lines
+            .is_some_and(|max| self.summary.line_count + self.framing_lines >= max)
+            || self
+                .max_total_bytes
+                .is_some_and(|max| self.summary.byte_count + self.framing_bytes >= max)
+    }
+
+    /// True once `max_files` files have been printed.
+    fn limit_reached(&self) -> bool {
+        self.max_files
+            .is_some_and(|max| self.summary.file_count >= max)
+    }
+
+    /// Bytes left under `abort_after_bytes`, if set.
+    fn bytes_remaining(&self) -> Option<u64> {
+        self.abort_after_bytes
+            .map(|max| max.saturating_sub(self.summary.byte_count + self.framing_bytes))
+    }
+
+    /// Print `text` as-is, outside any file section (e.g. a prompt preamble); see
+    /// [`Renderer::verbatim`].
+    pub fn print_verbatim(&mut self, text: &str) -> DumpResult<()> {
+        self.renderer
+            .verbatim(&mut self.out, text)
+            .context(WriteSnafu)?;
+        self.framing_lines += text.lines().count();
+        self.framing_bytes += text.len() as u64;
+        self.add_tokens(text);
+        self.out.flush().context(WriteSnafu)
+    }
+
+    /// Print a table of contents for `paths`, which must be every file about to
+    /// be printed, if the format has one. Call before the first file.
+    pub fn print_toc(&mut self, paths: &[PathBuf]) -> DumpResult<()> {
+        let entries: Vec<TocEntry> = paths
+            .iter()
+            .map(|path| TocEntry {
+                path,
+                shown: self.shown(path),
+            })
+            .collect();
+        match self.renderer.toc(&entries) {
+            Some(toc) => self.print_verbatim(&toc),
+            None => Ok(()),
+        }
+    }
 
-        println!("{}", SEPARATOR.bold().blue());
-        println!("{}", format!(" FILE: {}", path.display()).bold().blue());
-        println!("{}", SEPARATOR.bold().blue());
+    /// Print the `── budget reached ──` notice if any files were omitted.
+    /// Call once, after the last file.
+    pub fn print_budget_notice(&mut self) -> DumpResult<()> {
+        if self.summary.files_omitted > 0 {
+            self.renderer
+                .budget_notice(&mut self.out, self.summary.files_omitted)
+                .context(WriteSnafu)?;
+        }
+        Ok(())
+    }
 
-        let lines = if bat_available() {
-            print_with_bat(path)
+    /// Print `paths` in order. With more than one job, file contents are read
+    /// concurrently and printed in input order, so output is identical to the
+    /// serial path. A renderer that reads files itself (bat) always runs serially.
+    pub fn print_files(&mut self, paths: &[PathBuf]) -> DumpResult<()> {
+        let pool = if self.jobs > 1 && !self.renderer.reads_files() {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.jobs)
+                .build()
+                .ok()
         } else {
-            print_with_cat(path).context(IoSnafu {
-                path: path.display().to_string(),
-            })?
+            None
         };
 
-        println!();
+        // Serial path: bat output, a single job, or no pool could be started.
+        let Some(pool) = pool else {
+            for path in paths {
+                self.print_file(path)?;
+            }
+            return Ok(());
+        };
 
-        self.file_count += 1;
-        if let Some(n) = lines {
-            self.line_count += n;
+        let cap = self.abort_after_bytes;
+        let links = self.list_symlinks;
+        let cache = self.cache.clone();
+        let in_memory = std::mem::take(&mut self.in_memory);
+        for (n, chunk) in paths.chunks(self.jobs * READ_AHEAD).enumerate() {
+            if self.limit_reached() {
+                break;
+            }
+            if self.budget_reached() {
+                let done = n * self.jobs * READ_AHEAD;
+                self.summary.files_omitted += paths.len() - done;
+                break;
+            }
+            let loaded: Vec<(usize, Loaded)> = pool.install(|| {
+                chunk
+                    .par_iter()
+                    .enumerate()
+                    .map(|(i, path)| {
+                        let loaded = match in_memory.get(path) {
+                            Some(content) => Loaded::Read(Ok(content.clone()), None),
+                            None => load(path, cap, links, cache.as_ref()),
+                        };
+                        (i, loaded)
+                    })
+                    .collect()
+            });
+            for (i, file) in loaded {
+                if self.limit_reached() {
+                    break;
+                }
+                if self.budget_reached() {
+                    self.summary.files_omitted += 1;
+                    continue;
+                }
+                self.emit(&chunk[i], file)?;
+            }
         }
 
         Ok(())
     }
 
-    pub fn print_summary(&self) {
-        println!(
-            "{}",
-            format!(
-                "── Summary: {} file{}, {} line{}{}",
-                self.file_count,
-                if self.file_count == 1 { "" } else { "s" },
-                self.line_count,
-                if self.line_count == 1 { "" } else { "s" },
-                if self.skipped_unreadable > 0 {
-                    format!(", {} unreadable skipped", self.skipped_unreadable)
-                } else {
-                    String::new()
+    fn emit(&mut self, path: &Path, loaded: Loaded) -> DumpResult<()> {
+        if let Some(hook) = self.on_file.as_mut() {
+            hook(path);
+        }
+        // Decoded before the header, which may show the line count; cached
+        // content already is.
+        let bytes: Vec<u8>;
+        let sniff = self.renderer.shows_mime();
+        let (mut content, mut raw_size, digest, key, mime) = match loaded {
+            Loaded::Unreadable => {
+                crate::warning!("cannot read '{}' (permission denied)", path.display());
+                self.summary.skipped_unreadable += 1;
+                return Ok(());
+            },
+            Loaded::Link(target) => return self.emit_link(path, &target),
+            Loaded::Cached(file) => {
+                self.summary.cache_hits += 1;
+                (
+                    Cow::Owned(file.content),
+                    file.size,
+                    Some(file.digest),
+                    None,
+                    file.mime.map(Cow::Owned),
+                )
+            },
+            // Read once: the same bytes feed hashing, printing, and line counting.
+            Loaded::Read(result, key) => {
+                bytes = result.context(IoSnafu {
+                    path: path.display().to_string(),
+                })?;
+                let digest =
+                    (self.dedupe_content || key.is_some()).then(|| Sha256::digest(&bytes).into());
+                // Sniffed for the cache too, so a hit needs no read to show it.
+                let mime = (sniff || key.is_some())
+                    .then(|| sniff_mime(&bytes))
+                    .flatten()
+                    .map(Cow::Borrowed);
+                (decode(&bytes), bytes.len() as u64, digest, key, mime)
+            },
+        };
+
+        // The cache keeps the file's own content, not the filter command's output.
+        if let (Some(cache), Some(key), Some(digest)) = (&self.cache, &key, &digest)
+            && let Err(e) = cache.put(key, digest, raw_size, mime.as_deref(), &content)
+        {
+            crate::warning!("cannot cache '{}': {e}", path.display());
+        }
+        if let Some(command) = &self.filter_cmd {
+            match run_filter_cmd(command, &content, self.filter_timeout) {
+                Ok(filtered) => {
+                    raw_size = filtered.len() as u64;
+                    content = Cow::Owned(filtered);
+                },
+                Err(e) => crate::warning!(
+                    "filter command failed on '{}' ({e}); printing it unchanged",
+                    path.display()
+                ),
+            }
+        }
+
+        // A file that doesn't fit under `abort_after_bytes` is cut at the limit,
+        // and nothing after it is printed.
+        let remaining = self.bytes_remaining();
+        let overflow = remaining.filter(|&left| raw_size > left);
+        if overflow == Some(0) {
+            self.out.flush().context(WriteSnafu)?;
+            return self.size_exceeded();
+        }
+        if let Some(left) = overflow {
+            let cut = content.floor_char_boundary(usize::try_from(left).unwrap_or(usize::MAX));
+            content = Cow::Owned(content[..cut].to_string());
+        }
+        let size = match overflow {
+            Some(_) => content.len() as u64,
+            None => raw_size,
+        };
+        let lines = content.lines().count();
+
+        let duplicate_of = if let Some(digest) = digest.filter(|_| self.dedupe_content) {
+            match self.seen.get(&digest) {
+                Some(first) => Some(first.clone()),
+                None => {
+                    self.seen.insert(digest, path.to_path_buf());
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        let first = duplicate_of.as_deref().map(|first| self.shown(first));
+        let body = match &first {
+            Some(first) => Body::Duplicate(first),
+            None => Body::Content(&content),
+        };
+        let mime = mime.as_deref().filter(|_| sniff);
+        self.render(path, size, lines, body, overflow.is_some(), mime)?;
+        // Flush per file so stderr warnings land between sections, not inside one.
+        self.out.flush().context(WriteSnafu)?;
+
+        if duplicate_of.is_some() {
+            self.summary.duplicates_elided += 1;
+            self.summary.lines_saved += lines;
+            return Ok(());
+        }
+        self.summary.record_file(path, lines, size);
+        if self.summary.tokens.is_some() {
+            self.add_tokens(&content);
+        }
+
+        match overflow {
+            Some(_) => self.size_exceeded(),
+            None => Ok(()),
+        }
+    }
+
+    /// A listed symlink: its header and `-> target`, or whatever the format
+    /// makes of [`Body::Link`].
+    fn emit_link(&mut self, path: &Path, target: &Path) -> DumpResult<()> {
+        self.render(path, 0, 1, Body::Link(target), false, None)?;
+        self.out.flush().context(WriteSnafu)?;
+        self.summary.record_file(path, 1, 0);
+        Ok(())
+    }
+
+    /// Hand one file to the renderer. `bytes` and `lines` fill in the
+    /// `header_template`.
+    fn render(
+        &mut self,
+        path: &Path,
+        bytes: u64,
+        lines: usize,
+        body: Body<'_>,
+        cut_off: bool,
+        mime: Option<&str>,
+    ) -> DumpResult<()> {
+        let shown = self.shown(path);
+        let section = FileSection {
+            path,
+            title: self
+                .header_template
+                .as_deref()
+                .map(|template| render_header(template, Path::new(&shown), bytes, lines)),
+            git: self
+                .git_meta
+                .as_mut()
+                .and_then(|g| g.lookup(path))
+                .map(|meta| meta.to_string()),
+            stat: self.stat(path),
+            path_comment: self.path_comment(path, &shown, &body),
+            shown: &shown,
+            mime,
+            body,
+            cut_off,
+            lines: &self.lines,
+        };
+        self.renderer
+            .file(&mut self.out, &section)
+            .context(WriteSnafu)?;
+        self.summary.lines_truncated += self.lines.truncated.take();
+        Ok(())
+    }
+
+    fn size_exceeded(&self) -> DumpResult<()> {
+        TotalSizeExceededSnafu {
+            limit: self.abort_after_bytes.unwrap_or_default(),
+        }
+        .fail()
+    }
+
+    /// With `metadata`, the file's [`FileStat`], from one `fs::metadata` call.
+    fn stat(&self, path: &Path) -> Option<FileStat> {
+        self.metadata.then(|| FileStat::of(path)).flatten()
+    }
+
+    /// With `path_comment`, a synthetic first line such as `// src/main.rs`.
+    fn path_comment(&self, path: &Path, shown: &str, body: &Body<'_>) -> Option<String> {
+        if !self.path_comment {
+            return None;
+        }
+        let head = match body {
+            Body::Content(content) => content.as_bytes(),
+            _ => &[],
+        };
+        let (open, close) = language::detect(path, head).and_then(language::comment_syntax)?;
+        Some(format!("{open} {shown}{close}"))
+    }
+
+    /// Add `text` to the token estimate, if token counting is on.
+    fn add_tokens(&mut self, text: &str) {
+        if let Some(total) = &mut self.summary.tokens {
+            *total += tokens::estimate(text);
+        }
+    }
+
+    /// Totals so far, with `elapsed_ms` measured up to this call.
+    pub fn summary(&self) -> DumpSummary {
+        let elapsed = self.started.elapsed().as_millis();
+        DumpSummary {
+            elapsed_ms: u64::try_from(elapsed).unwrap_or(u64::MAX),
+            ..self.summary.clone()
+        }
+    }
+
+    /// Print the summary; see [`Renderer::summary`].
+    pub fn print_summary(&mut self, format: SummaryFormat) -> DumpResult<()> {
+        let summary = self.summary();
+        self.renderer
+            .summary(&mut self.out, &summary, format)
+            .context(WriteSnafu)?;
+        self.out.flush().context(WriteSnafu)
+    }
+
+    /// Print the `── Skipped:` report for `stats`; see [`Renderer::skipped`].
+    pub fn print_skipped(&mut self, stats: &FilterStats) -> DumpResult<()> {
+        self.renderer
+            .skipped(&mut self.out, stats)
+            .context(WriteSnafu)?;
+        self.out.flush().context(WriteSnafu)
+    }
+
+    /// Consume the printer, returning its writer, e.g. an in-memory buffer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Flush buffered output. Call before writing to stdout by other means.
+    pub fn flush(&mut self) -> DumpResult<()> {
+        self.out.flush().context(WriteSnafu)
+    }
+}
+
+/// The line options: tab expansion, then truncation or wrapping, applied as a
+/// renderer writes content.
+#[derive(Debug, Default)]
+pub(crate) struct LineOptions {
+    max_line_length: Option<usize>,
+    wrap: Option<usize>,
+    expand_tabs: Option<usize>,
+    expand_all_tabs: bool,
+    /// Lines cut by `max_line_length` since the printer last took the count.
+    truncated: Cell<usize>,
+}
+
+impl LineOptions {
+    fn new(cfg: &PrinterConfig) -> Self {
+        Self {
+            max_line_length: cfg.max_line_length.filter(|&n| n > 0),
+            wrap: cfg.wrap.filter(|&n| n > 0),
+            expand_tabs: cfg.expand_tabs.filter(|&n| n > 0),
+            expand_all_tabs: cfg.expand_all_tabs,
+            truncated: Cell::new(0),
+        }
+    }
+
+    /// `content` with tab expansion and truncation or wrapping applied.
+    pub(crate) fn apply<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        if self.max_line_length.is_none() && self.wrap.is_none() && self.expand_tabs.is_none() {
+            return Cow::Borrowed(content);
+        }
+
+        let mut out = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            let body = line.trim_end_matches(['\n', '\r']);
+            let ending = &line[body.len()..];
+            let body = match self.expand_tabs {
+                Some(width) => expand_tabs(body, width, self.expand_all_tabs),
+                None => Cow::Borrowed(body),
+            };
+            if let Some(max) = self.max_line_length {
+                if truncate_line(&body, max, &mut out) {
+                    self.truncated.set(self.truncated.get() + 1);
                 }
-            )
-            .dimmed()
-        );
+            } else if let Some(width) = self.wrap {
+                wrap_line(&body, width, &mut out);
+            } else {
+                out.push_str(&body);
+            }
+            out.push_str(ending);
+        }
+        Cow::Owned(out)
     }
 }
 
-fn is_readable(path: &Path) -> bool {
-    fs::File::open(path).is_ok()
+/// Fill in a `header_template`. Unknown placeholders are left as they are;
+/// `{ext}` is empty for files without an extension.
+fn render_header(template: &str, path: &Path, bytes: u64, lines: usize) -> String {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy())
+        .unwrap_or_default();
+    template
+        .replace("{path}", &path.display().to_string())
+        .replace("{bytes}", &bytes.to_string())
+        .replace("{lines}", &lines.to_string())
+        .replace("{ext}", &ext)
 }
 
-fn bat_available() -> bool {
-    which_bat().is_some()
+/// Replace tabs with spaces up to the next multiple of `width` columns. Only the
+/// leading indentation is touched unless `all` is set.
+fn expand_tabs(line: &str, width: usize, all: bool) -> Cow<'_, str> {
+    if !line.contains('\t') {
+        return Cow::Borrowed(line);
+    }
+
+    let mut out = String::with_capacity(line.len() + width);
+    let mut column = 0;
+    let mut leading = true;
+    for (i, c) in line.char_indices() {
+        leading &= c == ' ' || c == '\t';
+        if !leading && !all {
+            out.push_str(&line[i..]);
+            break;
+        }
+        if c == '\t' {
+            let pad = width - column % width;
+            out.extend(std::iter::repeat_n(' ', pad));
+            column += pad;
+        } else {
+            out.push(c);
+            column += 1;
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Append `line` to `out`, cut after `max` characters with a `… (+N chars)` suffix.
+/// Returns true if the line was truncated. Cuts on char boundaries, never inside a
+/// UTF-8 sequence.
+fn truncate_line(line: &str, max: usize, out: &mut String) -> bool {
+    match line.char_indices().nth(max) {
+        Some((cut, _)) => {
+            let rest = line[cut..].chars().count();
+            out.push_str(&line[..cut]);
+            out.push_str(&format!("… (+{} chars)", group_thousands(rest)));
+            true
+        },
+        None => {
+            out.push_str(line);
+            false
+        },
+    }
+}
+
+/// Append `line` to `out`, hard-wrapped every `width` characters.
+fn wrap_line(line: &str, width: usize, out: &mut String) {
+    for (i, c) in line.chars().enumerate() {
+        if i > 0 && i.is_multiple_of(width) {
+            out.push('\n');
+        }
+        out.push(c);
+    }
+}
+
+/// Format `n` with comma thousands separators, e.g. 38204 -> "38,204".
+fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/// Read `path`, or with a `cap` at most one byte more than `cap` — enough to see
+/// that it doesn't fit, without holding a huge file in memory. With
+/// `list_links`, a symlink is not read at all, and with a `cache`, neither is a
+/// file unchanged since it was cached.
+fn load(path: &Path, cap: Option<u64>, list_links: bool, cache: Option<&ContentCache>) -> Loaded {
+    if list_links && let Ok(target) = fs::read_link(path) {
+        return Loaded::Link(target);
+    }
+    // Checked first, so a file that became unreadable is not served from the cache.
+    if !is_readable(path) {
+        return Loaded::Unreadable;
+    }
+    // Keyed before the read, so a file changed meanwhile is stored under a
+    // stale key and misses next time.
+    let key = cache.and_then(|cache| cache.key(path));
+    if let (Some(cache), Some(key)) = (cache, &key)
+        && let Some(file) = cache.get(key)
+    {
+        return Loaded::Cached(file);
+    }
+    let read = match cap {
+        None => fs::read(path),
+        Some(cap) => read_up_to(path, cap.saturating_add(1)),
+    };
+    Loaded::Read(read, key)
+}
+
+/// Pipe `content` through the shell command `command` and return its stdout,
+/// decoded lossily; or why that failed: the command could not start, exited
+/// non-zero, or was still running after `timeout` and was killed.
+fn run_filter_cmd(command: &str, content: &str, timeout: Option<Duration>) -> io::Result<String> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    // Fed and drained from other threads, so a command that writes while still
+    // reading cannot stall on a full pipe, and one that hangs holds up neither.
+    // A command that ignores its input closes the pipe early, which is no error.
+    let input = content.as_bytes().to_vec();
+    std::thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+    let status = match timeout {
+        Some(timeout) => child.wait_timeout(timeout)?,
+        None => Some(child.wait()?),
+    };
+    let Some(status) = status else {
+        // Kill and reap, so no zombie is left behind.
+        let _ = child.kill();
+        let _ = child.wait();
+        let secs = timeout.map_or(0, |timeout| timeout.as_secs());
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("timed out after {secs}s"),
+        ));
+    };
+    if !status.success() {
+        return Err(io::Error::other(status.to_string()));
+    }
+    let output = reader
+        .join()
+        .map_err(|_| io::Error::other("reading its output failed"))??;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+fn read_up_to(path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    fs::File::open(path)?.take(limit).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn is_readable(path: &Path) -> bool {
+    fs::File::open(path).is_ok()
 }
 
 fn which_bat() -> Option<String> {
@@ -96,28 +838,714 @@ fn which_bat() -> Option<String> {
     None
 }
 
-fn print_with_bat(path: &Path) -> Option<usize> {
-    let bat = which_bat()?;
-    let status = Command::new(&bat)
-        .args(["--style=numbers", "--color=always", "--pager=none"])
-        .arg(path)
-        .status()
-        .ok()?;
+/// Theme names bat accepts, in bat's order, or `None` if bat is not installed.
+pub fn bat_themes() -> Option<Vec<String>> {
+    let output = Command::new(which_bat()?)
+        .args(["--list-themes", "--color=never"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect(),
+    )
+}
 
-    if !status.success() {
-        print_with_cat(path).ok()?
-    } else {
-        count_lines(path)
+/// Fail with `UnknownTheme` if bat is installed and has no theme called `name`.
+/// Without bat there is nothing to highlight, so any name passes.
+pub fn check_theme(name: &str) -> DumpResult<()> {
+    match bat_themes() {
+        Some(themes) if !themes.iter().any(|t| t == name) => UnknownThemeSnafu {
+            name,
+            available: themes.join(", "),
+        }
+        .fail(),
+        _ => Ok(()),
     }
 }
 
-fn print_with_cat(path: &Path) -> std::io::Result<Option<usize>> {
-    let content = fs::read_to_string(path)?;
-    print!("{content}");
-    Ok(Some(content.lines().count()))
+/// Print `path` through the `bat` binary. Returns false if bat could not run,
+/// failed, or outlived `timeout`, in which case the caller falls back to plain
+/// output. Whatever bat printed before a timeout stays on screen.
+pub(crate) fn print_with_bat(
+    bat: &str,
+    path: &Path,
+    tabs: Option<usize>,
+    theme: Option<&str>,
+    timeout: Option<Duration>,
+) -> bool {
+    let Ok(mut child) = bat_command(bat, path, tabs, theme).spawn() else {
+        return false;
+    };
+    let Some(timeout) = timeout else {
+        return child.wait().is_ok_and(|status| status.success());
+    };
+    let waited = child.wait_timeout(timeout);
+    if let Ok(Some(status)) = waited {
+        return status.success();
+    }
+    if waited.is_ok() {
+        crate::warning!(
+            "bat timed out on '{}' after {}s; printing it plainly",
+            path.display(),
+            timeout.as_secs()
+        );
+    }
+    // Kill and reap, so no zombie is left behind.
+    let _ = child.kill();
+    let _ = child.wait();
+    false
 }
 
-fn count_lines(path: &Path) -> Option<usize> {
-    let content = fs::read_to_string(path).ok()?;
-    Some(content.lines().count())
+/// The bat invocation [`print_with_bat`] runs for `path`.
+fn bat_command(bat: &str, path: &Path, tabs: Option<usize>, theme: Option<&str>) -> Command {
+    let mut cmd = Command::new(bat);
+    cmd.args(["--style=numbers", "--color=always", "--pager=none"]);
+    if let Some(width) = tabs {
+        cmd.arg(format!("--tabs={width}"));
+    }
+    if let Some(theme) = theme {
+        cmd.arg(format!("--theme={theme}"));
+    }
+    cmd.arg(path);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{markdown, render::SEPARATOR};
+
+    #[test]
+    fn bat_command_passes_the_theme_through() {
+        let path = Path::new("src/main.rs");
+        let args = |theme| -> Vec<String> {
+            bat_command("bat", path, None, theme)
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+        assert!(args(Some("Dracula")).contains(&"--theme=Dracula".to_string()));
+        assert!(!args(None).iter().any(|arg| arg.starts_with("--theme")));
+        assert_eq!(args(None).last().map(String::as_str), Some("src/main.rs"));
+    }
+
+    fn truncated(line: &str, max: usize) -> (String, bool) {
+        let mut out = String::new();
+        let cut = truncate_line(line, max, &mut out);
+        (out, cut)
+    }
+
+    fn wrapped(line: &str, width: usize) -> String {
+        let mut out = String::new();
+        wrap_line(line, width, &mut out);
+        out
+    }
+
+    #[test]
+    fn short_line_is_not_truncated() {
+        assert_eq!(truncated("hello", 5), ("hello".to_string(), false));
+    }
+
+    #[test]
+    fn long_line_is_truncated_with_suffix() {
+        assert_eq!(
+            truncated("hello world", 5),
+            ("hello… (+6 chars)".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn truncation_respects_char_boundaries() {
+        let (out, cut) = truncated("ééééé", 2);
+        assert!(cut);
+        assert_eq!(out, "éé… (+3 chars)");
+    }
+
+    #[test]
+    fn truncation_suffix_groups_thousands() {
+        let line = "x".repeat(38_214);
+        let (out, _) = truncated(&line, 10);
+        assert!(out.ends_with("… (+38,204 chars)"));
+    }
+
+    #[test]
+    fn wrap_splits_every_width_chars() {
+        assert_eq!(wrapped("abcdefg", 3), "abc\ndef\ng");
+    }
+
+    #[test]
+    fn wrap_respects_char_boundaries() {
+        assert_eq!(wrapped("日本語テキスト", 3), "日本語\nテキス\nト");
+    }
+
+    #[test]
+    fn wrap_leaves_short_line_alone() {
+        assert_eq!(wrapped("abc", 3), "abc");
+    }
+
+    #[test]
+    fn expands_leading_tabs_only() {
+        assert_eq!(expand_tabs("\t\tx\ty", 4, false), "        x\ty");
+    }
+
+    #[test]
+    fn leading_tab_after_spaces_aligns_to_tab_stop() {
+        assert_eq!(expand_tabs("  \tx", 4, false), "    x");
+    }
+
+    #[test]
+    fn expands_all_tabs_column_aware() {
+        assert_eq!(expand_tabs("\tab\tc", 4, true), "    ab  c");
+    }
+
+    #[test]
+    fn line_without_tabs_is_borrowed() {
+        assert!(matches!(expand_tabs("no tabs", 4, true), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn renders_into_an_in_memory_buffer() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello\n").unwrap();
+
+        crate::utils::set_color(false);
+        let mut printer = Printer::with_writer(&PrinterConfig::default(), Vec::new());
+        printer.print_verbatim("preamble").unwrap();
+        printer.print_file(&path).unwrap();
+        printer.print_summary(SummaryFormat::Text).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        assert!(out.starts_with("preamble\n"));
+        assert!(out.contains(&format!(" FILE: {}\n", path.display())));
+        assert!(out.contains("hello\n"));
+        assert!(out.ends_with("── Summary: 1 file, 1 line\n"));
+        assert!(!out.contains('\x1b'));
+    }
+
+    #[test]
+    fn markdown_fences_each_file_under_an_anchored_heading() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main = dir.path().join("main.rs");
+        let notes = dir.path().join("notes.txt");
+        fs::write(&main, "fn main() {}\n").unwrap();
+        fs::write(&notes, "no trailing newline").unwrap();
+
+        let cfg = PrinterConfig {
+            format: OutputFormat::Markdown,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        let files = vec![main.clone(), notes.clone()];
+        printer.print_toc(&files).unwrap();
+        printer.print_files(&files).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        let anchor = markdown::slug(&main.display().to_string());
+        assert!(out.starts_with("## Contents\n\n"));
+        assert!(out.contains(&format!("- [{}](#{anchor})\n", main.display())));
+        assert!(out.contains(&format!(
+            "<a id=\"{anchor}\"></a>\n## {}\n\n```rust\nfn main() {{}}\n```\n",
+            main.display()
+        )));
+        assert!(out.contains("```\nno trailing newline\n```\n"));
+    }
+
+    #[test]
+    fn ndjson_writes_one_object_per_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        fs::write(&a, "line \"one\"\n\ttwo\n").unwrap();
+        fs::write(&b, "line \"one\"\n\ttwo\n").unwrap();
+
+        let cfg = PrinterConfig {
+            format: OutputFormat::Ndjson,
+            dedupe_content: true,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_files(&[a.clone(), b.clone()]).unwrap();
+        printer.print_summary(SummaryFormat::Text).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        let records: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["path"], a.display().to_string());
+        assert_eq!(records[0]["content"], "line \"one\"\n\ttwo\n");
+        assert_eq!(records[1]["duplicate_of"], a.display().to_string());
+        assert!(records[1].get("content").is_none());
+        assert_eq!(records[2]["summary"]["file_count"], 1);
+    }
+
+    #[test]
+    fn ndjson_records_carry_the_sniffed_mime() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pdf = dir.path().join("doc.pdf");
+        let txt = dir.path().join("notes.txt");
+        fs::write(&pdf, "%PDF-1.7\n").unwrap();
+        fs::write(&txt, "just text\n").unwrap();
+
+        let cfg = PrinterConfig {
+            format: OutputFormat::Ndjson,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_files(&[pdf, txt]).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        let records: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[0]["mime"], "application/pdf");
+        assert!(records[1].get("mime").is_none());
+    }
+
+    #[test]
+    fn text_toc_numbers_files_without_reading_them() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "fn a() {}\n").unwrap();
+        fs::write(&b, "x\ny\nz\n").unwrap();
+        let missing = dir.path().join("gone.rs");
+        let mut printer = Printer::with_writer(&PrinterConfig::default(), Vec::new());
+        printer
+            .print_toc(&[a.clone(), b.clone(), missing.clone()])
+            .unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            format!(
+                "Contents:\n  1. {}\n  2. {}\n  3. {}\n\n",
+                a.display(),
+                b.display(),
+                missing.display()
+            )
+        );
+    }
+
+    #[test]
+    fn toc_is_skipped_in_manifest_output() {
+        let cfg = PrinterConfig {
+            format: OutputFormat::Manifest,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_toc(&[PathBuf::from("a.rs")]).unwrap();
+        assert!(printer.into_inner().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listed_symlink_prints_its_target_instead_of_content() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let link = dir.path().join("alias.rs");
+        fs::write(dir.path().join("real.rs"), "fn real() {}\n").unwrap();
+        std::os::unix::fs::symlink("real.rs", &link).unwrap();
+        let cfg = PrinterConfig {
+            use_bat: false,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.set_symlinks(Symlinks::List);
+        printer.print_file(&link).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+        assert!(out.contains(&format!(" FILE: {}", link.display())));
+        assert!(out.contains("-> real.rs\n"));
+        assert!(!out.contains("fn real"));
+    }
+
+    #[test]
+    fn cache_dir_serves_unchanged_files_on_the_next_run() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = tempfile::TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.rs", "b.rs", "c.rs"]
+            .iter()
+            .map(|n| dir.path().join(n))
+            .collect();
+        for (i, path) in files.iter().enumerate() {
+            fs::write(path, format!("fn f{i}() {{}}\n")).unwrap();
+        }
+        let cfg = PrinterConfig {
+            use_bat: false,
+            jobs: 2,
+            cache_dir: Some(cache.path().to_path_buf()),
+            ..PrinterConfig::default()
+        };
+        let run = || {
+            let mut printer = Printer::with_writer(&cfg, Vec::new());
+            printer.print_files(&files).unwrap();
+            let summary = printer.summary();
+            (String::from_utf8(printer.into_inner()).unwrap(), summary)
+        };
+
+        let (first, summary) = run();
+        assert_eq!((summary.file_count, summary.cache_hits), (3, 0));
+        let (second, summary) = run();
+        assert_eq!((summary.file_count, summary.cache_hits), (3, 3));
+        assert_eq!(second, first);
+
+        // A changed file is read again; the others still come from the cache.
+        fs::write(&files[1], "fn changed() {}\n").unwrap();
+        let (third, summary) = run();
+        assert_eq!((summary.file_count, summary.cache_hits), (3, 2));
+        assert!(third.contains("fn changed()"));
+    }
+
+    #[test]
+    fn cached_files_still_dedupe() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = tempfile::TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.txt", "b.txt"]
+            .iter()
+            .map(|n| dir.path().join(n))
+            .collect();
+        for path in &files {
+            fs::write(path, "same\n").unwrap();
+        }
+        let cfg = PrinterConfig {
+            use_bat: false,
+            dedupe_content: true,
+            cache_dir: Some(cache.path().to_path_buf()),
+            ..PrinterConfig::default()
+        };
+        for hits in [0, 2] {
+            let mut printer = Printer::with_writer(&cfg, Vec::new());
+            printer.print_files(&files).unwrap();
+            let summary = printer.summary();
+            assert_eq!((summary.cache_hits, summary.duplicates_elided), (hits, 1));
+        }
+    }
+
+    #[test]
+    fn cached_files_keep_their_sniffed_mime() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache = tempfile::TempDir::new().unwrap();
+        let pdf = dir.path().join("doc.pdf");
+        fs::write(&pdf, "%PDF-1.7\n").unwrap();
+        let cfg = PrinterConfig {
+            format: OutputFormat::Ndjson,
+            cache_dir: Some(cache.path().to_path_buf()),
+            ..PrinterConfig::default()
+        };
+        for hits in [0, 1] {
+            let mut printer = Printer::with_writer(&cfg, Vec::new());
+            printer.print_files(std::slice::from_ref(&pdf)).unwrap();
+            assert_eq!(printer.summary().cache_hits, hits);
+            let out = String::from_utf8(printer.into_inner()).unwrap();
+            let record: serde_json::Value = serde_json::from_str(out.trim_end()).unwrap();
+            assert_eq!(record["mime"], "application/pdf");
+        }
+    }
+
+    #[test]
+    fn on_file_hook_sees_every_printed_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.rs", "b.rs"]
+            .iter()
+            .map(|n| dir.path().join(n))
+            .collect();
+        for file in &files {
+            fs::write(file, "x\n").unwrap();
+        }
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut printer = Printer::with_writer(&PrinterConfig::default(), Vec::new());
+        let sink = std::rc::Rc::clone(&seen);
+        printer.set_on_file(move |path| sink.borrow_mut().push(path.to_path_buf()));
+        printer.print_files(&files).unwrap();
+        assert_eq!(*seen.borrow(), files);
+    }
+
+    #[test]
+    fn header_template_substitutes_placeholders() {
+        let path = Path::new("src/main.rs");
+        assert_eq!(
+            render_header(
+                "{path} ({lines} lines, {bytes} B, .{ext}) {other}",
+                path,
+                42,
+                3
+            ),
+            "src/main.rs (3 lines, 42 B, .rs) {other}"
+        );
+        assert_eq!(render_header("[{ext}]", Path::new("Makefile"), 0, 0), "[]");
+    }
+
+    #[test]
+    fn header_template_replaces_file_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one\ntwo\n").unwrap();
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            header_template: Some(">> {lines} lines, {bytes} bytes, {ext}".into()),
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_file(&path).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        assert!(out.contains(&format!(
+            "{SEPARATOR}\n>> 2 lines, 8 bytes, txt\n{SEPARATOR}\n"
+        )));
+        assert!(!out.contains(" FILE: "));
+    }
+
+    #[test]
+    fn metadata_adds_size_mtime_and_mode_to_the_header() {
+        use std::time::UNIX_EPOCH;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one\ntwo\n").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1_709_640_000))
+            .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            metadata: true,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_file(&path).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        let expected = " META: 8 bytes, modified 2024-03-05T12:00:00Z";
+        assert!(out.contains(expected), "{out}");
+        #[cfg(unix)]
+        assert!(out.contains(&format!("{expected}, mode 0640\n")), "{out}");
+    }
+
+    #[test]
+    fn abort_after_bytes_cuts_on_a_char_boundary_and_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "ééé\n").unwrap();
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            abort_after_bytes: Some(3),
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        let err = printer.print_file(&path).unwrap_err();
+        assert!(matches!(err, crate::DumpError::TotalSizeExceeded {
+            limit: 3
+        }));
+        assert_eq!(printer.summary().byte_count, 2);
+
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+        assert!(out.contains(&format!("{SEPARATOR}\né\n── output size limit reached")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filter_cmd_rewrites_content_and_falls_back_when_it_fails() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello\n").unwrap();
+
+        crate::utils::set_color(false);
+        let run = |command: &str| {
+            let cfg = PrinterConfig {
+                filter_cmd: Some(command.to_string()),
+                ..PrinterConfig::default()
+            };
+            let mut printer = Printer::with_writer(&cfg, Vec::new());
+            printer.print_file(&path).unwrap();
+            String::from_utf8(printer.into_inner()).unwrap()
+        };
+
+        assert!(run("tr a-z A-Z").contains("HELLO\n"));
+        assert!(run("exit 3").contains("hello\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filter_cmd_is_killed_after_its_timeout() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello\n").unwrap();
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            filter_cmd: Some("exec sleep 30".into()),
+            filter_cmd_timeout: 1,
+            ..PrinterConfig::default()
+        };
+        let started = Instant::now();
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_file(&path).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(20));
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+        assert!(out.contains("hello\n"), "{out}");
+    }
+
+    #[test]
+    fn max_files_counts_only_printed_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "missing.txt", "c.txt", "d.txt"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for path in paths.iter().filter(|path| !path.ends_with("missing.txt")) {
+            std::fs::write(path, "x\n").unwrap();
+        }
+        for jobs in [1, 2] {
+            let cfg = PrinterConfig {
+                jobs,
+                use_bat: false,
+                ..PrinterConfig::default()
+            };
+            let mut printer = Printer::with_writer(&cfg, Vec::new());
+            printer.set_max_files(2);
+            printer.print_files(&paths).unwrap();
+            let summary = printer.summary();
+            assert_eq!((summary.file_count, summary.files_omitted), (2, 0));
+            let out = String::from_utf8(printer.into_inner()).unwrap();
+            assert!(out.contains("c.txt") && !out.contains("d.txt"), "{out}");
+        }
+    }
+
+    #[test]
+    fn in_memory_files_print_without_touching_the_disk() {
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            jobs: 2,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        let paths: Vec<PathBuf> = ["src.zip!/a.rs", "src.zip!/b.rs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        for (path, content) in paths.iter().zip(["fn a() {}\n", "fn b() {}\n"]) {
+            printer.add_in_memory(path.clone(), content.into());
+        }
+        printer.print_files(&paths).unwrap();
+        assert_eq!(printer.summary().file_count, 2);
+
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+        assert!(out.contains(" FILE: src.zip!/a.rs\n"), "{out}");
+        assert!(out.find("fn a() {}").unwrap() < out.find("fn b() {}").unwrap());
+    }
+
+    #[test]
+    fn path_comment_uses_the_language_comment_syntax() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.rs", "b.toml", "c.html", "d.json"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, "x\n").unwrap();
+                path
+            })
+            .collect();
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            format: OutputFormat::Markdown,
+            path_comment: true,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.print_files(&files).unwrap();
+        assert_eq!(printer.summary().line_count, 4);
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        let shown = |i: usize| files[i].display().to_string();
+        assert!(out.contains(&format!("```rust\n// {}\nx\n```", shown(0))));
+        assert!(out.contains(&format!("```toml\n# {}\nx\n```", shown(1))));
+        assert!(out.contains(&format!("```html\n<!-- {} -->\nx\n```", shown(2))));
+        assert!(out.contains("```json\nx\n```"));
+    }
+
+    #[test]
+    fn root_label_replaces_the_walked_root() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main = dir.path().join("src").join("main.rs");
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(&main, "fn main() {}\n").unwrap();
+
+        crate::utils::set_color(false);
+        let cfg = PrinterConfig {
+            format: OutputFormat::Markdown,
+            root_label: Some("project".into()),
+            path_comment: true,
+            ..PrinterConfig::default()
+        };
+        let mut printer = Printer::with_writer(&cfg, Vec::new());
+        printer.set_roots(&[dir.path().to_path_buf()]);
+        printer.print_toc(std::slice::from_ref(&main)).unwrap();
+        printer.print_file(&main).unwrap();
+        let out = String::from_utf8(printer.into_inner()).unwrap();
+
+        assert!(out.contains("- [project/src/main.rs](#project-src-main-rs)\n"));
+        assert!(out.contains("## project/src/main.rs\n"));
+        assert!(out.contains("// project/src/main.rs\n"));
+        assert!(!out.contains(&dir.path().display().to_string()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bat_is_probed_once_per_printer_not_per_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let files: Vec<PathBuf> = ["a.rs", "b.rs", "c.rs"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                fs::write(&path, "x\n").unwrap();
+                path
+            })
+            .collect();
+
+        // `true` stands in for bat: it accepts any arguments and succeeds.
+        let probes = std::cell::Cell::new(0);
+        let probe = || {
+            probes.set(probes.get() + 1);
+            Some("true".to_string())
+        };
+        let cfg = PrinterConfig::default();
+        let mut printer = Printer::with_bat_probe(&cfg, Vec::new(), probe);
+        printer.print_files(&files).unwrap();
+        assert_eq!(probes.get(), 1);
+        assert_eq!(printer.summary().file_count, 3);
+
+        // Nothing to probe for when bat could not be used anyway.
+        let cfg = PrinterConfig {
+            format: OutputFormat::Markdown,
+            ..PrinterConfig::default()
+        };
+        let probe = || {
+            probes.set(probes.get() + 1);
+            None
+        };
+        drop(Printer::with_bat_probe(&cfg, Vec::new(), probe));
+        assert_eq!(probes.get(), 1);
+    }
+
+    #[test]
+    fn group_thousands_formats() {
+        assert_eq!(group_thousands(0), "0");
+        assert_eq!(group_thousands(999), "999");
+        assert_eq!(group_thousands(1000), "1,000");
+        assert_eq!(group_thousands(1_234_567), "1,234,567");
+    }
 }
@@ -0,0 +1,562 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use colored::Colorize;
+use serde_json::json;
+
+use crate::{
+    config::PrinterConfig,
+    enums::{OutputFormat, SummaryFormat},
+    filter::FilterStats,
+    language,
+    markdown::{self, Slugger},
+    printer::{LineOptions, print_with_bat},
+    summary::DumpSummary,
+    traits::Renderer,
+    utils::time,
+};
+
+pub(crate) const SEPARATOR: &str = "====================================================";
+
+/// One file as the printer hands it to a [`Renderer`], with everything the
+/// formats share already worked out.
+pub struct FileSection<'a> {
+    /// The file on disk.
+    pub path: &'a Path,
+    /// `path` as it appears in the output; see `root_label`.
+    pub shown: &'a str,
+    /// The `header_template` rendering, in place of the format's own title.
+    pub title: Option<String>,
+    /// The last commit touching the file, with `git_meta`.
+    pub git: Option<String>,
+    /// With `metadata`, the file's size, modification time and mode.
+    pub stat: Option<FileStat>,
+    /// With `path_comment`, a first line such as `// src/main.rs`.
+    pub path_comment: Option<String>,
+    /// With a renderer that [`shows_mime`](Renderer::shows_mime), the sniffed
+    /// MIME type, if recognized.
+    pub mime: Option<&'a str>,
+    pub body: Body<'a>,
+    /// The file was cut off by `abort_after_bytes`.
+    pub cut_off: bool,
+    pub(crate) lines: &'a LineOptions,
+}
+
+/// What follows a file's header.
+pub enum Body<'a> {
+    /// The decoded content, before the line options; see [`FileSection::shape`].
+    Content(&'a str),
+    /// With `dedupe_content`, the earlier file (as shown) with the same bytes.
+    Duplicate(&'a str),
+    /// A symlink listed by its target (`symlinks = "list"`).
+    Link(&'a Path),
+}
+
+impl FileSection<'_> {
+    /// `content` with the line options (tab expansion, then truncation or
+    /// wrapping) applied, as a renderer writes it.
+    pub fn shape<'c>(&self, content: &'c str) -> Cow<'c, str> {
+        self.lines.apply(content)
+    }
+}
+
+/// A file listed in a table of contents.
+pub struct TocEntry<'a> {
+    pub path: &'a Path,
+    pub shown: String,
+}
+
+/// What `metadata` adds to a header: size, modification time and, where the
+/// platform has them, Unix permission bits.
+pub struct FileStat {
+    size: u64,
+    modified: Option<SystemTime>,
+    mode: Option<u32>,
+}
+
+impl FileStat {
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            Some(meta.permissions().mode() & 0o7777)
+        };
+        #[cfg(not(unix))]
+        let mode = None;
+        Some(Self {
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            mode,
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut stat = json!({ "size": self.size });
+        if let Some(modified) = self.modified {
+            stat["modified"] = json!(time::format_utc(modified));
+        }
+        if let Some(mode) = self.mode {
+            stat["mode"] = json!(format!("{mode:04o}"));
+        }
+        stat
+    }
+}
+
+/// `1234 bytes, modified 2024-03-05T12:00:00Z, mode 0644`.
+impl fmt::Display for FileStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes", self.size)?;
+        if let Some(modified) = self.modified {
+            write!(f, ", modified {}", time::format_utc(modified))?;
+        }
+        if let Some(mode) = self.mode {
+            write!(f, ", mode {mode:04o}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The renderer for `cfg.format`. `bat` is the bat binary to highlight text
+/// output with, if any.
+pub fn for_format(cfg: &PrinterConfig, bat: Option<String>) -> Box<dyn Renderer> {
+    match cfg.format {
+        OutputFormat::Text => Box::new(TextRenderer::new(cfg, bat)),
+        // Manifests are written by the `manifest` module; a printer asked for
+        // one renders plain text without a table of contents.
+        OutputFormat::Manifest => Box::new(TextRenderer {
+            toc: false,
+            ..TextRenderer::new(cfg, None)
+        }),
+        OutputFormat::Markdown => Box::new(MarkdownRenderer::default()),
+        OutputFormat::Ndjson => Box::new(NdjsonRenderer::default()),
+        OutputFormat::Concat => Box::new(ConcatRenderer),
+    }
+}
+
+/// The cut-off notice after a file `abort_after_bytes` stopped in.
+fn write_cut_off(out: &mut dyn Write, path: &Path) -> io::Result<()> {
+    let notice = format!(
+        "── output size limit reached, {} cut off ──",
+        path.display()
+    );
+    writeln!(out, "{}", notice.yellow())
+}
+
+/// The default format: each file under a separator-framed `FILE:` header, its
+/// content through bat when installed, else plain.
+pub struct TextRenderer {
+    bat: Option<String>,
+    expand_tabs: Option<usize>,
+    theme: Option<String>,
+    bat_timeout: Option<Duration>,
+    toc: bool,
+}
+
+impl TextRenderer {
+    pub fn new(cfg: &PrinterConfig, bat: Option<String>) -> Self {
+        Self {
+            bat,
+            expand_tabs: cfg.expand_tabs.filter(|&n| n > 0),
+            theme: cfg.theme.clone(),
+            bat_timeout: (cfg.bat_timeout > 0).then(|| Duration::from_secs(cfg.bat_timeout)),
+            toc: true,
+        }
+    }
+
+    fn header(&self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()> {
+        writeln!(out, "{}", SEPARATOR.bold().blue())?;
+        let title = file
+            .title
+            .clone()
+            .unwrap_or_else(|| format!(" FILE: {}", file.shown));
+        writeln!(out, "{}", title.bold().blue())?;
+        if let Some(git) = &file.git {
+            writeln!(out, "{}", format!(" GIT:  {git}").blue())?;
+        }
+        if let Some(stat) = &file.stat {
+            writeln!(out, "{}", format!(" META: {stat}").blue())?;
+        }
+        writeln!(out, "{}", SEPARATOR.bold().blue())
+    }
+}
+
+impl Renderer for TextRenderer {
+    fn file(&mut self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()> {
+        self.header(out, file)?;
+        let content = match file.body {
+            Body::Link(target) => return writeln!(out, "-> {}\n", target.display()),
+            Body::Duplicate(first) => {
+                return writeln!(out, "{}\n", format!("(identical to {first})").dimmed());
+            },
+            Body::Content(content) => content,
+        };
+
+        // bat writes straight to the inherited stdout, so drain ours first. It
+        // would print the whole file, so a cut-off file never goes through it,
+        // nor one that is not on disk (an archive entry).
+        let with_bat = match self.bat.as_deref() {
+            Some(bat) if !file.cut_off && file.path.is_file() => {
+                out.flush()?;
+                print_with_bat(
+                    bat,
+                    file.path,
+                    self.expand_tabs,
+                    self.theme.as_deref(),
+                    self.bat_timeout,
+                )
+            },
+            _ => false,
+        };
+        if !with_bat {
+            if let Some(comment) = &file.path_comment {
+                writeln!(out, "{comment}")?;
+            }
+            out.write_all(file.shape(content).as_bytes())?;
+        }
+
+        writeln!(out)?;
+        if file.cut_off {
+            write_cut_off(out, file.path)?;
+        }
+        Ok(())
+    }
+
+    /// `Contents:` and one `N. path` entry per file. Files are not read for it,
+    /// so it costs nothing against `max_total_bytes`.
+    fn toc(&mut self, files: &[TocEntry<'_>]) -> Option<String> {
+        if !self.toc {
+            return None;
+        }
+        let width = files.len().to_string().len();
+        let mut toc = String::from("Contents:\n");
+        for (i, file) in files.iter().enumerate() {
+            toc.push_str(&format!("  {:>width$}. {}\n", i + 1, file.shown));
+        }
+        toc.push('\n');
+        Some(toc)
+    }
+
+    fn reads_files(&self) -> bool {
+        self.bat.is_some()
+    }
+}
+
+/// Markdown: an anchored `##` heading per file and its content in a fenced code
+/// block tagged with the file's language.
+#[derive(Default)]
+pub struct MarkdownRenderer {
+    /// Anchors handed out so far; the table of contents fills this before any file.
+    anchors: HashMap<PathBuf, String>,
+    slugger: Slugger,
+}
+
+impl Renderer for MarkdownRenderer {
+    fn file(&mut self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()> {
+        let anchor = match self.anchors.get(file.path) {
+            Some(anchor) => anchor.clone(),
+            None => self.slugger.slug(file.shown),
+        };
+        // An explicit anchor: renderers disagree on slugging `.` and `/`.
+        writeln!(out, "<a id=\"{anchor}\"></a>")?;
+        writeln!(out, "## {}\n", file.title.as_deref().unwrap_or(file.shown))?;
+        if let Some(git) = &file.git {
+            writeln!(out, "> {git}\n")?;
+        }
+        if let Some(stat) = &file.stat {
+            writeln!(out, "> {stat}\n")?;
+        }
+
+        let content = match file.body {
+            Body::Link(target) => return writeln!(out, "-> {}\n", target.display()),
+            Body::Duplicate(first) => return writeln!(out, "_(identical to {first})_\n"),
+            Body::Content(content) => content,
+        };
+        let fence = markdown::fence(content);
+        let lang = language::detect(file.path, content.as_bytes()).unwrap_or_default();
+        writeln!(out, "{fence}{lang}")?;
+        if let Some(comment) = &file.path_comment {
+            writeln!(out, "{comment}")?;
+        }
+        out.write_all(file.shape(content).as_bytes())?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            writeln!(out)?;
+        }
+        writeln!(out, "{fence}")?;
+
+        writeln!(out)?;
+        if file.cut_off {
+            write_cut_off(out, file.path)?;
+        }
+        Ok(())
+    }
+
+    /// A `## Contents` list linking to each file's anchor.
+    fn toc(&mut self, files: &[TocEntry<'_>]) -> Option<String> {
+        // Reserve the heading's own anchor so no file can claim it.
+        self.slugger.slug("Contents");
+        let mut toc = String::from("## Contents\n\n");
+        for file in files {
+            let anchor = self.slugger.slug(&file.shown);
+            toc.push_str(&format!("- [{}](#{anchor})\n", file.shown));
+            self.anchors.insert(file.path.to_path_buf(), anchor);
+        }
+        toc.push('\n');
+        Some(toc)
+    }
+}
+
+/// NDJSON: one JSON object per line, so consumers can stream the dump. Files,
+/// verbatim text, the skip report and the summary each get their own shape.
+#[derive(Default)]
+pub struct NdjsonRenderer {
+    /// Bytes the `concat` format would have written so far, for the next
+    /// `start_offset`.
+    offset: u64,
+}
+
+impl Renderer for NdjsonRenderer {
+    /// `path`, optional `git`, `metadata` and `mime`, and either `content`
+    /// (with line options applied) or `duplicate_of`. Content comes with its
+    /// `line_count` and its `start_offset`: the byte it begins at in the
+    /// `concat` output of the same dump, verbatim text included. `truncated`
+    /// marks a file cut off by `abort_after_bytes`. A listed symlink has
+    /// `symlink` instead.
+    fn file(&mut self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()> {
+        let mut record = json!({ "path": file.shown });
+        if let Body::Link(target) = file.body {
+            record["symlink"] = json!(target.display().to_string());
+            return writeln!(out, "{record}");
+        }
+        if let Some(git) = &file.git {
+            record["git"] = json!(git);
+        }
+        if let Some(stat) = &file.stat {
+            record["metadata"] = stat.to_json();
+        }
+        if let Some(mime) = file.mime {
+            record["mime"] = json!(mime);
+        }
+        match file.body {
+            Body::Duplicate(first) => record["duplicate_of"] = json!(first),
+            Body::Content(content) => {
+                let content = file.shape(content);
+                record["start_offset"] = json!(self.offset);
+                record["line_count"] = json!(content.lines().count());
+                record["content"] = json!(content);
+                self.offset += concat_len(&content);
+            },
+            Body::Link(_) => {},
+        }
+        if file.cut_off {
+            record["truncated"] = json!(true);
+        }
+        writeln!(out, "{record}")
+    }
+
+    /// A `{"summary": ...}` line, whatever `format`.
+    fn summary(
+        &self,
+        out: &mut dyn Write,
+        summary: &DumpSummary,
+        _format: SummaryFormat,
+    ) -> io::Result<()> {
+        writeln!(out, "{}", json!({ "summary": summary }))
+    }
+
+    fn verbatim(&mut self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        self.offset += concat_len(text);
+        writeln!(out, "{}", json!({ "text": text }))
+    }
+
+    fn skipped(&self, out: &mut dyn Write, stats: &FilterStats) -> io::Result<()> {
+        writeln!(out, "{}", json!({ "skipped": stats }))
+    }
+
+    /// Left to the summary's `files_omitted`.
+    fn budget_notice(&self, _out: &mut dyn Write, _omitted: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn shows_mime(&self) -> bool {
+        true
+    }
+}
+
+/// The bytes `text` takes in concat output: itself, and the newline added
+/// after text that doesn't end in one.
+fn concat_len(text: &str) -> u64 {
+    text.len() as u64 + u64::from(!text.is_empty() && !text.ends_with('\n'))
+}
+
+/// Concat: the contents alone, one file after another, for a single blob to
+/// diff or pipe on. A file not ending in a newline gets one, so the next starts
+/// on its own line; nothing else is added, not even a `path_comment`. Links and
+/// repeated files have no content of their own and print nothing.
+pub struct ConcatRenderer;
+
+impl Renderer for ConcatRenderer {
+    fn file(&mut self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()> {
+        let Body::Content(content) = file.body else {
+            return Ok(());
+        };
+        out.write_all(file.shape(content).as_bytes())?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn budget_notice(&self, _out: &mut dyn Write, _omitted: usize) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section<'a>(shown: &'a str, body: Body<'a>, lines: &'a LineOptions) -> FileSection<'a> {
+        FileSection {
+            path: Path::new(shown),
+            shown,
+            title: None,
+            git: None,
+            stat: None,
+            path_comment: None,
+            mime: None,
+            body,
+            cut_off: false,
+            lines,
+        }
+    }
+
+    fn render(renderer: &mut dyn Renderer, file: &FileSection<'_>) -> String {
+        let mut out = Vec::new();
+        renderer.file(&mut out, file).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn text_frames_the_content_under_a_file_header() {
+        crate::utils::set_color(false);
+        let lines = LineOptions::default();
+        let mut text = TextRenderer::new(&PrinterConfig::default(), None);
+        let file = FileSection {
+            path_comment: Some("// src/a.rs".into()),
+            ..section("src/a.rs", Body::Content("fn a() {}\n"), &lines)
+        };
+        assert_eq!(
+            render(&mut text, &file),
+            format!("{SEPARATOR}\n FILE: src/a.rs\n{SEPARATOR}\n// src/a.rs\nfn a() {{}}\n\n")
+        );
+
+        let duplicate = section("src/b.rs", Body::Duplicate("src/a.rs"), &lines);
+        assert!(render(&mut text, &duplicate).ends_with("\n(identical to src/a.rs)\n\n"));
+    }
+
+    #[test]
+    fn ndjson_writes_one_record_per_file() {
+        let lines = LineOptions::default();
+        let file = FileSection {
+            mime: Some("application/pdf"),
+            cut_off: true,
+            ..section("doc.pdf", Body::Content("%PDF"), &lines)
+        };
+        let record: serde_json::Value =
+            serde_json::from_str(&render(&mut NdjsonRenderer::default(), &file)).unwrap();
+        assert_eq!(
+            record,
+            json!({
+                "path": "doc.pdf",
+                "mime": "application/pdf",
+                "start_offset": 0,
+                "line_count": 1,
+                "content": "%PDF",
+                "truncated": true,
+            })
+        );
+
+        let link = section("latest", Body::Link(Path::new("v2")), &lines);
+        assert_eq!(
+            render(&mut NdjsonRenderer::default(), &link),
+            "{\"path\":\"latest\",\"symlink\":\"v2\"}\n"
+        );
+    }
+
+    #[test]
+    fn ndjson_offsets_follow_the_concat_output() {
+        let lines = LineOptions::default();
+        let mut ndjson = NdjsonRenderer::default();
+        let mut concat = ConcatRenderer;
+        let mut concatenated = Vec::new();
+        let prompt = "review this";
+        ndjson.verbatim(&mut Vec::new(), prompt).unwrap();
+        concat.verbatim(&mut concatenated, prompt).unwrap();
+        let contents = ["one\n", "two\nthree", "", "é\n"];
+        let mut last = None;
+        for (i, content) in contents.into_iter().enumerate() {
+            let shown = format!("{i}.txt");
+            let file = section(&shown, Body::Content(content), &lines);
+            let record: serde_json::Value =
+                serde_json::from_str(&render(&mut ndjson, &file)).unwrap();
+            let start = record["start_offset"].as_u64().unwrap();
+            assert!(last.is_none_or(|last| start >= last));
+            assert_eq!(start, concatenated.len() as u64);
+            assert_eq!(record["line_count"], content.lines().count());
+            concat.file(&mut concatenated, &file).unwrap();
+            let start = start as usize;
+            assert_eq!(
+                &concatenated[start..start + content.len()],
+                content.as_bytes()
+            );
+            last = Some(start as u64);
+        }
+
+        let dup = section("4.txt", Body::Duplicate("0.txt"), &lines);
+        let record: serde_json::Value = serde_json::from_str(&render(&mut ndjson, &dup)).unwrap();
+        assert!(record.get("start_offset").is_none());
+    }
+
+    #[test]
+    fn concat_writes_contents_alone() {
+        let lines = LineOptions::default();
+        let mut concat = ConcatRenderer;
+        let a = FileSection {
+            path_comment: Some("# a.txt".into()),
+            ..section("a.txt", Body::Content("one\n"), &lines)
+        };
+        let b = section("b.txt", Body::Content("two"), &lines);
+        let dup = section("c.txt", Body::Duplicate("a.txt"), &lines);
+        let rendered: String = [a, b, dup]
+            .iter()
+            .map(|file| render(&mut concat, file))
+            .collect();
+        assert_eq!(rendered, "one\ntwo\n");
+    }
+
+    #[test]
+    fn markdown_toc_anchors_are_reused_by_the_headings() {
+        let lines = LineOptions::default();
+        let mut md = MarkdownRenderer::default();
+        let toc = md
+            .toc(&[TocEntry {
+                path: Path::new("contents"),
+                shown: "contents".into(),
+            }])
+            .unwrap();
+        // "contents" collides with the heading's own anchor.
+        assert_eq!(toc, "## Contents\n\n- [contents](#contents-1)\n\n");
+        let file = section("contents", Body::Content("x"), &lines);
+        assert_eq!(
+            render(&mut md, &file),
+            "<a id=\"contents-1\"></a>\n## contents\n\n```\nx\n```\n\n"
+        );
+    }
+}
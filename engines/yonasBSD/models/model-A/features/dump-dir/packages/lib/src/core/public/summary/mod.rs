@@ -0,0 +1,249 @@
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{enums::SummaryFormat, utils::tokens};
+
+/// Key used in [`DumpSummary::extensions`] for files without an extension.
+pub const NO_EXTENSION: &str = "(none)";
+
+/// Totals for a dump run. Accumulated by the printer; serializable so embedders
+/// and CI jobs get the same numbers as the human summary line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpSummary {
+    /// Files whose content was printed (duplicates excluded).
+    pub file_count: usize,
+    /// Lines printed across all files.
+    pub line_count: usize,
+    /// Bytes of file content printed across all files.
+    pub byte_count: u64,
+    /// Files skipped because they could not be opened.
+    pub skipped_unreadable: usize,
+    /// Files elided by `dedupe_content`.
+    pub duplicates_elided: usize,
+    /// Lines not printed thanks to `dedupe_content`.
+    pub lines_saved: usize,
+    /// Lines cut by `max_line_length`.
+    pub lines_truncated: usize,
+    /// Files not printed because the total output budget was reached.
+    pub files_omitted: usize,
+    /// Files whose content came from `cache_dir` instead of being read.
+    #[serde(default)]
+    pub cache_hits: usize,
+    /// Files left out because an earlier root or link already reached them.
+    #[serde(default)]
+    pub duplicate_paths: usize,
+    /// Estimated LLM tokens in the output, prompt text included. Only present
+    /// when token counting was requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+    /// The `changed_since` cutoff that was applied, as an RFC 3339 UTC timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_since: Option<String>,
+    /// The `changed_before` cutoff that was applied, as an RFC 3339 UTC timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_before: Option<String>,
+    /// Wall-clock time from printer creation to the summary.
+    pub elapsed_ms: u64,
+    /// Per-extension breakdown, keyed by lowercase extension.
+    pub extensions: BTreeMap<String, ExtensionSummary>,
+}
+
+/// Per-extension totals within a [`DumpSummary`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionSummary {
+    pub file_count: usize,
+    pub line_count: usize,
+    pub byte_count: u64,
+}
+
+impl DumpSummary {
+    /// Count a printed file towards the totals and its extension's breakdown.
+    pub fn record_file(&mut self, path: &Path, lines: usize, bytes: u64) {
+        self.file_count += 1;
+        self.line_count += lines;
+        self.byte_count += bytes;
+
+        let ext = path.extension().map_or_else(
+            || NO_EXTENSION.to_string(),
+            |e| e.to_string_lossy().to_lowercase(),
+        );
+        let entry = self.extensions.entry(ext).or_default();
+        entry.file_count += 1;
+        entry.line_count += lines;
+        entry.byte_count += bytes;
+    }
+
+    /// Compact single-line JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("DumpSummary serializes to JSON")
+    }
+
+    /// Render for the terminal: the dimmed human line, or compact JSON.
+    pub fn render(&self, format: SummaryFormat) -> String {
+        match format {
+            SummaryFormat::Text => self.to_string().dimmed().to_string(),
+            SummaryFormat::Json => self.to_json(),
+        }
+    }
+}
+
+/// The human-readable summary line.
+impl fmt::Display for DumpSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "── Summary: {} file{}, {} line{}",
+            self.file_count,
+            plural(self.file_count),
+            self.line_count,
+            plural(self.line_count),
+        )?;
+        if self.duplicates_elided > 0 {
+            write!(
+                f,
+                ", {} duplicate{} elided ({} line{} saved)",
+                self.duplicates_elided,
+                plural(self.duplicates_elided),
+                self.lines_saved,
+                plural(self.lines_saved),
+            )?;
+        }
+        if self.lines_truncated > 0 {
+            write!(
+                f,
+                ", {} line{} truncated",
+                self.lines_truncated,
+                plural(self.lines_truncated),
+            )?;
+        }
+        if self.skipped_unreadable > 0 {
+            write!(f, ", {} unreadable skipped", self.skipped_unreadable)?;
+        }
+        if self.files_omitted > 0 {
+            write!(
+                f,
+                ", {} file{} omitted (budget)",
+                self.files_omitted,
+                plural(self.files_omitted),
+            )?;
+        }
+        if self.cache_hits > 0 {
+            write!(f, ", {} from cache", self.cache_hits)?;
+        }
+        if self.duplicate_paths > 0 {
+            write!(
+                f,
+                ", {} duplicate path{} skipped",
+                self.duplicate_paths,
+                plural(self.duplicate_paths),
+            )?;
+        }
+        if let Some(n) = self.tokens {
+            write!(f, "\n── Tokens: ~{n} ({})", tokens::METHOD)?;
+        }
+        match (&self.changed_since, &self.changed_before) {
+            (Some(since), Some(before)) => write!(f, "\n── Modified: {since} to {before}")?,
+            (Some(since), None) => write!(f, "\n── Modified: since {since}")?,
+            (None, Some(before)) => write!(f, "\n── Modified: before {before}")?,
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_file_updates_totals_and_extensions() {
+        let mut summary = DumpSummary::default();
+        summary.record_file(Path::new("src/main.rs"), 10, 200);
+        summary.record_file(Path::new("src/lib.RS"), 5, 100);
+        summary.record_file(Path::new("Makefile"), 2, 20);
+
+        assert_eq!(summary.file_count, 3);
+        assert_eq!(summary.line_count, 17);
+        assert_eq!(summary.byte_count, 320);
+        assert_eq!(summary.extensions["rs"], ExtensionSummary {
+            file_count: 2,
+            line_count: 15,
+            byte_count: 300,
+        });
+        assert_eq!(summary.extensions[NO_EXTENSION].file_count, 1);
+    }
+
+    #[test]
+    fn display_matches_human_summary_line() {
+        let mut summary = DumpSummary::default();
+        summary.record_file(Path::new("a.txt"), 1, 4);
+        assert_eq!(summary.to_string(), "── Summary: 1 file, 1 line");
+
+        summary.skipped_unreadable = 2;
+        summary.lines_truncated = 3;
+        assert_eq!(
+            summary.to_string(),
+            "── Summary: 1 file, 1 line, 3 lines truncated, 2 unreadable skipped"
+        );
+
+        summary.cache_hits = 1;
+        assert!(summary.to_string().ends_with(", 1 from cache"));
+    }
+
+    #[test]
+    fn modified_line_names_the_cutoffs() {
+        let mut summary = DumpSummary::default();
+        assert!(!summary.to_string().contains("Modified"));
+        assert!(!summary.to_json().contains("changed_since"));
+
+        summary.changed_since = Some("2024-03-01T00:00:00Z".into());
+        assert!(
+            summary
+                .to_string()
+                .ends_with("\n── Modified: since 2024-03-01T00:00:00Z")
+        );
+        summary.changed_before = Some("2024-03-08T00:00:00Z".into());
+        assert!(
+            summary
+                .to_string()
+                .ends_with("\n── Modified: 2024-03-01T00:00:00Z to 2024-03-08T00:00:00Z")
+        );
+        assert!(
+            summary
+                .to_json()
+                .contains(r#""changed_before":"2024-03-08T00:00:00Z""#)
+        );
+    }
+
+    #[test]
+    fn token_line_only_when_counted() {
+        let mut summary = DumpSummary::default();
+        assert!(!summary.to_string().contains("Tokens"));
+        assert!(!summary.to_json().contains("tokens"));
+
+        summary.tokens = Some(42);
+        assert!(
+            summary
+                .to_string()
+                .ends_with(&format!("\n── Tokens: ~42 ({})", tokens::METHOD))
+        );
+        assert!(summary.to_json().contains(r#""tokens":42"#));
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let mut summary = DumpSummary::default();
+        summary.record_file(Path::new("a.txt"), 1, 4);
+        summary.elapsed_ms = 12;
+        let json = summary.to_json();
+        assert!(!json.contains('\n'));
+        let back: DumpSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, summary);
+    }
+}
@@ -1,64 +1,766 @@
 use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    fmt, fs,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex, mpsc},
+    time::SystemTime,
 };
 
-use ignore::{DirEntry, WalkBuilder};
+use ignore::{
+    DirEntry, Match, Walk, WalkBuilder, WalkState,
+    gitignore::{Gitignore, GitignoreBuilder, Glob},
+};
 use snafu::ResultExt;
 
 use crate::{
+    enums::{Order, SkipReason, Symlinks},
     errors::{DumpResult, WalkSnafu},
-    filter::Filter,
+    filter::{Filter, FilterDecision, FilterStats},
 };
 
-/// Collect all files under `root` that pass the filter, in sorted order.
+/// Per-directory ignore file with gitignore syntax, for dump-only exclusions.
+/// Its rules override `.gitignore` where both apply.
+pub const DUMPIGNORE_FILENAME: &str = ".dumpignore";
+
+/// Collect all files under `root` that pass the filter, in walk order: sorted by
+/// name within each directory, depth-first. A thin wrapper over [`walk_files`],
+/// unless the filter asks for more than one walk thread (`threads`): then the
+/// walk runs in parallel and the files are sorted afterwards, which gives the
+/// same order.
 pub fn collect_files(root: &Path, filter: Arc<Filter>) -> DumpResult<Vec<PathBuf>> {
-    let mut files: Vec<PathBuf> = Vec::new();
+    match filter.threads() {
+        1 => walk_files(root, filter).collect(),
+        threads => collect_parallel(root, &filter, threads).map(|(files, _)| files),
+    }
+}
 
-    let filter_dir = Arc::clone(&filter);
+/// [`collect_files`], also returning the [`SortKey`] of each file when the
+/// filter's order sorts by metadata, for [`sort_files`].
+pub fn collect_files_with_keys(
+    root: &Path,
+    filter: Arc<Filter>,
+) -> DumpResult<(Vec<PathBuf>, SortKeys)> {
+    match filter.threads() {
+        1 => walk_report(root, filter, |_| {}).map(|walk| (walk.files, walk.keys)),
+        threads => collect_parallel(root, &filter, threads),
+    }
+}
 
-    let walker = WalkBuilder::new(root)
-        .git_ignore(true)
-        .git_global(true)
-        .git_exclude(true)
-        .hidden(false)
-        .follow_links(false)
-        .sort_by_file_name(|a, b| a.cmp(b))
+/// A walked file's size and modification time, read from its `DirEntry` while
+/// the walk has it, so sorting by them does not stat the file again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortKey {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl From<&fs::Metadata> for SortKey {
+    fn from(metadata: &fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// The [`SortKey`] of each file, by path.
+pub type SortKeys = HashMap<PathBuf, SortKey>;
+
+/// [`collect_files_with_keys`] on `threads` walker threads. Workers filter files as they
+/// go and send the survivors over a channel. Each directory's filter (its
+/// parent's, plus a nested `dump.toml`) is recorded when the directory is let
+/// through, which is always before its entries are visited.
+fn collect_parallel(
+    root: &Path,
+    filter: &Filter,
+    threads: usize,
+) -> DumpResult<(Vec<PathBuf>, SortKeys)> {
+    let filter = Arc::new(filter.for_root(root));
+    let dirs = Arc::new(Mutex::new(HashMap::from([(
+        root.to_path_buf(),
+        Arc::clone(&filter),
+    )])));
+    let filter_of = {
+        let dirs = Arc::clone(&dirs);
+        let filter = Arc::clone(&filter);
+        move |dir: Option<&Path>| -> Arc<Filter> {
+            dir.and_then(|dir| dirs.lock().ok()?.get(dir).cloned())
+                .unwrap_or_else(|| Arc::clone(&filter))
+        }
+    };
+    let filter_dir = filter_of.clone();
+
+    let symlinks = filter.symlinks();
+    let (tx, rx) = mpsc::channel();
+    walk_builder(root, &filter)
+        .threads(threads)
         .filter_entry(move |entry: &DirEntry| {
-            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-                if entry.depth() == 0 {
-                    return true;
-                }
-                !filter_dir.should_skip_dir(entry.path())
-            } else {
-                true
+            if entry.depth() == 0 || !entry.file_type().is_some_and(|t| t.is_dir()) {
+                return true;
+            }
+            let current = filter_dir(entry.path().parent());
+            if current.dir_skip_reason(entry.path()).is_some() {
+                return false;
             }
+            let own = current.nested(entry.path()).map_or(current, Arc::new);
+            if let Ok(mut dirs) = dirs.lock() {
+                dirs.insert(entry.path().to_path_buf(), own);
+            }
+            true
         })
-        .build();
-
-    for result in walker {
-        match result {
-            Ok(entry) => {
-                if entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
-                    let path = entry.into_path();
-                    if !filter.should_skip(&path) {
-                        files.push(path);
+        .build_parallel()
+        .run(|| {
+            let tx = tx.clone();
+            let filter_of = filter_of.clone();
+            Box::new(move |entry| match entry {
+                Ok(entry) => {
+                    if is_file_entry(&entry, symlinks)
+                        && let Ok(key) = visit_file(&filter_of(entry.path().parent()), &entry)
+                    {
+                        let _ = tx.send(Ok((entry.into_path(), key)));
                     }
+                    WalkState::Continue
+                },
+                Err(e) if is_soft_error(&e) => {
+                    crate::warning!("{e}");
+                    WalkState::Continue
+                },
+                Err(e) => {
+                    let _ = tx.send(Err(e));
+                    WalkState::Quit
+                },
+            })
+        });
+    drop(tx);
+
+    let mut files = Vec::new();
+    let mut keys = SortKeys::new();
+    for found in rx {
+        let (path, key) = found.context(WalkSnafu)?;
+        if let Some(key) = key {
+            keys.insert(path.clone(), key);
+        }
+        files.push(path);
+    }
+    files.sort();
+    Ok((files, keys))
+}
+
+/// Lazily yield the files under `root` that pass the filter, so callers can
+/// process them as the walk goes. Paths come in walk order (by name within a
+/// directory, depth-first) with no global sort; collect and use [`sort_files`]
+/// for another order. A soft walk error is warned about and skipped; a hard one
+/// is yielded as an `Err`.
+pub fn walk_files(root: &Path, filter: Arc<Filter>) -> impl Iterator<Item = DumpResult<PathBuf>> {
+    let pruned = Arc::new(Mutex::new(FilterStats::default()));
+    FilteredWalk::new(root, filter, pruned).filter_map(|entry| match entry {
+        Ok(Visit::File(path, Ok(_))) => Some(Ok(path)),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    })
+}
+
+/// Like [`collect_files`], also recording what the filter and the ignore files
+/// excluded and why.
+pub fn collect_files_with_stats(
+    root: &Path,
+    filter: Arc<Filter>,
+) -> DumpResult<(Vec<PathBuf>, FilterStats)> {
+    let mut walk = walk_report(root, Arc::clone(&filter), |_| {})?;
+    record_ignored(root, &filter, &mut walk);
+    Ok((walk.files, walk.stats))
+}
+
+/// What one serial walk over a root saw, for callers that report on more than
+/// the files it found.
+#[derive(Debug, Default)]
+pub struct WalkReport {
+    /// The files that pass the filter, in walk order.
+    pub files: Vec<PathBuf>,
+    /// Every directory the walk descended into, `root` first, in walk order.
+    /// Pruned directories are never visited.
+    pub dirs: Vec<PathBuf>,
+    /// What the filter excluded and why. What ignore files left out is only
+    /// added by [`record_ignored`].
+    pub stats: FilterStats,
+    /// The files' sort keys, when the filter's order sorts by metadata.
+    pub keys: SortKeys,
+}
+
+/// Walk `root` once, in walk order, keeping its directories and what the filter
+/// excluded along with the files. `on_file` sees each file that passes as soon
+/// as it is found, e.g. to drive a progress display.
+pub fn walk_report(
+    root: &Path,
+    filter: Arc<Filter>,
+    mut on_file: impl FnMut(&Path),
+) -> DumpResult<WalkReport> {
+    let mut walk = WalkReport::default();
+    // `filter_entry` must be `Fn + Send + Sync`, so pruned-dir counts go through a lock.
+    let pruned = Arc::new(Mutex::new(FilterStats::default()));
+    for entry in FilteredWalk::new(root, filter, Arc::clone(&pruned)) {
+        match entry? {
+            Visit::Dir(path) => walk.dirs.push(path),
+            Visit::File(path, Err(reason)) => walk.stats.record_file(&path, reason),
+            Visit::File(path, Ok(key)) => {
+                on_file(&path);
+                if let Some(key) = key {
+                    walk.keys.insert(path.clone(), key);
                 }
+                walk.files.push(path);
             },
-            Err(e) => {
-                // Log a warning for soft walk errors but don't abort.
-                // Only hard errors (e.g. permission denied on root) warrant propagation.
-                if e.io_error().map(|io| io.kind()) == Some(std::io::ErrorKind::PermissionDenied) {
-                    eprintln!("Warning: {e}");
+        }
+    }
+    if let Ok(pruned) = pruned.lock() {
+        walk.stats.merge(&pruned);
+    }
+    Ok(walk)
+}
+
+/// Add the entries ignore files kept out of `walk` to its stats, each under the
+/// kind of ignore file that matched it. Only the directories `walk` descended
+/// into are listed again, so an ignored directory is recorded as pruned and
+/// nothing below it is visited.
+pub fn record_ignored(root: &Path, filter: &Filter, walk: &mut WalkReport) {
+    let mut seen: HashSet<&Path> = walk.dirs.iter().map(PathBuf::as_path).collect();
+    seen.extend(walk.files.iter().map(PathBuf::as_path));
+    seen.extend(walk.stats.skipped.iter().map(|s| s.path.as_path()));
+
+    let mut rules = IgnoreRules::new(root, filter);
+    let mut ignored = FilterStats::default();
+    for dir in &walk.dirs {
+        // The walk lists a directory at the depth limit but never reads it.
+        let depth = dir
+            .strip_prefix(root)
+            .map_or(0, |rel| rel.components().count());
+        if filter.max_depth().is_some_and(|max| depth >= max) {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if seen.contains(path.as_path()) {
+                continue;
+            }
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            if let Match::Ignore(rule) = rules.matched(&path, is_dir) {
+                if is_dir {
+                    ignored.record_dir(&path, rule.reason);
                 } else {
-                    return Err(e).context(WalkSnafu);
+                    ignored.record_file(&path, rule.reason);
                 }
+            }
+        }
+    }
+    walk.stats.merge(&ignored);
+}
+
+/// The directories among `dirs` (as [`walk_report`] lists them for `root`) that
+/// contribute none of `files` to the dump: every file beneath them was filtered
+/// out or dropped later, or there was none. Only the outermost such directory
+/// is listed, not those nested in it, in walk order. Pruned directories are not
+/// visited, so are not listed (`--show-skipped` reports them); neither is
+/// `root` itself.
+pub fn empty_dirs(root: &Path, dirs: &[PathBuf], files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut contributing = HashSet::new();
+    for file in files {
+        for dir in file.ancestors().skip(1) {
+            if !contributing.insert(dir) {
+                break;
+            }
+        }
+    }
+
+    let mut empty: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        if dir == root
+            || contributing.contains(dir.as_path())
+            || empty.last().is_some_and(|outer| dir.starts_with(outer))
+        {
+            continue;
+        }
+        empty.push(dir.clone());
+    }
+    empty
+}
+
+/// One entry of a [`FilteredWalk`].
+enum Visit {
+    /// A directory the walk descends into (pruned ones never show up).
+    Dir(PathBuf),
+    /// A file, with the rule that excludes it or else its sort key, if the
+    /// order needs one.
+    File(PathBuf, Result<Option<SortKey>, SkipReason>),
+}
+
+/// The filter in effect at each depth: the root's, then one per nested
+/// `dump.toml` above the current entry.
+type Layers = Arc<Mutex<Vec<(usize, Arc<Filter>)>>>;
+
+/// The walk shared by [`walk_files`] and [`walk_report`]: every directory and file that survives
+/// ignore files and directory pruning, each file with the rule that excludes it, if any. Pruned
+/// directories are recorded in `pruned`.
+struct FilteredWalk {
+    walk: Walk,
+    layers: Layers,
+    filter: Arc<Filter>,
+}
+
+impl FilteredWalk {
+    fn new(root: &Path, filter: Arc<Filter>, pruned: Arc<Mutex<FilterStats>>) -> Self {
+        let filter = Arc::new(filter.for_root(root));
+        // `filter_entry` sees entries in walk order, so the stack matches the
+        // entry `next` receives next.
+        let layers: Layers = Arc::new(Mutex::new(vec![(0, Arc::clone(&filter))]));
+        let layers_dir = Arc::clone(&layers);
+
+        let walk = walk_builder(root, &filter)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry: &DirEntry| {
+                let Ok(mut layers) = layers_dir.lock() else {
+                    return true;
+                };
+                while layers.len() > 1 && layers.last().is_some_and(|(d, _)| *d >= entry.depth()) {
+                    layers.pop();
+                }
+                if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                    if entry.depth() == 0 {
+                        return true;
+                    }
+                    let current = Arc::clone(&layers[layers.len() - 1].1);
+                    match current.dir_skip_reason(entry.path()) {
+                        Some(reason) => {
+                            if let Ok(mut stats) = pruned.lock() {
+                                stats.record_dir(entry.path(), reason);
+                            }
+                            false
+                        },
+                        None => {
+                            if let Some(nested) = current.nested(entry.path()) {
+                                layers.push((entry.depth(), Arc::new(nested)));
+                            }
+                            true
+                        },
+                    }
+                } else {
+                    true
+                }
+            })
+            .build();
+
+        Self {
+            walk,
+            layers,
+            filter,
+        }
+    }
+}
+
+impl Iterator for FilteredWalk {
+    type Item = DumpResult<Visit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.walk.next()? {
+                Ok(entry) => {
+                    if entry.file_type().is_some_and(|t| t.is_dir()) {
+                        return Some(Ok(Visit::Dir(entry.into_path())));
+                    }
+                    if !is_file_entry(&entry, self.filter.symlinks()) {
+                        continue;
+                    }
+                    let current = match self.layers.lock() {
+                        Ok(layers) => Arc::clone(&layers[layers.len() - 1].1),
+                        Err(_) => Arc::clone(&self.filter),
+                    };
+                    let verdict = visit_file(&current, &entry);
+                    return Some(Ok(Visit::File(entry.into_path(), verdict)));
+                },
+                Err(e) => {
+                    // Log a warning for soft walk errors (an unreadable entry, a link
+                    // cycle or a dangling link when following) but don't abort.
+                    if is_soft_error(&e) {
+                        crate::warning!("{e}");
+                    } else {
+                        return Some(Err(e).context(WalkSnafu));
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// The walk over `root` with the filter's ignore-file, symlink and depth
+/// settings; ordering and directory pruning are up to the caller.
+fn walk_builder(root: &Path, filter: &Filter) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    if filter.respects_dumpignore() {
+        builder.add_custom_ignore_filename(DUMPIGNORE_FILENAME);
+    }
+    for name in filter.custom_ignore_files() {
+        builder.add_custom_ignore_filename(name);
+    }
+    let git = filter.respects_gitignore();
+    builder
+        .ignore(true)
+        .git_ignore(git)
+        .git_global(git)
+        .git_exclude(git)
+        .hidden(false)
+        .follow_links(filter.symlinks() == Symlinks::Follow)
+        .max_depth(filter.max_depth());
+    builder
+}
+
+/// The rule that keeps the file `entry` out of the dump, or else its sort key
+/// when the filter's order needs one. The modification time is only looked at
+/// when nothing else decides, and the metadata is read at most once for both.
+fn visit_file(filter: &Filter, entry: &DirEntry) -> Result<Option<SortKey>, SkipReason> {
+    if let Some(reason) = filter.walked_skip_reason(entry.path(), entry.path_is_symlink()) {
+        return Err(reason);
+    }
+    let cell = OnceCell::new();
+    let metadata = || cell.get_or_init(|| entry.metadata().ok()).as_ref();
+    if !filter.always_includes(entry.path())
+        && let Some(reason) = filter.mtime_skip_reason(|| metadata()?.modified().ok())
+    {
+        return Err(reason);
+    }
+    Ok(filter
+        .order()
+        .needs_metadata()
+        .then(|| metadata().map(SortKey::from))
+        .flatten())
+}
+
+/// A file the walk hands to the filter: a regular file, or (unless links are
+/// followed, when they resolve to their targets) a symlink of any kind.
+fn is_file_entry(entry: &DirEntry, symlinks: Symlinks) -> bool {
+    entry
+        .file_type()
+        .is_some_and(|t| t.is_file() || (t.is_symlink() && symlinks != Symlinks::Follow))
+}
+
+/// Walk errors worth a warning rather than aborting the dump.
+fn is_soft_error(e: &ignore::Error) -> bool {
+    match e {
+        ignore::Error::Loop {
+            ..
+        } => true,
+        ignore::Error::WithPath {
+            err, ..
+        }
+        | ignore::Error::WithDepth {
+            err, ..
+        }
+        | ignore::Error::WithLineNumber {
+            err, ..
+        } => is_soft_error(err),
+        _ => matches!(
+            e.io_error().map(|io| io.kind()),
+            Some(std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::NotFound)
+        ),
+    }
+}
+
+/// An ignore-file rule that keeps a path out of the walk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreMatch {
+    /// The rule as written, e.g. `target/`.
+    pub pattern: String,
+    /// The ignore file the rule came from.
+    pub file: Option<PathBuf>,
+    /// 1-based line of the rule in `file`, when it could be found.
+    pub line: Option<usize>,
+    /// The kind of ignore file the rule came from.
+    pub reason: SkipReason,
+}
+
+/// `target/ in .gitignore line 3`; the file is shown relative to the cwd when
+/// it lies below it.
+impl fmt::Display for IgnoreMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)?;
+        if let Some(file) = &self.file {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let shown = file.strip_prefix(&cwd).unwrap_or(file);
+            write!(f, " in {}", shown.display())?;
+        }
+        if let Some(line) = self.line {
+            write!(f, " line {line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl IgnoreMatch {
+    fn from_glob(glob: &Glob, reason: SkipReason) -> Self {
+        let file = glob.from().map(Path::to_path_buf);
+        let line = file.as_deref().and_then(|file| {
+            let text = std::fs::read_to_string(file).ok()?;
+            // The last copy of a rule is the one that wins.
+            text.lines()
+                .enumerate()
+                .filter(|(_, line)| line.trim_end() == glob.original())
+                .last()
+                .map(|(i, _)| i + 1)
+        });
+        Self {
+            pattern: glob.original().to_string(),
+            file,
+            line,
+            reason,
+        }
+    }
+}
+
+/// The ignore files the walk honours, read at most once per directory and
+/// resolved the way the walk does: custom ignore files (`.dumpignore` and
+/// `custom_ignore_files`) over `.ignore` over `.gitignore` over
+/// `.git/info/exclude` over the global excludes, deeper files first within each
+/// kind, and the git ones only inside the repository around the starting path.
+struct IgnoreRules {
+    /// The file names of each per-directory kind, in precedence order.
+    kinds: Vec<Vec<String>>,
+    repo: Option<PathBuf>,
+    /// `.git/info/exclude` and the global excludes, when in a repository.
+    repo_wide: Vec<Gitignore>,
+    /// Each directory's matcher per kind, `None` where it has no such file.
+    dirs: HashMap<PathBuf, Vec<Option<Gitignore>>>,
+}
+
+impl IgnoreRules {
+    /// The per-directory kind `.gitignore` files are read as.
+    const GIT: usize = 2;
+
+    fn new(path: &Path, filter: &Filter) -> Self {
+        let repo = path
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .filter(|_| filter.respects_gitignore())
+            .map(Path::to_path_buf);
+        let mut custom: Vec<String> = Vec::new();
+        if filter.respects_dumpignore() {
+            custom.push(DUMPIGNORE_FILENAME.to_string());
+        }
+        custom.extend(filter.custom_ignore_files().iter().cloned());
+        let git = if repo.is_some() {
+            vec![".gitignore".to_string()]
+        } else {
+            Vec::new()
+        };
+
+        let mut repo_wide = Vec::new();
+        if let Some(repo) = &repo {
+            let mut exclude = GitignoreBuilder::new(repo);
+            exclude.add(repo.join(".git/info/exclude"));
+            repo_wide.extend(exclude.build().ok());
+            repo_wide.push(GitignoreBuilder::new(repo).build_global().0);
+        }
+        Self {
+            kinds: vec![custom, vec![".ignore".to_string()], git],
+            repo,
+            repo_wide,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Read the ignore files in `dir`, unless already done.
+    fn load(&mut self, dir: &Path) {
+        if self.dirs.contains_key(dir) {
+            return;
+        }
+        let matchers = self
+            .kinds
+            .iter()
+            .map(|names| {
+                let mut builder = GitignoreBuilder::new(dir);
+                let mut any = false;
+                for name in names {
+                    let file = dir.join(name);
+                    if file.is_file() {
+                        builder.add(file);
+                        any = true;
+                    }
+                }
+                builder.build().ok().filter(|_| any)
+            })
+            .collect();
+        self.dirs.insert(dir.to_path_buf(), matchers);
+    }
+
+    /// The rule deciding `path` itself, ignoring whether a directory above it
+    /// is ignored.
+    fn matched(&mut self, path: &Path, is_dir: bool) -> Match<IgnoreMatch> {
+        let dirs: Vec<&Path> = path.ancestors().skip(1).collect();
+        for dir in &dirs {
+            self.load(dir);
+        }
+        let rules = &*self;
+        let dirs = &dirs;
+        let per_dir = (0..rules.kinds.len()).flat_map(|kind| {
+            dirs.iter()
+                .filter(move |dir| {
+                    kind != Self::GIT
+                        || rules
+                            .repo
+                            .as_ref()
+                            .is_some_and(|repo| dir.starts_with(repo))
+                })
+                .filter_map(move |dir| Some((kind, rules.dirs[*dir][kind].as_ref()?)))
+        });
+        let within_repo = rules
+            .repo
+            .as_ref()
+            .is_some_and(|repo| path.starts_with(repo));
+        let repo_wide = rules
+            .repo_wide
+            .iter()
+            .filter(|_| within_repo)
+            .map(|matcher| (Self::GIT, matcher));
+        for (kind, matcher) in per_dir.chain(repo_wide) {
+            let rule = |glob: &Glob| IgnoreMatch::from_glob(glob, Self::reason(kind, glob));
+            match matcher.matched(path, is_dir) {
+                Match::None => {},
+                Match::Ignore(glob) => return Match::Ignore(rule(glob)),
+                Match::Whitelist(glob) => return Match::Whitelist(rule(glob)),
+            }
+        }
+        Match::None
+    }
+
+    /// The [`SkipReason`] for a rule of the given kind: custom ignore files
+    /// share a kind, so theirs goes by the file the rule was read from.
+    fn reason(kind: usize, glob: &Glob) -> SkipReason {
+        match kind {
+            Self::GIT => SkipReason::Gitignore,
+            0 if glob
+                .from()
+                .is_some_and(|file| file.ends_with(DUMPIGNORE_FILENAME)) =>
+            {
+                SkipReason::Dumpignore
             },
+            _ => SkipReason::IgnoreFile,
         }
     }
+}
+
+/// The ignore-file rule that keeps `path` out of the walk, if any, resolved the
+/// way the walk does (see [`IgnoreRules`]). Each directory between the cwd (or
+/// the repository root) and `path` is checked first, since the walk never
+/// descends into an ignored one.
+pub fn ignore_match(path: &Path, filter: &Filter) -> Option<IgnoreMatch> {
+    let path = std::path::absolute(path).ok()?;
+    let repo = path.ancestors().find(|dir| dir.join(".git").exists());
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let base = [Some(cwd.as_path()), repo]
+        .into_iter()
+        .flatten()
+        .find(|base| path.starts_with(base) && path != *base)
+        .or(path.parent())?;
+
+    let mut rules = IgnoreRules::new(&path, filter);
+    let mut candidates: Vec<&Path> = path.ancestors().take_while(|p| *p != base).collect();
+    candidates.reverse();
+    for candidate in candidates {
+        let is_dir = candidate != path || path.is_dir();
+        match rules.matched(candidate, is_dir) {
+            Match::Ignore(rule) => return Some(rule),
+            Match::Whitelist(_) | Match::None => {},
+        }
+    }
+    None
+}
+
+/// Why the walk would keep or leave out `path`: an ignore-file rule first, as
+/// the walk never yields those, then [`Filter::check`].
+pub fn explain(path: &Path, filter: &Filter) -> FilterDecision {
+    match ignore_match(path, filter) {
+        Some(rule) => FilterDecision::Skipped {
+            reason: rule.reason,
+            rule: format!("{}: {rule}", rule.reason),
+        },
+        None => {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            filter.for_root(&cwd).check(path)
+        },
+    }
+}
+
+/// Reorder collected files. `Lexical` keeps the order they were collected in;
+/// `Breadth` lists shallower files before deeper ones, lexically within a depth.
+/// `Size` and `Mtime` go by the files' `keys` from the walk, reading the
+/// metadata of a file without one; a file whose metadata cannot be read sorts
+/// last. Every key-based order breaks ties by path. `reverse` turns the result
+/// around.
+pub fn sort_files(files: &mut [PathBuf], order: Order, reverse: bool, keys: &SortKeys) {
+    let key = |path: &PathBuf| {
+        keys.get(path)
+            .copied()
+            .or_else(|| fs::metadata(path).ok().map(|meta| SortKey::from(&meta)))
+    };
+    match order {
+        Order::Lexical => {},
+        Order::Breadth => files.sort_by(|a, b| {
+            a.components()
+                .count()
+                .cmp(&b.components().count())
+                .then_with(|| a.cmp(b))
+        }),
+        Order::Size => files.sort_by_cached_key(|path| {
+            let size = key(path).map_or(u64::MAX, |key| key.size);
+            (size, path.clone())
+        }),
+        Order::Mtime => files.sort_by_cached_key(|path| {
+            let mtime = key(path).and_then(|key| key.modified);
+            (std::cmp::Reverse(mtime), path.clone())
+        }),
+        Order::Extension => files.sort_by_cached_key(|path| {
+            let ext = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            (ext, path.clone())
+        }),
+    }
+    if reverse {
+        files.reverse();
+    }
+}
+
+/// Drop the files a previous root (or an earlier link into the same tree)
+/// already reached, recording the rest in `seen`, which callers share across
+/// roots so the first root to reach a file keeps it. Files are compared by
+/// canonical path, or as given when that fails. With [`Symlinks::List`] a link
+/// is its own entry, so only its directory is resolved. Returns how many files
+/// were dropped.
+pub fn dedupe_files(
+    files: &mut Vec<PathBuf>,
+    seen: &mut HashSet<PathBuf>,
+    symlinks: Symlinks,
+) -> usize {
+    let before = files.len();
+    files.retain(|file| seen.insert(physical_path(file, symlinks)));
+    before - files.len()
+}
 
-    Ok(files)
+/// Where `path` lives on disk, for [`dedupe_files`].
+fn physical_path(path: &Path, symlinks: Symlinks) -> PathBuf {
+    let resolved = match (symlinks, path.parent(), path.file_name()) {
+        (Symlinks::List, Some(parent), Some(name)) => {
+            // `Path::new("a.rs").parent()` is `""`, which does not canonicalize.
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            parent.canonicalize().map(|dir| dir.join(name))
+        },
+        _ => path.canonicalize(),
+    };
+    resolved.unwrap_or_else(|_| path.to_path_buf())
 }
 
 #[cfg(test)]
@@ -80,6 +782,7 @@ mod tests {
                 skip_globs: vec![],
                 skip_binary: false,
                 skip_hidden: false,
+                ..AppConfig::default()
             })
             .unwrap(),
         )
@@ -133,6 +836,116 @@ mod tests {
         assert_eq!(names, sorted);
     }
 
+    fn relative(dir: &TempDir, files: &[PathBuf]) -> Vec<String> {
+        files
+            .iter()
+            .map(|p| {
+                p.strip_prefix(dir.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lexical_order_is_depth_first() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["b.rs", "a/deep/x.rs", "a/y.rs", "c.rs"]);
+        let mut files = collect_files(dir.path(), bare_filter()).unwrap();
+        sort_files(&mut files, Order::Lexical, false, &SortKeys::new());
+        assert_eq!(relative(&dir, &files), vec![
+            "a/deep/x.rs",
+            "a/y.rs",
+            "b.rs",
+            "c.rs"
+        ]);
+    }
+
+    #[test]
+    fn breadth_order_lists_shallow_files_first() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["b.rs", "a/deep/x.rs", "a/y.rs", "c.rs", "z/w.rs"]);
+        let mut files = collect_files(dir.path(), bare_filter()).unwrap();
+        sort_files(&mut files, Order::Breadth, false, &SortKeys::new());
+        assert_eq!(relative(&dir, &files), vec![
+            "b.rs",
+            "c.rs",
+            "a/y.rs",
+            "z/w.rs",
+            "a/deep/x.rs"
+        ]);
+    }
+
+    #[test]
+    fn size_mtime_and_extension_orders_break_ties_by_path() {
+        let dir = TempDir::new().unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, content, age) in [
+            ("b.rs", "22", 30),
+            ("a.toml", "333", 10),
+            ("c.rs", "1", 20),
+            ("Makefile", "22", 20),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            let mtime = now - std::time::Duration::from_secs(age);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+        let sorted = |order, reverse| {
+            let mut files = collect_files(dir.path(), bare_filter()).unwrap();
+            sort_files(&mut files, order, reverse, &SortKeys::new());
+            relative(&dir, &files)
+        };
+
+        assert_eq!(sorted(Order::Size, false), [
+            "c.rs", "Makefile", "b.rs", "a.toml"
+        ]);
+        assert_eq!(sorted(Order::Size, true), [
+            "a.toml", "b.rs", "Makefile", "c.rs"
+        ]);
+        assert_eq!(sorted(Order::Mtime, false), [
+            "a.toml", "Makefile", "c.rs", "b.rs"
+        ]);
+        assert_eq!(sorted(Order::Extension, false), [
+            "Makefile", "b.rs", "c.rs", "a.toml"
+        ]);
+        assert_eq!(sorted(Order::Lexical, true), [
+            "c.rs", "b.rs", "a.toml", "Makefile"
+        ]);
+    }
+
+    #[test]
+    fn size_order_uses_the_metadata_read_during_the_walk() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["a.rs", "b.rs", "c.rs"]);
+        fs::write(dir.path().join("a.rs"), "333").unwrap();
+        fs::write(dir.path().join("b.rs"), "1").unwrap();
+        fs::write(dir.path().join("c.rs"), "22").unwrap();
+        for threads in [1, 2] {
+            let filter = Filter::builder()
+                .order(Order::Size)
+                .threads(threads)
+                .build()
+                .unwrap();
+            let (mut files, keys) = collect_files_with_keys(dir.path(), Arc::new(filter)).unwrap();
+            assert_eq!(keys.len(), 3);
+            // Growing a file after the walk does not reorder it.
+            fs::write(dir.path().join("b.rs"), "4444").unwrap();
+            sort_files(&mut files, Order::Size, false, &keys);
+            assert_eq!(relative(&dir, &files), ["b.rs", "c.rs", "a.rs"]);
+            fs::write(dir.path().join("b.rs"), "1").unwrap();
+        }
+
+        let (_, keys) = collect_files_with_keys(dir.path(), bare_filter()).unwrap();
+        assert!(keys.is_empty());
+    }
+
     #[test]
     fn returns_empty_for_empty_dir() {
         let dir = TempDir::new().unwrap();
@@ -162,6 +975,7 @@ mod tests {
             skip_filenames: vec![],
             skip_path_components: vec![],
             skip_globs: vec![],
+            ..AppConfig::default()
         });
         let files = collect_files(dir.path(), filter).unwrap();
         assert_eq!(filenames(&files), vec!["main.rs"]);
@@ -179,11 +993,34 @@ mod tests {
             skip_patterns: vec![],
             skip_filenames: vec![],
             skip_path_components: vec![],
+            ..AppConfig::default()
         });
         let files = collect_files(dir.path(), filter).unwrap();
         assert_eq!(filenames(&files), vec!["main.rs"]);
     }
 
+    #[test]
+    fn negated_glob_reaches_files_under_skipped_dir() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "src/main.rs",
+            "target/debug/dump-dir",
+            "target/doc/index.html",
+        ]);
+        let filter = arc_filter(AppConfig {
+            skip_globs: vec!["**/target/**".into(), "!**/target/doc/**".into()],
+            skip_binary: false,
+            skip_hidden: false,
+            skip_extensions: vec![],
+            skip_patterns: vec![],
+            skip_filenames: vec![],
+            skip_path_components: vec![],
+            ..AppConfig::default()
+        });
+        let files = collect_files(dir.path(), filter).unwrap();
+        assert_eq!(filenames(&files), vec!["index.html", "main.rs"]);
+    }
+
     #[test]
     fn hidden_files_excluded_when_skip_hidden_true() {
         let dir = TempDir::new().unwrap();
@@ -196,11 +1033,169 @@ mod tests {
             skip_filenames: vec![],
             skip_path_components: vec![],
             skip_globs: vec![],
+            ..AppConfig::default()
         });
         let files = collect_files(dir.path(), filter).unwrap();
         assert_eq!(filenames(&files), vec!["main.rs"]);
     }
 
+    #[test]
+    fn stats_count_skips_by_reason() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "src/main.rs",
+            "Cargo.lock",
+            "yarn.lock",
+            "LICENSE",
+            "src/app.min.js",
+            "node_modules/pkg/index.js",
+            "node_modules/other/index.js",
+        ]);
+        fs::write(dir.path().join("blob.bin"), [0u8, 1, 2, 0, 3]).unwrap();
+        let filter = arc_filter(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            skip_filenames: vec!["license".into()],
+            skip_path_components: vec!["node_modules".into()],
+            skip_globs: vec!["**/*.min.js".into()],
+            skip_binary: true,
+            skip_hidden: false,
+            skip_patterns: vec![],
+            ..AppConfig::default()
+        });
+
+        let (files, stats) = collect_files_with_stats(dir.path(), filter).unwrap();
+
+        assert_eq!(filenames(&files), vec!["main.rs"]);
+        assert_eq!(stats.files_skipped[&SkipReason::Extension], 2);
+        assert_eq!(stats.files_skipped[&SkipReason::Filename], 1);
+        assert_eq!(stats.files_skipped[&SkipReason::Glob], 1);
+        assert_eq!(stats.files_skipped[&SkipReason::Binary], 1);
+        assert_eq!(stats.total_files_skipped(), 5);
+        // node_modules is pruned once; its files are never visited.
+        assert_eq!(stats.dirs_pruned[&SkipReason::PathComponent], 1);
+        assert!(!stats.files_skipped.contains_key(&SkipReason::PathComponent));
+        assert_eq!(stats.skipped.len(), 6);
+        assert!(stats.skipped.iter().any(|s| s.is_dir
+            && s.reason == SkipReason::PathComponent
+            && s.path.ends_with("node_modules")));
+    }
+
+    #[test]
+    fn empty_dirs_lists_the_outermost_directory_without_dumped_files() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "src/main.rs",
+            "src/gen/parser.lock",
+            "locks/Cargo.lock",
+            "locks/nested/yarn.lock",
+            "node_modules/pkg/index.js",
+        ]);
+        fs::create_dir(dir.path().join("blank")).unwrap();
+        let filter = arc_filter(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            skip_path_components: vec!["node_modules".into()],
+            ..AppConfig::default()
+        });
+
+        let walk = walk_report(dir.path(), filter, |_| {}).unwrap();
+        let empty = |files: &[PathBuf]| -> Vec<_> {
+            empty_dirs(dir.path(), &walk.dirs, files)
+                .iter()
+                .map(|p| p.strip_prefix(dir.path()).unwrap().to_path_buf())
+                .collect()
+        };
+
+        // locks/nested is inside locks; node_modules is pruned, not visited.
+        assert_eq!(empty(&walk.files), vec![
+            PathBuf::from("blank"),
+            PathBuf::from("locks"),
+            PathBuf::from("src/gen"),
+        ]);
+        // Files dropped after the walk (by `--changed`, say) leave their
+        // directories empty too.
+        assert_eq!(empty(&[]), vec![
+            PathBuf::from("blank"),
+            PathBuf::from("locks"),
+            PathBuf::from("src"),
+        ]);
+    }
+
+    #[test]
+    fn skip_path_components_ignore_the_roots_own_ancestors() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "node_modules/proj/src/main.rs",
+            "node_modules/proj/web/node_modules/pkg/index.js",
+        ]);
+        let root = dir.path().join("node_modules/proj");
+        let filter = arc_filter(AppConfig {
+            skip_path_components: vec!["node_modules".into()],
+            ..AppConfig::default()
+        });
+
+        let files = collect_files(&root, filter).unwrap();
+        assert_eq!(filenames(&files), vec!["main.rs"]);
+    }
+
+    #[test]
+    fn stats_record_ignored_paths_by_ignore_file() {
+        let dir = TempDir::new().unwrap();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(dir.path())
+            .output()
+            .ok();
+        make_files(&dir, &[
+            "src/main.rs",
+            "a.log",
+            "out/x.rs",
+            "out/y.rs",
+            "src/notes.md",
+            "draft.txt",
+        ]);
+        fs::write(dir.path().join(".gitignore"), "*.log\nout/\n").unwrap();
+        fs::write(dir.path().join("src/.dumpignore"), "*.md\n").unwrap();
+        fs::write(dir.path().join(".ignore"), "draft.txt\n").unwrap();
+        let filter = arc_filter(AppConfig {
+            skip_path_components: vec![".git".into()],
+            ..AppConfig::default()
+        });
+
+        let (files, stats) = collect_files_with_stats(dir.path(), filter).unwrap();
+
+        assert_eq!(filenames(&files), vec!["main.rs"]);
+        // An ignored directory is pruned, not walked for its files.
+        assert_eq!(stats.files_skipped[&SkipReason::Gitignore], 1);
+        assert_eq!(stats.dirs_pruned[&SkipReason::Gitignore], 1);
+        assert_eq!(stats.files_skipped[&SkipReason::Dumpignore], 1);
+        assert_eq!(stats.files_skipped[&SkipReason::IgnoreFile], 1);
+        let reason_of = |name: &str| {
+            stats
+                .skipped
+                .iter()
+                .find(|s| s.path.ends_with(name))
+                .map(|s| (s.reason, s.is_dir))
+        };
+        assert_eq!(reason_of("out"), Some((SkipReason::Gitignore, true)));
+        assert_eq!(reason_of("notes.md"), Some((SkipReason::Dumpignore, false)));
+        assert_eq!(
+            reason_of("draft.txt"),
+            Some((SkipReason::IgnoreFile, false))
+        );
+    }
+
+    #[test]
+    fn include_globs_reach_nested_files() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["a/b/c/deep.rs", "a/top.toml", "x.rs"]);
+        let filter = arc_filter(AppConfig {
+            include_globs: vec!["**/*.rs".into()],
+            ..AppConfig::default()
+        });
+        let files = collect_files(dir.path(), filter).unwrap();
+        assert_eq!(filenames(&files), vec!["deep.rs", "x.rs"]);
+    }
+
     #[test]
     fn respects_gitignore() {
         let dir = TempDir::new().unwrap();
@@ -216,4 +1211,335 @@ mod tests {
         assert!(!names.contains(&"ignored.log".to_string()));
         assert!(names.contains(&"main.rs".to_string()));
     }
+
+    #[test]
+    fn always_included_files_survive_directory_pruning() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "src/main.rs",
+            "node_modules/pkg/index.js",
+            "node_modules/other/index.js",
+            ".github/workflows/ci.yml",
+            ".github/CODEOWNERS",
+            "target/debug/app",
+        ]);
+        let filter = Filter::builder()
+            .skip_hidden(true)
+            .skip_path_component("node_modules")
+            .skip_path_component("target")
+            .always_include("node_modules/pkg/index.js")
+            .always_include(".github/workflows/ci.yml")
+            .build()
+            .unwrap();
+        let (files, stats) = collect_files_with_stats(dir.path(), Arc::new(filter)).unwrap();
+        let mut rel: Vec<String> = files
+            .iter()
+            .map(|p| p.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect();
+        rel.sort();
+        assert_eq!(rel, vec![
+            ".github/workflows/ci.yml",
+            "node_modules/pkg/index.js",
+            "src/main.rs"
+        ]);
+        // Inside a kept directory, the rest is still left out: other
+        // subdirectories pruned (`node_modules/other`), files skipped one by one.
+        assert_eq!(stats.dirs_pruned[&SkipReason::PathComponent], 2);
+        assert_eq!(stats.files_skipped[&SkipReason::Hidden], 1);
+    }
+
+    #[test]
+    fn respect_gitignore_false_walks_gitignored_files() {
+        let dir = TempDir::new().unwrap();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(dir.path())
+            .output()
+            .ok();
+        make_files(&dir, &["src/main.rs", "ignored.log"]);
+        fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+        let filter = Filter::builder().respect_gitignore(false).build().unwrap();
+        let files = collect_files(dir.path(), Arc::new(filter)).unwrap();
+        assert!(filenames(&files).contains(&"ignored.log".to_string()));
+        assert!(
+            ignore_match(
+                &dir.path().join("ignored.log"),
+                &Filter::builder().respect_gitignore(false).build().unwrap()
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn ignore_match_names_the_gitignore_rule_and_line() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(
+            dir.path().join(".gitignore"),
+            "# build output\n*.log\ntarget/\n",
+        )
+        .unwrap();
+        make_files(&dir, &["target/debug/app", "src/main.rs"]);
+
+        let found = ignore_match(&dir.path().join("target/debug/app"), &bare_filter()).unwrap();
+        assert_eq!(found.pattern, "target/");
+        assert_eq!(found.file, Some(dir.path().join(".gitignore")));
+        assert_eq!(found.line, Some(3));
+        assert_eq!(found.reason, SkipReason::Gitignore);
+        assert!(ignore_match(&dir.path().join("src/main.rs"), &bare_filter()).is_none());
+    }
+
+    #[test]
+    fn ignore_match_honours_whitelist_rules() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "*.log\n!keep.log\n").unwrap();
+        make_files(&dir, &["keep.log", "debug.log"]);
+
+        assert!(ignore_match(&dir.path().join("keep.log"), &bare_filter()).is_none());
+        assert_eq!(
+            ignore_match(&dir.path().join("debug.log"), &bare_filter())
+                .unwrap()
+                .pattern,
+            "*.log"
+        );
+    }
+
+    #[test]
+    fn ignore_match_reports_dumpignore_unless_disabled() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join(DUMPIGNORE_FILENAME), "gen.rs\n").unwrap();
+        make_files(&dir, &["gen.rs"]);
+        let path = dir.path().join("gen.rs");
+
+        let found = ignore_match(&path, &bare_filter()).unwrap();
+        assert_eq!(found.file, Some(dir.path().join(DUMPIGNORE_FILENAME)));
+        assert_eq!(found.reason, SkipReason::Dumpignore);
+        assert!(
+            explain(&path, &bare_filter())
+                .to_string()
+                .starts_with("skipped by dumpignore: gen.rs in ")
+        );
+        let off = arc_filter(AppConfig {
+            respect_dumpignore: false,
+            ..AppConfig::default()
+        });
+        assert!(ignore_match(&path, &off).is_none());
+    }
+
+    #[test]
+    fn explain_falls_back_to_the_filter() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["Cargo.lock", "main.rs"]);
+        let filter = arc_filter(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            ..AppConfig::default()
+        });
+
+        assert_eq!(
+            explain(&dir.path().join("Cargo.lock"), &filter).to_string(),
+            "skipped by skip_extensions: lock"
+        );
+        assert_eq!(
+            explain(&dir.path().join("main.rs"), &filter),
+            FilterDecision::Included
+        );
+    }
+
+    #[test]
+    fn globs_and_regexes_are_anchored_to_the_walked_root() {
+        // The temp dir is not the cwd, so a root-relative glob only matches
+        // if the walker anchors the filter to the root.
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "src/main.rs",
+            "src/generated/api.rs",
+            "src/legacy/old.rs",
+            "docs/guide.md",
+        ]);
+        let filter = arc_filter(AppConfig {
+            skip_globs: vec!["src/generated/**".into()],
+            skip_patterns: vec!["^src/legacy/".into()],
+            include_globs: vec!["src/**".into()],
+            ..AppConfig::default()
+        });
+
+        let files = collect_files(dir.path(), filter).unwrap();
+        assert_eq!(relative(&dir, &files), vec!["src/main.rs"]);
+    }
+
+    #[test]
+    fn regexes_do_not_see_the_root_itself() {
+        let parent = TempDir::new().unwrap();
+        let root = parent.path().join("test-fixtures");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/lib_test.rs"), "").unwrap();
+        let filter = arc_filter(AppConfig {
+            skip_patterns: vec![r".*test.*\.rs$".into()],
+            ..AppConfig::default()
+        });
+
+        let files = collect_files(&root, filter).unwrap();
+        assert_eq!(filenames(&files), vec!["lib.rs"]);
+    }
+
+    #[test]
+    fn walk_files_yields_the_same_files_as_collect_files() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "a.rs",
+            "Cargo.lock",
+            "src/b.rs",
+            "src/deep/c.rs",
+            "docs/d.md",
+        ]);
+        let filter = arc_filter(AppConfig {
+            skip_extensions: vec!["lock".into()],
+            ..AppConfig::default()
+        });
+
+        let collected = collect_files(dir.path(), Arc::clone(&filter)).unwrap();
+        let walked: Vec<PathBuf> = walk_files(dir.path(), Arc::clone(&filter))
+            .collect::<DumpResult<_>>()
+            .unwrap();
+        assert_eq!(walked, collected);
+        assert_eq!(filenames(&walked), ["a.rs", "b.rs", "c.rs", "d.md"]);
+
+        let first = walk_files(dir.path(), filter).next().unwrap().unwrap();
+        assert_eq!(first, collected[0]);
+    }
+
+    #[cfg(unix)]
+    fn linked_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["real.rs", "lib/inner.rs"]);
+        std::os::unix::fs::symlink("real.rs", dir.path().join("alias.rs")).unwrap();
+        std::os::unix::fs::symlink("lib", dir.path().join("lib_link")).unwrap();
+        // A cycle: following it would recurse forever.
+        std::os::unix::fs::symlink("..", dir.path().join("lib/up")).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    fn with_symlinks(symlinks: Symlinks) -> Arc<Filter> {
+        Arc::new(Filter::builder().symlinks(symlinks).build().unwrap())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_skipped_by_default() {
+        let dir = linked_tree();
+        let (files, stats) = collect_files_with_stats(dir.path(), bare_filter()).unwrap();
+        assert_eq!(filenames(&files), ["inner.rs", "real.rs"]);
+        assert_eq!(stats.files_skipped[&SkipReason::Symlink], 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn listed_symlinks_are_yielded_without_descending() {
+        let dir = linked_tree();
+        let files = collect_files(dir.path(), with_symlinks(Symlinks::List)).unwrap();
+        assert_eq!(filenames(&files), [
+            "alias.rs", "inner.rs", "lib_link", "real.rs", "up"
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_symlinks_resolve_and_cycles_are_not_fatal() {
+        let dir = linked_tree();
+        let files = collect_files(dir.path(), with_symlinks(Symlinks::Follow)).unwrap();
+        assert_eq!(filenames(&files), [
+            "alias.rs", "inner.rs", "inner.rs", "real.rs"
+        ]);
+        assert!(files.contains(&dir.path().join("lib_link/inner.rs")));
+    }
+
+    #[test]
+    fn parallel_walk_matches_the_serial_order_and_filters() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &[
+            "a.rs",
+            "a/z.rs",
+            "a.b/c.rs",
+            "b/notes.md",
+            "b/deep/x.rs",
+            "b/deep/y.md",
+            "ignored.rs",
+            "node_modules/pkg/index.js",
+            "z.txt",
+        ]);
+        fs::write(dir.path().join(".ignore"), "ignored.rs\n").unwrap();
+        fs::write(
+            dir.path().join("b/dump.toml"),
+            "skip_extensions = [\"md\"]\n",
+        )
+        .unwrap();
+
+        let walk = |threads| {
+            let cfg = AppConfig {
+                nested_configs: true,
+                threads,
+                ..AppConfig::default()
+            };
+            collect_files(dir.path(), arc_filter(cfg)).unwrap()
+        };
+        let serial = walk(1);
+        assert!(serial.contains(&dir.path().join("b/deep/x.rs")));
+        for gone in [
+            "b/notes.md",
+            "b/deep/y.md",
+            "ignored.rs",
+            "node_modules/pkg/index.js",
+        ] {
+            assert!(!serial.contains(&dir.path().join(gone)), "{gone}");
+        }
+        assert_eq!(walk(4), serial);
+        assert_eq!(walk(0), serial);
+    }
+
+    #[test]
+    fn max_depth_stops_the_walk_but_keeps_a_file_root() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["top.rs", "a/mid.rs", "a/b/deep.rs"]);
+        let filter = |depth| Arc::new(Filter::builder().max_depth(depth).build().unwrap());
+
+        let files = collect_files(dir.path(), filter(1)).unwrap();
+        assert_eq!(filenames(&files), ["top.rs"]);
+        let files = collect_files(dir.path(), filter(2)).unwrap();
+        assert_eq!(filenames(&files), ["mid.rs", "top.rs"]);
+
+        let deep = dir.path().join("a/b/deep.rs");
+        assert_eq!(collect_files(&deep, filter(0)).unwrap(), [deep]);
+    }
+
+    #[test]
+    fn dedupe_files_keeps_the_first_root_to_reach_a_file() {
+        let dir = TempDir::new().unwrap();
+        make_files(&dir, &["a.rs", "sub/b.rs"]);
+        let mut seen = HashSet::new();
+
+        let mut whole = collect_files(dir.path(), bare_filter()).unwrap();
+        assert_eq!(dedupe_files(&mut whole, &mut seen, Symlinks::Skip), 0);
+        let mut sub = collect_files(&dir.path().join("sub"), bare_filter()).unwrap();
+        assert_eq!(dedupe_files(&mut sub, &mut seen, Symlinks::Skip), 1);
+        assert!(sub.is_empty());
+        assert_eq!(filenames(&whole), ["a.rs", "b.rs"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dedupe_files_resolves_followed_links_but_not_listed_ones() {
+        let dir = linked_tree();
+        let mut files = collect_files(dir.path(), with_symlinks(Symlinks::Follow)).unwrap();
+        dedupe_files(&mut files, &mut HashSet::new(), Symlinks::Follow);
+        assert_eq!(filenames(&files), ["alias.rs", "inner.rs"]);
+
+        let mut files = collect_files(dir.path(), with_symlinks(Symlinks::List)).unwrap();
+        assert_eq!(
+            dedupe_files(&mut files, &mut HashSet::new(), Symlinks::List),
+            0
+        );
+    }
 }
@@ -1 +1,161 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Order in which collected files are printed.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    /// Depth-first, sorted by name within each directory (the walk order).
+    #[default]
+    #[serde(alias = "name")]
+    #[value(alias = "name")]
+    Lexical,
+    /// Shallow files first: by number of path components, then by path.
+    Breadth,
+    /// Smallest files first, then by path.
+    Size,
+    /// Most recently modified files first, then by path.
+    Mtime,
+    /// By lowercase extension (files without one first), then by path.
+    #[serde(rename = "ext", alias = "extension")]
+    #[value(name = "ext", alias = "extension")]
+    Extension,
+}
+
+impl Order {
+    /// Whether files are sorted by their metadata, which the walk then reads
+    /// once per kept file.
+    pub fn needs_metadata(self) -> bool {
+        matches!(self, Self::Size | Self::Mtime)
+    }
+}
+
+/// What is written for each file.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// A header and the file's content.
+    #[default]
+    Text,
+    /// A `##` heading per file and its content in a fenced code block.
+    Markdown,
+    /// One JSON object per line: `{"path": ..., "content": ...}` per file, so
+    /// consumers can stream the dump.
+    Ndjson,
+    /// One `<sha256>  <size>  <path>` line per file, no content.
+    Manifest,
+    /// The files' contents back to back: no headers, separators or notices.
+    Concat,
+}
+
+/// How the end-of-run summary is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryFormat {
+    /// The human-readable `── Summary:` line.
+    #[default]
+    Text,
+    /// A compact single-line JSON object (see `summary::DumpSummary`).
+    Json,
+}
+
+/// What the walk does with symbolic links.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Symlinks {
+    /// Leave symlinks out of the dump.
+    #[default]
+    Skip,
+    /// Walk into linked directories and print linked files' targets; link
+    /// cycles are detected and reported, not followed.
+    Follow,
+    /// Print each symlink as a one-line `-> target` placeholder, without
+    /// reading the target.
+    List,
+}
+
+/// Which filter rule excluded a file or pruned a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// A path component is listed in `skip_path_components`.
+    PathComponent,
+    /// A hidden file or directory, with `skip_hidden` on.
+    Hidden,
+    /// The extension is listed in `skip_extensions`.
+    Extension,
+    /// The file name (or stem) is listed in `skip_filenames`.
+    Filename,
+    /// The file belongs to a language listed in `skip_languages`.
+    Language,
+    /// The path matches a `skip_patterns` regex.
+    Pattern,
+    /// The path matches a `skip_globs` glob.
+    Glob,
+    /// A symbolic link, with `symlinks = "skip"`.
+    Symlink,
+    /// The file is larger than `skip_larger_than`.
+    TooLarge,
+    /// The file was last modified outside `changed_since`..`changed_before`.
+    Modified,
+    /// The content looks binary, with `skip_binary` on.
+    Binary,
+    /// The sniffed MIME type is left out by `skip_mime_prefixes` or
+    /// `include_mime_prefixes`.
+    MimeType,
+    /// A generated file (by name or by a `generated_markers` entry), with
+    /// `skip_generated` on.
+    Generated,
+    /// A minified or bundled asset, with `skip_minified` on.
+    Minified,
+    /// The file has more lines than `max_lines_per_file`.
+    TooManyLines,
+    /// The content matches a `skip_content_patterns` regex.
+    Content,
+    /// Include rules are set and the file matches none of them.
+    NotIncluded,
+    /// Excluded by `.gitignore`, `.git/info/exclude` or the global git excludes.
+    Gitignore,
+    /// Excluded by a `.dumpignore` file.
+    Dumpignore,
+    /// Excluded by an `.ignore` file or one of `custom_ignore_files`.
+    IgnoreFile,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PathComponent => "component",
+            Self::Hidden => "hidden",
+            Self::Extension => "extension",
+            Self::Filename => "filename",
+            Self::Language => "language",
+            Self::Pattern => "regex",
+            Self::Glob => "glob",
+            Self::Symlink => "symlink",
+            Self::TooLarge => "size",
+            Self::Modified => "mtime",
+            Self::Binary => "binary",
+            Self::MimeType => "mime",
+            Self::Generated => "generated",
+            Self::Minified => "minified",
+            Self::TooManyLines => "lines",
+            Self::Content => "content",
+            Self::NotIncluded => "not included",
+            Self::Gitignore => "gitignore",
+            Self::Dumpignore => "dumpignore",
+            Self::IgnoreFile => "ignore file",
+        })
+    }
+}
+
 mod tests;
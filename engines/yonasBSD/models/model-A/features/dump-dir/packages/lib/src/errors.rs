@@ -29,12 +29,73 @@ pub enum DumpError {
     )]
     ConfigNotFound { path: String },
 
+    /// A project config (`dump.toml` or a `--config` file) set `filter_cmd`.
+    #[snafu(display("filter_cmd is not allowed in {path}"))]
+    #[diagnostic(
+        code(dump_dir::config::local_filter_cmd),
+        help(
+            "A project config could run any command. Set filter_cmd in \
+             ~/.config/dump-dir/config.toml, or pass --filter-cmd."
+        )
+    )]
+    LocalFilterCmd { path: String },
+
+    /// A size such as `skip_larger_than` could not be parsed.
+    #[snafu(display("Invalid size '{value}'"))]
+    #[diagnostic(
+        code(dump_dir::config::invalid_size),
+        help("Use a byte count with an optional unit, e.g. 4096, 500k, 2MiB or 1.5GB.")
+    )]
+    InvalidSize { value: String },
+
+    /// A `--lang`, `include_languages` or `skip_languages` entry names no known language.
+    #[snafu(display("Unknown language '{name}'"))]
+    #[diagnostic(
+        code(dump_dir::config::unknown_language),
+        help("Supported languages: {supported}")
+    )]
+    UnknownLanguage { name: String, supported: String },
+
+    /// A `--preset` or `presets` entry names no built-in preset.
+    #[snafu(display("Unknown preset '{name}'"))]
+    #[diagnostic(
+        code(dump_dir::config::unknown_preset),
+        help(
+            "Available presets: {available}. Run `dump-dir --list-presets` to see what each adds."
+        )
+    )]
+    UnknownPreset { name: String, available: String },
+
+    /// A `--skip-groups` or `skip_groups` entry names no built-in group.
+    #[snafu(display("Unknown skip group '{name}'"))]
+    #[diagnostic(
+        code(dump_dir::config::unknown_group),
+        help(
+            "Available groups: {available}. Run `dump-dir --list-groups` to see what each skips."
+        )
+    )]
+    UnknownGroup { name: String, available: String },
+
+    /// A `changed_since` or `changed_before` value is neither a duration nor a date.
+    #[snafu(display("Invalid time '{value}'"))]
+    #[diagnostic(
+        code(dump_dir::config::invalid_time),
+        help(
+            "Use a duration back from now (90s, 30m, 36h, 7d, 2w) or an RFC 3339 date \
+             (2024-03-05 or 2024-03-05T12:00:00Z)."
+        )
+    )]
+    InvalidTime { value: String },
+
     // ── Filter construction ───────────────────────────────────────────────
-    /// A regex pattern in skip_patterns failed to compile.
+    /// A regex pattern in skip_patterns or skip_content_patterns failed to compile.
     #[snafu(display("Invalid regex pattern '{pattern}': {source}"))]
     #[diagnostic(
         code(dump_dir::filter::invalid_regex),
-        help("Check your skip_patterns config. Patterns must be valid Rust regex syntax.")
+        help(
+            "Check your skip_patterns and skip_content_patterns config. Patterns must be valid \
+             Rust regex syntax."
+        )
     )]
     InvalidRegex {
         pattern: String,
@@ -77,6 +138,17 @@ pub enum DumpError {
         source: std::io::Error,
     },
 
+    /// A path could not be watched for changes (`--watch`).
+    #[snafu(display("Failed to watch '{path}': {source}"))]
+    #[diagnostic(
+        code(dump_dir::io::watch_failed),
+        help("On Linux, raising fs.inotify.max_user_watches may help for large trees.")
+    )]
+    Watch {
+        path: String,
+        source: std::io::Error,
+    },
+
     // ── Walker ────────────────────────────────────────────────────────────
     /// The ignore crate emitted a walk error for an entry.
     #[snafu(display("Walk error: {source}"))]
@@ -85,6 +157,121 @@ pub enum DumpError {
         help("A filesystem entry could not be accessed during directory traversal.")
     )]
     Walk { source: ignore::Error },
+
+    // ── Git ───────────────────────────────────────────────────────────────
+    /// A git-based selection (`--changed`, `--git-tracked`, …) was given for a
+    /// path outside any git repository.
+    #[snafu(display("Not inside a git repository: {path}"))]
+    #[diagnostic(
+        code(dump_dir::git::not_a_repository),
+        help("Git-based selections need a repository; drop the flag or dump a path inside one.")
+    )]
+    NotAGitRepository { path: String },
+
+    /// `git diff` for `--changed` or `--changed-between` failed (unknown ref, git
+    /// missing, …).
+    #[snafu(display("Failed to diff against '{rev}': {message}"))]
+    #[diagnostic(
+        code(dump_dir::git::diff_failed),
+        help("Check that the ref exists, e.g. with `git rev-parse --verify <REF>`.")
+    )]
+    GitDiff { rev: String, message: String },
+
+    /// `git ls-files` failed while selecting tracked or untracked files.
+    #[snafu(display("Failed to list git files: {message}"))]
+    #[diagnostic(code(dump_dir::git::ls_files_failed))]
+    GitLsFiles { message: String },
+
+    // ── Manifest ──────────────────────────────────────────────────────────
+    /// A manifest passed to `--verify` could not be parsed.
+    #[snafu(display("Invalid manifest '{path}' at line {line}: {message}"))]
+    #[diagnostic(
+        code(dump_dir::manifest::invalid),
+        help("Manifests are produced by `dump-dir --format manifest`.")
+    )]
+    InvalidManifest {
+        path: String,
+        line: usize,
+        message: String,
+    },
+
+    /// `--verify` found files that differ from the manifest.
+    #[snafu(display(
+        "Tree differs from manifest: {added} added, {removed} removed, {changed} changed"
+    ))]
+    #[diagnostic(code(dump_dir::manifest::mismatch))]
+    ManifestMismatch {
+        added: usize,
+        removed: usize,
+        changed: usize,
+    },
+
+    // ── Archive ───────────────────────────────────────────────────────────
+    /// A `.zip` or `.tar(.gz)` path argument could not be read as an archive.
+    #[snafu(display("Failed to read archive '{path}': {message}"))]
+    #[diagnostic(
+        code(dump_dir::archive::read_failed),
+        help("Check that the file is a complete zip, tar or gzipped tar archive.")
+    )]
+    Archive { path: String, message: String },
+
+    /// The entries an archive dump would keep add up to more than
+    /// [`MAX_ARCHIVE_BYTES`](crate::archive::MAX_ARCHIVE_BYTES) once decompressed.
+    #[snafu(display("Archive '{path}' inflates past {limit} bytes"))]
+    #[diagnostic(
+        code(dump_dir::archive::too_large),
+        help(
+            "Extract the files you need, or leave entries out with skip rules or skip_larger_than."
+        )
+    )]
+    ArchiveTooLarge { path: String, limit: u64 },
+
+    // ── Printer ───────────────────────────────────────────────────────────
+    /// Writing the dump to its output failed. A `BrokenPipe` source means the
+    /// reader went away (e.g. `| head`) and is not a real failure.
+    #[snafu(display("Failed to write output: {source}"))]
+    #[diagnostic(code(dump_dir::printer::write_failed))]
+    Write { source: std::io::Error },
+
+    /// The rendered dump could not be placed on the system clipboard.
+    #[snafu(display("Failed to copy to clipboard: {message}"))]
+    #[diagnostic(
+        code(dump_dir::printer::clipboard_failed),
+        help("A clipboard needs a graphical session (e.g. DISPLAY or WAYLAND_DISPLAY).")
+    )]
+    Clipboard { message: String },
+
+    /// `theme` names a theme bat doesn't have.
+    #[snafu(display("Unknown theme '{name}'. Valid themes: {available}"))]
+    #[diagnostic(
+        code(dump_dir::printer::unknown_theme),
+        help("Run `dump-dir --list-themes` to see every theme name.")
+    )]
+    UnknownTheme { name: String, available: String },
+
+    /// The output budget was reached and `--fail-on-budget` was requested.
+    #[snafu(display("Output budget reached: {omitted} file(s) omitted"))]
+    #[diagnostic(
+        code(dump_dir::printer::budget_exceeded),
+        help("Raise --max-total-lines / --max-total-bytes, or narrow the paths being dumped.")
+    )]
+    BudgetExceeded { omitted: usize },
+
+    /// `abort_after_bytes` was exhausted; the dump stopped mid-way.
+    #[snafu(display("Output size limit of {limit} bytes reached; dump aborted"))]
+    #[diagnostic(
+        code(dump_dir::printer::total_size_exceeded),
+        help("Raise --abort-after-bytes, or exclude large files (e.g. with --skip-extensions).")
+    )]
+    TotalSizeExceeded { limit: u64 },
+}
+
+impl DumpError {
+    /// True if output could not be written because the reading end of the pipe
+    /// was closed.
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Self::Write { source } if source.kind() == std::io::ErrorKind::BrokenPipe)
+    }
 }
 
 /// Convenience Result alias for the dump-dir library.
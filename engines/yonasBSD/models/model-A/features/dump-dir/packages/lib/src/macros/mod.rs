@@ -1 +1,12 @@
+/// Print a `Warning: ...` line to stderr unless warnings were silenced with
+/// [`utils::set_quiet`](crate::utils::set_quiet). Takes `format!` arguments.
+#[macro_export]
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        if !$crate::utils::is_quiet() {
+            eprintln!("Warning: {}", format_args!($($arg)*));
+        }
+    };
+}
+
 mod tests;
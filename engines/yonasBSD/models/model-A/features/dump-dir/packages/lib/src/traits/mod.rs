@@ -1 +1,75 @@
+use std::io::{self, Write};
+
+use colored::Colorize;
+
+use crate::{
+    enums::SummaryFormat,
+    filter::FilterStats,
+    render::{FileSection, TocEntry},
+    summary::DumpSummary,
+};
+
 mod tests;
+
+/// One output format. The [`Printer`](crate::printer::Printer) reads, budgets
+/// and dedupes the files; its renderer only decides how each piece looks. See
+/// [`crate::render`] for the built-in formats.
+pub trait Renderer {
+    /// One file: its header, then its content, a symlink target, or a note that
+    /// it repeats an earlier file.
+    fn file(&mut self, out: &mut dyn Write, file: &FileSection<'_>) -> io::Result<()>;
+
+    /// The totals of the run. `format` is what `--summary-format` asked for; a
+    /// renderer with a summary shape of its own may ignore it.
+    fn summary(
+        &self,
+        out: &mut dyn Write,
+        summary: &DumpSummary,
+        format: SummaryFormat,
+    ) -> io::Result<()> {
+        writeln!(out, "{}", summary.render(format))
+    }
+
+    /// Text outside any file section, e.g. a prompt preamble. A trailing newline
+    /// is added if missing so the next header starts cleanly.
+    fn verbatim(&mut self, out: &mut dyn Write, text: &str) -> io::Result<()> {
+        write!(out, "{text}")?;
+        if !text.is_empty() && !text.ends_with('\n') {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// A table of contents for `files`, every file about to be rendered, printed
+    /// as verbatim text before the first. `None` if the format has none.
+    fn toc(&mut self, _files: &[TocEntry<'_>]) -> Option<String> {
+        None
+    }
+
+    /// The `--show-skipped` report.
+    fn skipped(&self, out: &mut dyn Write, stats: &FilterStats) -> io::Result<()> {
+        writeln!(out, "{}", stats.to_string().dimmed())
+    }
+
+    /// The notice that the output budget left `omitted` files out, if the format
+    /// shows one outside its summary.
+    fn budget_notice(&self, out: &mut dyn Write, omitted: usize) -> io::Result<()> {
+        let notice = format!(
+            "── budget reached, {omitted} file{} omitted ──",
+            if omitted == 1 { "" } else { "s" }
+        );
+        writeln!(out, "{}", notice.yellow())
+    }
+
+    /// Whether [`FileSection::mime`] should be sniffed; off by default, as it may
+    /// take an extra read.
+    fn shows_mime(&self) -> bool {
+        false
+    }
+
+    /// Whether the renderer reads files itself (bat does), so reading them ahead
+    /// in parallel gains nothing.
+    fn reads_files(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+/// Detect Unicode text that isn't plain UTF-8: a UTF-8/UTF-16 byte order mark, or
+/// BOM-less UTF-16 recognised by its NUL-byte pattern. Returns the encoding and
+/// the length of the BOM to strip (0 when there is none).
+pub fn sniff_unicode(bytes: &[u8]) -> Option<(&'static Encoding, usize)> {
+    if let Some(found) = Encoding::for_bom(bytes) {
+        return Some(found);
+    }
+    sniff_bomless_utf16(bytes).map(|encoding| (encoding, 0))
+}
+
+/// Decode file bytes for display, never failing:
+///   1. BOM'd or BOM-less UTF-16 / UTF-8 with BOM → transcoded, BOM stripped
+///   2. Valid UTF-8 → borrowed as-is
+///   3. Anything else → encoding guessed by chardetng (e.g. Latin-1, Shift_JIS)
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    if let Some((encoding, bom_len)) = sniff_unicode(bytes) {
+        return encoding.decode_without_bom_handling(&bytes[bom_len..]).0;
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    encoding.decode_without_bom_handling(bytes).0
+}
+
+/// Mostly-ASCII UTF-16 without a BOM has a NUL in every other byte: the high byte
+/// of each code unit. Require at least a few code units and NULs in 90% of one
+/// parity with none in the other.
+fn sniff_bomless_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let units = bytes.len() / 2;
+    if units < 4 {
+        return None;
+    }
+
+    let (mut even_nuls, mut odd_nuls) = (0, 0);
+    for pair in bytes.chunks_exact(2) {
+        even_nuls += usize::from(pair[0] == 0);
+        odd_nuls += usize::from(pair[1] == 0);
+    }
+
+    let mostly = |n: usize| n * 10 >= units * 9;
+    if even_nuls == 0 && mostly(odd_nuls) {
+        Some(UTF_16LE)
+    } else if odd_nuls == 0 && mostly(even_nuls) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn detects_utf16le_bom() {
+        let (encoding, bom_len) = sniff_unicode(b"\xFF\xFEh\x00i\x00").unwrap();
+        assert_eq!(encoding, UTF_16LE);
+        assert_eq!(bom_len, 2);
+    }
+
+    #[test]
+    fn detects_bomless_utf16le() {
+        let (encoding, bom_len) = sniff_unicode(&utf16le("hello world")).unwrap();
+        assert_eq!(encoding, UTF_16LE);
+        assert_eq!(bom_len, 0);
+    }
+
+    #[test]
+    fn detects_bomless_utf16be() {
+        let bytes: Vec<u8> = "hello world"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(sniff_unicode(&bytes).unwrap().0, UTF_16BE);
+    }
+
+    #[test]
+    fn plain_utf8_is_not_sniffed() {
+        assert!(sniff_unicode(b"plain ascii text").is_none());
+    }
+
+    #[test]
+    fn scattered_nuls_are_not_utf16() {
+        assert!(sniff_unicode(b"\x7FELF\x02\x01\x01\x00\x00\x00\x00\x00").is_none());
+    }
+
+    #[test]
+    fn decode_strips_utf8_bom() {
+        assert_eq!(decode(b"\xEF\xBB\xBFhi"), "hi");
+    }
+
+    #[test]
+    fn decode_transcodes_utf16be_bom() {
+        assert_eq!(decode(b"\xFE\xFF\x00h\x00i"), "hi");
+    }
+
+    #[test]
+    fn decode_transcodes_bomless_utf16le() {
+        assert_eq!(decode(&utf16le("grüße aus köln")), "grüße aus köln");
+    }
+
+    #[test]
+    fn decode_passes_plain_utf8_through() {
+        assert!(matches!(decode(b"plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn decode_guesses_latin1() {
+        // "café crème" in ISO-8859-1 / windows-1252
+        assert_eq!(decode(b"caf\xE9 cr\xE8me"), "café crème");
+    }
+}
@@ -1 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub mod encoding;
+pub mod size;
+pub mod time;
+pub mod tokens;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Silence non-fatal warnings for the rest of the run (`--quiet`). Hard errors
+/// are returned as `DumpError` and are unaffected.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Force ANSI colors on or off for all output, overriding terminal detection.
+pub fn set_color(enabled: bool) {
+    colored::control::set_override(enabled);
+}
+
 mod tests;
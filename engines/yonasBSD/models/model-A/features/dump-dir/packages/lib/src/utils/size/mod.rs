@@ -0,0 +1,90 @@
+use crate::errors::{DumpError, DumpResult};
+
+/// Unit suffixes (compared lowercase) and their multipliers. `k`, `M` and `G`
+/// are decimal; the `i` forms are binary.
+const UNITS: &[(&str, u64)] = &[
+    ("", 1),
+    ("b", 1),
+    ("k", 1_000),
+    ("kb", 1_000),
+    ("kib", 1 << 10),
+    ("m", 1_000_000),
+    ("mb", 1_000_000),
+    ("mib", 1 << 20),
+    ("g", 1_000_000_000),
+    ("gb", 1_000_000_000),
+    ("gib", 1 << 30),
+];
+
+/// Parse a human-readable size such as `"500k"`, `"2MiB"` or `"1.5 GB"` into
+/// bytes. Units are case-insensitive; a bare number is a byte count.
+pub fn parse_size(text: &str) -> DumpResult<u64> {
+    let invalid = || DumpError::InvalidSize {
+        value: text.to_string(),
+    };
+    let trimmed = text.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let unit = unit.trim_start().to_lowercase();
+
+    let (_, multiplier) = UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .ok_or_else(invalid)?;
+    if !number.contains('.') {
+        return number
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(*multiplier))
+            .ok_or_else(invalid);
+    }
+    let bytes = number.parse::<f64>().map_err(|_| invalid())? * *multiplier as f64;
+    if bytes.is_finite() && bytes < u64::MAX as f64 {
+        Ok(bytes.round() as u64)
+    } else {
+        Err(invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_binary_units() {
+        assert_eq!(parse_size("1MiB").unwrap(), 1_048_576);
+        assert_eq!(parse_size("2kib").unwrap(), 2048);
+    }
+
+    #[test]
+    fn parses_decimal_units() {
+        assert_eq!(parse_size("500k").unwrap(), 500_000);
+        assert_eq!(parse_size("1.5 GB").unwrap(), 1_500_000_000);
+    }
+
+    #[test]
+    fn bare_number_is_bytes() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("10B").unwrap(), 10);
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        for bad in [
+            "",
+            "MiB",
+            "ten k",
+            "5 parsecs",
+            "1.2.3k",
+            "-1k",
+            "99999999999999999999G",
+        ] {
+            assert!(
+                matches!(parse_size(bad), Err(DumpError::InvalidSize { .. })),
+                "{bad}"
+            );
+        }
+    }
+}
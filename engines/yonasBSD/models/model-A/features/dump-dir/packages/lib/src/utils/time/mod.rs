@@ -0,0 +1,220 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::errors::{DumpError, DumpResult};
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// Parse a point in time: a duration back from `now` (`90s`, `30m`, `36h`,
+/// `7d`, `2w`) or an RFC 3339 date, either a bare `2024-03-05` (midnight UTC)
+/// or a full `2024-03-05T12:00:00+02:00`.
+pub fn parse_time(text: &str, now: SystemTime) -> DumpResult<SystemTime> {
+    let trimmed = text.trim();
+    parse_duration(trimmed)
+        .and_then(|ago| now.checked_sub(ago))
+        .or_else(|| parse_rfc3339(trimmed))
+        .ok_or_else(|| DumpError::InvalidTime {
+            value: text.to_string(),
+        })
+}
+
+/// `t` as an RFC 3339 UTC timestamp with whole seconds, e.g. `2024-03-05T12:00:00Z`.
+pub fn format_utc(t: SystemTime) -> String {
+    let secs = match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    };
+    let (days, rem) = (secs.div_euclid(DAY as i64), secs.rem_euclid(DAY as i64));
+    let (y, m, d) = civil_from_days(days);
+    format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+    let unit = text.chars().last()?;
+    let n: u64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => DAY,
+        'w' => 7 * DAY,
+        _ => return None,
+    };
+    n.checked_mul(secs).map(Duration::from_secs)
+}
+
+fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = text.get(range)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let sep = |i: usize, ok: &[u8]| text.as_bytes().get(i).is_some_and(|b| ok.contains(b));
+
+    if !(sep(4, b"-") && sep(7, b"-")) {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    let mut secs = days_from_civil(year, month, day) * DAY as i64;
+
+    if text.len() > 10 {
+        if !(sep(10, b"Tt ") && sep(13, b":") && sep(16, b":")) {
+            return None;
+        }
+        let (hour, min, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+        if hour > 23 || min > 59 || sec > 60 {
+            return None;
+        }
+        secs += hour * 3600 + min * 60 + sec;
+
+        let mut rest = &text[19..];
+        if let Some(frac) = rest.strip_prefix('.') {
+            let digits = frac.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return None;
+            }
+            rest = &frac[digits..];
+        }
+        secs -= match rest.as_bytes() {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let hh = num_pair(*h1, *h2)?;
+                let mm = num_pair(*m1, *m2)?;
+                if hh > 23 || mm > 59 {
+                    return None;
+                }
+                let offset = hh * 3600 + mm * 60;
+                if *sign == b'+' { offset } else { -offset }
+            },
+            _ => return None,
+        };
+    }
+
+    match u64::try_from(secs) {
+        Ok(secs) => UNIX_EPOCH.checked_add(Duration::from_secs(secs)),
+        Err(_) => UNIX_EPOCH.checked_sub(Duration::from_secs(secs.unsigned_abs())),
+    }
+}
+
+fn num_pair(a: u8, b: u8) -> Option<i64> {
+    (a.is_ascii_digit() && b.is_ascii_digit()).then(|| i64::from((a - b'0') * 10 + (b - b'0')))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn durations_count_back_from_now() {
+        let now = at(1_000_000);
+        assert_eq!(parse_time("7d", now).unwrap(), at(1_000_000 - 7 * DAY));
+        assert_eq!(parse_time("36h", now).unwrap(), at(1_000_000 - 36 * 3600));
+        assert_eq!(parse_time("90s", now).unwrap(), at(1_000_000 - 90));
+    }
+
+    #[test]
+    fn parses_rfc3339_dates() {
+        let now = SystemTime::now();
+        assert_eq!(parse_time("2024-03-05", now).unwrap(), at(1_709_596_800));
+        assert_eq!(
+            parse_time("2024-03-05T12:00:00Z", now).unwrap(),
+            at(1_709_640_000)
+        );
+        assert_eq!(
+            parse_time("2024-03-05T14:00:00.250+02:00", now).unwrap(),
+            at(1_709_640_000)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let now = SystemTime::now();
+        for bad in [
+            "",
+            "7",
+            "7y",
+            "d",
+            "2024-13-01",
+            "2023-02-29",
+            "2024-03-05T25:00:00Z",
+            "2024-03-05T12:00:00",
+            "2024-03-05 12:00",
+            "yesterday",
+        ] {
+            assert!(
+                matches!(parse_time(bad, now), Err(DumpError::InvalidTime { .. })),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn error_help_lists_accepted_formats() {
+        use miette::Diagnostic;
+
+        let err = parse_time("last week", SystemTime::now()).unwrap_err();
+        let help = err.help().unwrap().to_string();
+        assert!(help.contains("36h") && help.contains("2024-03-05T12:00:00Z"));
+    }
+
+    #[test]
+    fn formats_as_utc() {
+        assert_eq!(format_utc(at(1_709_640_000)), "2024-03-05T12:00:00Z");
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn civil_round_trips() {
+        for days in [-800_000, -1, 0, 59, 60, 19_787, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+}
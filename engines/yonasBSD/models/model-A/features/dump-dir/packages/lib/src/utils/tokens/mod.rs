@@ -0,0 +1,52 @@
+/// How [`estimate`] counts, for display next to the total.
+#[cfg(not(feature = "tiktoken"))]
+pub const METHOD: &str = "bytes/4 estimate";
+#[cfg(feature = "tiktoken")]
+pub const METHOD: &str = "cl100k_base";
+
+/// Approximate LLM token count of `text`: one token per four bytes, rounded up.
+/// Close enough for English prose and code to budget a context window.
+#[cfg(not(feature = "tiktoken"))]
+pub fn estimate(text: &str) -> u64 {
+    (text.len() as u64).div_ceil(4)
+}
+
+/// Token count of `text` under OpenAI's `cl100k_base` encoding.
+#[cfg(feature = "tiktoken")]
+pub fn estimate(text: &str) -> u64 {
+    use std::sync::OnceLock;
+
+    use tiktoken_rs::CoreBPE;
+
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    match BPE.get_or_init(|| tiktoken_rs::cl100k_base().ok()) {
+        Some(bpe) => bpe.encode_ordinary(text).len() as u64,
+        // The encoder ships inside the crate, so this is not expected to happen.
+        None => (text.len() as u64).div_ceil(4),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_text_has_no_tokens() {
+        assert_eq!(estimate(""), 0);
+    }
+
+    #[test]
+    fn estimate_grows_with_input() {
+        let short = estimate(&"let x = 1;\n".repeat(10));
+        let long = estimate(&"let x = 1;\n".repeat(1000));
+        assert!(short > 0);
+        assert!(long >= short * 90 && long <= short * 110);
+    }
+
+    #[cfg(not(feature = "tiktoken"))]
+    #[test]
+    fn heuristic_is_bytes_over_four_rounded_up() {
+        assert_eq!(estimate("abcd"), 1);
+        assert_eq!(estimate("abcde"), 2);
+    }
+}
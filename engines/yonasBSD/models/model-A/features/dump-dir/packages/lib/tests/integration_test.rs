@@ -40,6 +40,7 @@ fn no_filter_cfg() -> AppConfig {
         skip_globs: vec![],
         skip_binary: false,
         skip_hidden: false,
+        ..AppConfig::default()
     }
 }
 
@@ -136,6 +137,28 @@ fn regex_skips_test_files_keeps_normal() {
     assert!(names.contains(&"src/main.rs".to_string()));
 }
 
+// ── Include allowlist ──────────────────────────────────────────────────────
+
+#[test]
+fn include_extensions_drop_other_types_and_keep_skip_rules() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("src/main.rs", "fn main() {}"),
+        ("src/main_test.rs", "#[test] fn t() {}"),
+        ("src/LIB.RS", "pub fn lib() {}"),
+        ("Cargo.toml", "[package]"),
+    ]);
+    let cfg = AppConfig {
+        include_extensions: vec!["rs".into()],
+        skip_patterns: vec![r".*test.*\.rs$".into()],
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec![
+        "src/LIB.RS",
+        "src/main.rs"
+    ]);
+}
+
 // ── Default config smoke test ──────────────────────────────────────────────
 
 #[test]
@@ -177,3 +200,179 @@ fn no_filter_returns_everything() {
     let names = collected_names(&dir, no_filter_cfg());
     assert_eq!(names.len(), 4);
 }
+
+// ── .dumpignore ────────────────────────────────────────────────────────────
+
+#[test]
+fn nested_dumpignore_overrides_parent() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        (".dumpignore", "*.log\ngenerated/\n"),
+        ("app.log", "root log"),
+        ("generated/out.rs", "// generated"),
+        ("src/main.rs", "fn main() {}"),
+        ("src/.dumpignore", "!keep.log\nmain.rs\n"),
+        ("src/keep.log", "kept"),
+        ("src/drop.log", "dropped"),
+    ]);
+    let cfg = AppConfig {
+        skip_hidden: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec!["src/keep.log"]);
+}
+
+#[test]
+fn dumpignore_overrides_gitignore() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        (".gitignore", "*.txt\n"),
+        (".dumpignore", "!notes.txt\n"),
+        ("notes.txt", "notes"),
+        ("other.txt", "other"),
+    ]);
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    let cfg = AppConfig {
+        skip_hidden: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec!["notes.txt"]);
+}
+
+#[test]
+fn dot_ignore_file_excludes_paths() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        (".ignore", "scratch/\n"),
+        ("scratch/notes.txt", "notes"),
+        ("src/main.rs", "fn main() {}"),
+    ]);
+    let cfg = AppConfig {
+        skip_hidden: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec!["src/main.rs"]);
+}
+
+#[test]
+fn custom_ignore_files_are_honoured() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        (".aiignore", "*.secret\n"),
+        ("keys.secret", "hunter2"),
+        ("src/.aiignore", "gen.rs\n"),
+        ("src/gen.rs", ""),
+        ("src/main.rs", ""),
+    ]);
+    let cfg = AppConfig {
+        skip_hidden: true,
+        custom_ignore_files: vec![".aiignore".into()],
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg.clone()), vec!["src/main.rs"]);
+    let cfg = AppConfig {
+        custom_ignore_files: vec![],
+        ..cfg
+    };
+    assert_eq!(collected_names(&dir, cfg), vec![
+        "keys.secret",
+        "src/gen.rs",
+        "src/main.rs"
+    ]);
+}
+
+#[test]
+fn dumpignore_is_ignored_when_disabled() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[(".dumpignore", "*.log\n"), ("app.log", "log")]);
+    let cfg = AppConfig {
+        skip_hidden: true,
+        respect_dumpignore: false,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec!["app.log"]);
+}
+
+// ── Nested dump.toml ───────────────────────────────────────────────────────
+
+#[test]
+fn nested_config_applies_to_its_subtree_only() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("a.lock", ""),
+        ("a.log", ""),
+        ("web/dump.toml", "skip_extensions = [\"log\"]\n"),
+        ("web/b.lock", ""),
+        ("web/b.log", ""),
+        ("web/app/c.log", ""),
+        ("web/app/c.js", ""),
+        ("z/d.log", ""),
+    ]);
+    let cfg = AppConfig {
+        skip_extensions: vec!["lock".into()],
+        nested_configs: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec![
+        "a.log",
+        "web/app/c.js",
+        "web/dump.toml",
+        "z/d.log"
+    ]);
+}
+
+#[test]
+fn nested_configs_stack_and_override_scalars() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("svc/dump.toml", "skip_globs = [\"**/gen/**\"]\n"),
+        ("svc/gen/a.rs", ""),
+        (
+            "svc/inner/dump.toml",
+            "skip_hidden = true\nskip_filenames = [\"dump\"]\n",
+        ),
+        ("svc/inner/.env", ""),
+        ("svc/inner/gen/b.rs", ""),
+        ("svc/inner/main.rs", ""),
+        ("svc/.env", ""),
+    ]);
+    let cfg = AppConfig {
+        nested_configs: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec![
+        "svc/.env",
+        "svc/dump.toml",
+        "svc/inner/main.rs"
+    ]);
+}
+
+#[test]
+fn nested_configs_are_ignored_unless_enabled() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("web/dump.toml", "skip_extensions = [\"log\"]\n"),
+        ("web/b.log", ""),
+    ]);
+    assert_eq!(collected_names(&dir, no_filter_cfg()), vec![
+        "web/b.log",
+        "web/dump.toml"
+    ]);
+}
+
+#[test]
+fn invalid_nested_config_is_skipped() {
+    let dir = TempDir::new().unwrap();
+    make(&dir, &[
+        ("web/dump.toml", "skip_globs = [\"[broken\"]\n"),
+        ("web/b.log", ""),
+    ]);
+    let cfg = AppConfig {
+        nested_configs: true,
+        ..no_filter_cfg()
+    };
+    assert_eq!(collected_names(&dir, cfg), vec![
+        "web/b.log",
+        "web/dump.toml"
+    ]);
+}
@@ -9,7 +9,14 @@
 ///   INSTA_UPDATE=always cargo test
 use std::fs;
 
-use lib::{config::AppConfig, filter::Filter, walker::collect_files};
+use lib::{
+    config::{AppConfig, PrinterConfig},
+    enums::OutputFormat,
+    filter::Filter,
+    markdown::Slugger,
+    printer::Printer,
+    walker::collect_files,
+};
 use tempfile::TempDir;
 
 // ── helpers ────────────────────────────────────────────────────────────────
@@ -48,6 +55,7 @@ fn no_filter() -> AppConfig {
         skip_globs: vec![],
         skip_binary: false,
         skip_hidden: false,
+        ..AppConfig::default()
     }
 }
 
@@ -145,3 +153,39 @@ fn snap_filter_decisions_default_config() {
 
     insta::assert_yaml_snapshot!(decisions);
 }
+
+// ── Markdown anchor snapshots ──────────────────────────────────────────────
+
+/// Slugs handed out for paths that look alike. `src/main.rs` and
+/// `src/main_test.rs` must not collide; `src/main_rs` does and gets a suffix.
+#[test]
+fn snap_markdown_slugs() {
+    let mut slugger = Slugger::new();
+    let slugs: Vec<(&str, String)> = ["src/main.rs", "src/main_test.rs", "src/main_rs"]
+        .into_iter()
+        .map(|p| (p, slugger.slug(p)))
+        .collect();
+
+    assert_ne!(slugs[0].1, slugs[1].1);
+    insta::assert_yaml_snapshot!(slugs);
+}
+
+/// The rendered table of contents for the same paths.
+#[test]
+fn snap_markdown_toc() {
+    let cfg = PrinterConfig {
+        format: OutputFormat::Markdown,
+        toc: true,
+        ..PrinterConfig::default()
+    };
+    let paths: Vec<std::path::PathBuf> = ["src/main.rs", "src/main_test.rs", "src/main_rs"]
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    let mut printer = Printer::with_writer(&cfg, Vec::new());
+    printer.print_toc(&paths).unwrap();
+    let toc = String::from_utf8(printer.into_inner()).unwrap();
+
+    insta::assert_snapshot!(toc);
+}